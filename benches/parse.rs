@@ -0,0 +1,56 @@
+//! Parse throughput on generated documents.
+//!
+//! Run it with and without the `simd` feature to compare the structural index against the
+//! plain lexer:
+//!
+//! ```text
+//! cargo bench --bench parse
+//! cargo bench --bench parse --features simd
+//! ```
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crystalrake::json::JsonValue;
+
+/// Records of short strings, numbers and literals, as in a typical API response.
+fn records(n: usize) -> String {
+    let records: Vec<String> = (0..n)
+        .map(|i| {
+            format!(
+                r#"{{"id": {}, "name": "user{}", "tags": ["a", "b", "c"], "score": {}.5, "ok": {}, "nested": {{"x": [1, 2, 3], "y": null}}}}"#,
+                i,
+                i,
+                i,
+                i % 2 == 0
+            )
+        })
+        .collect();
+    format!("[{}]", records.join(", "))
+}
+
+/// Long strings of text, with an escape now and then.
+fn text(n: usize) -> String {
+    let paragraph = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.";
+    let strings: Vec<String> = (0..n)
+        .map(|i| if i % 10 == 0 { format!(r#""{}\n""#, paragraph) } else { format!(r#""{}""#, paragraph) })
+        .collect();
+    format!("[{}]", strings.join(",\n"))
+}
+
+fn bench(name: &str, source: &str) {
+    let mut runs = 0;
+    let start = Instant::now();
+    while runs < 3 || start.elapsed() < Duration::from_secs(2) {
+        black_box(source.parse::<JsonValue>().unwrap());
+        runs += 1;
+    }
+    let per_run = start.elapsed() / runs;
+    let throughput = source.len() as f64 / per_run.as_secs_f64() / 1e6;
+    println!("{:<10} {:>8.1} ms {:>8.1} MB/s", name, per_run.as_secs_f64() * 1e3, throughput);
+}
+
+fn main() {
+    bench("records", &records(50_000));
+    bench("text", &text(50_000));
+}
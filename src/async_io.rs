@@ -0,0 +1,88 @@
+//! Parsing JSON from a `futures` [`AsyncRead`] without blocking the executor.
+//!
+//! Both functions here feed what the reader returns into a [`PushParser`], so a read that
+//! ends in the middle of a token simply waits for the next one.
+
+use std::future::poll_fn;
+use std::io::ErrorKind;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+use futures_io::AsyncRead;
+
+use crate::error::ReadError;
+use crate::json::JsonValue;
+use crate::push::{Event, PushParser};
+
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// Reads a single JSON text from `reader` until the end of its input.
+pub async fn from_async_reader<R: AsyncRead + Unpin>(mut reader: R) -> Result<JsonValue, ReadError> {
+    let mut parser = PushParser::single();
+    let mut buf = vec![0; BUFFER_SIZE];
+    loop {
+        match poll_fn(|cx| Pin::new(&mut reader).poll_read(cx, &mut buf)).await {
+            Ok(0) => break,
+            Ok(n) => parser.feed(&buf[..n])?,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {},
+            Err(e) => return Err(e.into()),
+        }
+    }
+    parser.finish()?;
+    Ok(parser.next_value().expect("a finished parser has a complete value"))
+}
+
+/// A [`Stream`] of the [`Event`]s of the JSON read from an [`AsyncRead`].
+///
+/// Like [`PushParser`], it accepts several JSON texts separated by whitespace. Events read
+/// before an error are yielded first, then the error, and then the stream ends.
+pub struct EventStream<R> {
+    reader: R,
+    parser: PushParser,
+    buf: Box<[u8]>,
+    error: Option<ReadError>,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> EventStream<R> {
+    pub fn new(reader: R) -> EventStream<R> {
+        EventStream { reader, parser: PushParser::new(), buf: vec![0; BUFFER_SIZE].into(), error: None, done: false }
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for EventStream<R> {
+    type Item = Result<Event, ReadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(event) = this.parser.next_event() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            if let Some(e) = this.error.take() {
+                return Poll::Ready(Some(Err(e)));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+            let result = match ready!(Pin::new(&mut this.reader).poll_read(cx, &mut this.buf)) {
+                Ok(0) => {
+                    this.done = true;
+                    this.parser.finish()
+                },
+                Ok(n) => this.parser.feed(&this.buf[..n]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    this.done = true;
+                    this.error = Some(e.into());
+                    continue;
+                },
+            };
+            if let Err(e) = result {
+                this.done = true;
+                this.error = Some(e.into());
+            }
+        }
+    }
+}
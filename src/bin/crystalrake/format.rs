@@ -0,0 +1,53 @@
+//! Reformatting of valid JSON text token by token, so that numbers keep the digits they were
+//! written with instead of going through `f64`.
+
+use crystalrake::json::JsonValue;
+use crystalrake::tokenizer::{Token, TokenKind, Tokenizer};
+use crystalrake::writer;
+
+/// Rewrites `source`, which must be valid JSON, in the layout of the writer: compact without
+/// `indent`, otherwise with one member or element per line. Strings are written as the writer
+/// escapes them; numbers, `true`, `false` and `null` are copied from the source.
+pub fn reformat(source: &str, indent: Option<&str>) -> String {
+    let tokens: Vec<Token> = Tokenizer::new(source).filter(|token| !token.is_trivia()).collect();
+    let mut out = String::with_capacity(source.len());
+    let mut level = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::BeginObject | TokenKind::BeginArray => {
+                out.push_str(token.text);
+                level += 1;
+                if !matches!(tokens.get(i + 1).map(|next| next.kind), Some(TokenKind::EndObject | TokenKind::EndArray)) {
+                    newline(&mut out, indent, level);
+                }
+            },
+            TokenKind::EndObject | TokenKind::EndArray => {
+                level -= 1;
+                if !matches!(tokens[i - 1].kind, TokenKind::BeginObject | TokenKind::BeginArray) {
+                    newline(&mut out, indent, level);
+                }
+                out.push_str(token.text);
+            },
+            TokenKind::ValueSeparator => {
+                out.push(',');
+                newline(&mut out, indent, level);
+            },
+            TokenKind::NameSeparator => out.push_str(if indent.is_some() { ": " } else { ":" }),
+            TokenKind::String => {
+                let value: JsonValue = token.text.parse().expect("the source was parsed before");
+                out.push_str(&writer::to_string(&value));
+            },
+            _ => out.push_str(token.text),
+        }
+    }
+    out
+}
+
+fn newline(out: &mut String, indent: Option<&str>, level: usize) {
+    if let Some(indent) = indent {
+        out.push('\n');
+        for _ in 0..level {
+            out.push_str(indent);
+        }
+    }
+}
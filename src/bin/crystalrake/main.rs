@@ -0,0 +1,219 @@
+//! `crystalrake` command-line tool: formats, minifies, validates and queries JSON files.
+
+mod format;
+mod query;
+
+use std::ffi::OsString;
+use std::io::{IsTerminal, Read, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use crystalrake::json::JsonValue;
+use crystalrake::writer;
+
+use query::Query;
+
+const USAGE: &str = "\
+usage: crystalrake <command> [options] [file...]
+       crystalrake query [options] <expr> [file...]
+
+commands:
+  fmt       pretty-print JSON
+  minify    print JSON without whitespace
+  check     validate JSON and report errors with their positions
+  query     run a jq-style expression such as `.items[] | select(.price < 10) | {name}`
+
+options:
+  --indent <n>  indent `fmt` output with n spaces (default 2)
+  --tab         indent `fmt` output with tabs
+  -w, --write   rewrite the files in place instead of printing them
+  -c, --compact print `query` results without whitespace
+  -r, --raw     print `query` results that are strings without quotes
+  -h, --help    print this help
+
+Without files, or with `-`, JSON is read from standard input.
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Fmt,
+    Minify,
+    Check,
+    Query,
+}
+
+struct Options {
+    command: Command,
+    indent: String,
+    write: bool,
+    compact: bool,
+    raw: bool,
+    query: Option<Query>,
+    files: Vec<String>,
+}
+
+/// Parses the arguments after the program name; `None` if help was requested.
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>, String> {
+    let command = match args.next().as_deref() {
+        Some("fmt") => Command::Fmt,
+        Some("minify") => Command::Minify,
+        Some("check") => Command::Check,
+        Some("query") => Command::Query,
+        Some("-h") | Some("--help") | Some("help") => return Ok(None),
+        Some(other) => return Err(format!("unknown command '{}'", other)),
+        None => return Err("missing command".to_string()),
+    };
+    let mut options = Options {
+        command,
+        indent: "  ".to_string(),
+        write: false,
+        compact: false,
+        raw: false,
+        query: None,
+        files: Vec::new(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-w" | "--write" => options.write = true,
+            "-c" | "--compact" => options.compact = true,
+            "-r" | "--raw" => options.raw = true,
+            "--tab" => options.indent = "\t".to_string(),
+            "--indent" => {
+                let n = args.next().ok_or("--indent needs a number")?;
+                let n: usize = n.parse().map_err(|_| format!("invalid indent '{}'", n))?;
+                options.indent = " ".repeat(n);
+            },
+            "--" => {
+                options.files.extend(args.by_ref());
+            },
+            "-" => options.files.push(arg),
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            _ => options.files.push(arg),
+        }
+    }
+    if command == Command::Query {
+        if options.files.is_empty() {
+            return Err("missing query expression".to_string());
+        }
+        let query = Query::parse(&options.files.remove(0)).map_err(|e| format!("invalid query: {}", e))?;
+        options.query = Some(query);
+    }
+    if options.write {
+        if matches!(command, Command::Check | Command::Query) {
+            return Err("--write can only be used with fmt and minify".to_string());
+        }
+        if options.files.is_empty() || options.files.iter().any(|f| f == "-") {
+            return Err("--write needs files to rewrite".to_string());
+        }
+    }
+    if options.files.is_empty() {
+        options.files.push("-".to_string());
+    }
+    Ok(Some(options))
+}
+
+fn read(path: &str) -> std::io::Result<String> {
+    if path == "-" {
+        let mut buf = String::new();
+        std::io::stdin().read_to_string(&mut buf)?;
+        Ok(buf)
+    } else {
+        std::fs::read_to_string(path)
+    }
+}
+
+/// Replaces the contents of `path` without truncating it first: the text is written to a
+/// temporary file beside it, which is then renamed over it.
+fn replace(path: &Path, text: &str) -> std::io::Result<()> {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".crystalrake.tmp");
+    let temp = path.with_file_name(name);
+    let result = std::fs::write(&temp, text)
+        .and_then(|_| std::fs::set_permissions(&temp, std::fs::metadata(path)?.permissions()))
+        .and_then(|_| std::fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+fn output(options: &Options, source: &str, value: &JsonValue) -> Result<String, String> {
+    let compact = options.command == Command::Minify || options.compact;
+    let values = match &options.query {
+        Some(query) => query.run(value)?,
+        // Without a query the source itself is reformatted, so its numbers stay exact.
+        None if !(options.raw && value.is_string()) => {
+            let mut text = format::reformat(source, if compact { None } else { Some(&options.indent) });
+            text.push('\n');
+            return Ok(text);
+        },
+        None => vec![value.clone()],
+    };
+    let mut text = String::new();
+    for value in &values {
+        match value {
+            JsonValue::String(s) if options.raw => text.push_str(s),
+            _ if compact => text.push_str(&writer::to_string(value)),
+            _ => text.push_str(&writer::to_string_pretty(value, &options.indent)),
+        }
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        },
+        Err(message) => {
+            eprint!("crystalrake: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        },
+    };
+    let colored = std::io::stderr().is_terminal();
+    let mut stdout = std::io::stdout().lock();
+    // 1 if some input is not valid JSON, 2 if a file could not be read or written,
+    // 5 if a query failed on some input.
+    let mut status = 0;
+    for path in &options.files {
+        let name = if path == "-" { "<stdin>" } else { path.as_str() };
+        let source = match read(path) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("crystalrake: {}: {}", name, e);
+                status = status.max(2);
+                continue;
+            },
+        };
+        let value = match source.parse::<JsonValue>() {
+            Ok(value) => value,
+            Err(e) => {
+                eprint!("{}", e.render_file(&source, name, colored));
+                status = status.max(1);
+                continue;
+            },
+        };
+        if options.command == Command::Check {
+            continue;
+        }
+        let text = match output(&options, &source, &value) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("crystalrake: {}: {}", name, e);
+                status = status.max(5);
+                continue;
+            },
+        };
+        let written = if options.write { replace(Path::new(path), &text) } else { stdout.write_all(text.as_bytes()) };
+        if let Err(e) = written {
+            eprintln!("crystalrake: {}: {}", name, e);
+            status = status.max(2);
+        }
+    }
+    ExitCode::from(status)
+}
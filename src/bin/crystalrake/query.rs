@@ -0,0 +1,539 @@
+//! A small jq-like query language evaluated on `JsonValue` trees.
+//!
+//! Supported: `.`, `..`, `.name`, `."name"`, `.[index]`, `.[]`, pipes `|`, `,`, literals,
+//! `(...)`, array and object construction, comparisons, `and`, `or`, and the functions
+//! `map(f)`, `select(f)`, `keys`, `length` and `not`.
+
+use std::cmp::Ordering;
+
+use crystalrake::json::{JsonObject, JsonValue};
+
+#[derive(Debug, Clone)]
+enum Filter {
+    Identity,
+    Recurse,
+    Literal(JsonValue),
+    /// `.name` or `.[index]` applied to the output of the first filter.
+    Index(Box<Filter>, Box<Filter>),
+    /// `.[]` applied to the output of the filter.
+    Iterate(Box<Filter>),
+    Pipe(Box<Filter>, Box<Filter>),
+    Comma(Box<Filter>, Box<Filter>),
+    Array(Option<Box<Filter>>),
+    Object(Vec<(Filter, Filter)>),
+    Binary(Box<Filter>, BinaryOp, Box<Filter>),
+    Map(Box<Filter>),
+    Select(Box<Filter>),
+    Keys,
+    Length,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    DotDot,
+    /// `.name` or `."name"`.
+    Field(String),
+    Ident(String),
+    Literal(JsonValue),
+    Punct(&'static str),
+}
+
+/// A compiled query.
+#[derive(Debug, Clone)]
+pub struct Query {
+    filter: Filter,
+}
+
+impl Query {
+    pub fn parse(source: &str) -> Result<Query, String> {
+        let mut parser = Parser { tokens: lex(source)?, position: 0 };
+        let filter = parser.pipe()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(Query { filter }),
+            Some(token) => Err(format!("unexpected {}", describe(token))),
+        }
+    }
+
+    /// Runs the query on `input`, returning every output in order.
+    pub fn run(&self, input: &JsonValue) -> Result<Vec<JsonValue>, String> {
+        eval(&self.filter, input)
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Dot => "`.`".to_string(),
+        Token::DotDot => "`..`".to_string(),
+        Token::Field(name) => format!("`.{}`", name),
+        Token::Ident(name) => format!("`{}`", name),
+        Token::Literal(value) => format!("`{}`", value),
+        Token::Punct(p) => format!("`{}`", p),
+    }
+}
+
+const PUNCTS: [&str; 15] = ["==", "!=", "<=", ">=", "<", ">", "|", ",", ":", "(", ")", "[", "]", "{", "}"];
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Reads a JSON string literal at the start of `s`, returning it and its length in bytes.
+fn string_literal(s: &str) -> Result<(String, usize), String> {
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(1) {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => {
+                return match s[..=i].parse() {
+                    Ok(JsonValue::String(s)) => Ok((s, i + 1)),
+                    _ => Err(format!("invalid string {}", &s[..=i])),
+                };
+            },
+            _ => escaped = false,
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+fn lex(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if rest.starts_with("..") {
+            tokens.push(Token::DotDot);
+            2
+        } else if c == '.' {
+            let after = &rest[1..];
+            if after.starts_with('"') {
+                let (name, len) = string_literal(after)?;
+                tokens.push(Token::Field(name));
+                len + 1
+            } else if after.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                let len = after.find(|c| !is_ident(c)).unwrap_or(after.len());
+                tokens.push(Token::Field(after[..len].to_string()));
+                len + 1
+            } else {
+                tokens.push(Token::Dot);
+                1
+            }
+        } else if c == '"' {
+            let (s, len) = string_literal(rest)?;
+            tokens.push(Token::Literal(JsonValue::String(s)));
+            len
+        } else if c.is_ascii_digit() || (c == '-' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) {
+            let len = rest[1..].find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '+' || c == '-'))
+                .map_or(rest.len(), |i| i + 1);
+            let n = rest[..len].parse::<f64>().map_err(|_| format!("invalid number {}", &rest[..len]))?;
+            tokens.push(Token::Literal(JsonValue::Number(n)));
+            len
+        } else if is_ident(c) {
+            let len = rest.find(|c| !is_ident(c)).unwrap_or(rest.len());
+            tokens.push(match &rest[..len] {
+                "true" => Token::Literal(JsonValue::Boolean(true)),
+                "false" => Token::Literal(JsonValue::Boolean(false)),
+                "null" => Token::Literal(JsonValue::Null),
+                ident => Token::Ident(ident.to_string()),
+            });
+            len
+        } else {
+            match PUNCTS.iter().find(|p| rest.starts_with(*p)) {
+                Some(p) => {
+                    tokens.push(Token::Punct(p));
+                    p.len()
+                },
+                None => return Err(format!("unexpected character '{}'", c)),
+            }
+        };
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(p)) if *p == punct) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), String> {
+        if self.eat(punct) {
+            return Ok(());
+        }
+        match self.peek() {
+            Some(token) => Err(format!("expected `{}` but found {}", punct, describe(token))),
+            None => Err(format!("expected `{}` at the end of the query", punct)),
+        }
+    }
+
+    fn eat_ident(&mut self, ident: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(name)) if name == ident) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// `a | b`, the loosest binding operator.
+    fn pipe(&mut self) -> Result<Filter, String> {
+        let left = self.comma()?;
+        if self.eat("|") {
+            return Ok(Filter::Pipe(Box::new(left), Box::new(self.pipe()?)));
+        }
+        Ok(left)
+    }
+
+    fn comma(&mut self) -> Result<Filter, String> {
+        let mut left = self.or()?;
+        while self.eat(",") {
+            left = Filter::Comma(Box::new(left), Box::new(self.or()?));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Filter, String> {
+        let mut left = self.and()?;
+        while self.eat_ident("or") {
+            left = Filter::Binary(Box::new(left), BinaryOp::Or, Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut left = self.comparison()?;
+        while self.eat_ident("and") {
+            left = Filter::Binary(Box::new(left), BinaryOp::And, Box::new(self.comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Filter, String> {
+        let left = self.postfix()?;
+        let op = match self.peek() {
+            Some(Token::Punct("==")) => BinaryOp::Eq,
+            Some(Token::Punct("!=")) => BinaryOp::Ne,
+            Some(Token::Punct("<")) => BinaryOp::Lt,
+            Some(Token::Punct("<=")) => BinaryOp::Le,
+            Some(Token::Punct(">")) => BinaryOp::Gt,
+            Some(Token::Punct(">=")) => BinaryOp::Ge,
+            _ => return Ok(left),
+        };
+        self.position += 1;
+        Ok(Filter::Binary(Box::new(left), op, Box::new(self.postfix()?)))
+    }
+
+    /// A term followed by any number of `.name`, `[index]` and `[]` suffixes.
+    fn postfix(&mut self) -> Result<Filter, String> {
+        let mut filter = self.term()?;
+        loop {
+            match self.peek() {
+                Some(Token::Field(name)) => {
+                    let name = JsonValue::String(name.clone());
+                    self.position += 1;
+                    filter = Filter::Index(Box::new(filter), Box::new(Filter::Literal(name)));
+                },
+                Some(Token::Dot) if self.tokens.get(self.position + 1) == Some(&Token::Punct("[")) => {
+                    self.position += 1;
+                },
+                Some(Token::Punct("[")) => {
+                    self.position += 1;
+                    if self.eat("]") {
+                        filter = Filter::Iterate(Box::new(filter));
+                    } else {
+                        let index = self.pipe()?;
+                        self.expect("]")?;
+                        filter = Filter::Index(Box::new(filter), Box::new(index));
+                    }
+                },
+                _ => return Ok(filter),
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Filter, String> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return Err("unexpected end of the query".to_string()),
+        };
+        self.position += 1;
+        match token {
+            // `.[index]` is read as an `[index]` suffix of the identity.
+            Token::Dot => Ok(Filter::Identity),
+            Token::DotDot => Ok(Filter::Recurse),
+            Token::Field(name) => Ok(Filter::Index(Box::new(Filter::Identity), Box::new(Filter::Literal(JsonValue::String(name))))),
+            Token::Literal(value) => Ok(Filter::Literal(value)),
+            Token::Punct("(") => {
+                let filter = self.pipe()?;
+                self.expect(")")?;
+                Ok(filter)
+            },
+            Token::Punct("[") => {
+                if self.eat("]") {
+                    return Ok(Filter::Array(None));
+                }
+                let filter = self.pipe()?;
+                self.expect("]")?;
+                Ok(Filter::Array(Some(Box::new(filter))))
+            },
+            Token::Punct("{") => self.object(),
+            Token::Ident(name) => match name.as_str() {
+                "map" | "select" => {
+                    self.expect("(")?;
+                    let f = Box::new(self.pipe()?);
+                    self.expect(")")?;
+                    Ok(if name == "map" { Filter::Map(f) } else { Filter::Select(f) })
+                },
+                "keys" => Ok(Filter::Keys),
+                "length" => Ok(Filter::Length),
+                "not" => Ok(Filter::Not),
+                _ => Err(format!("unknown function `{}`", name)),
+            },
+            token => Err(format!("unexpected {}", describe(&token))),
+        }
+    }
+
+    /// Object construction after `{`: `{a, "b": .x, (.k): .v}`.
+    fn object(&mut self) -> Result<Filter, String> {
+        let mut entries = Vec::new();
+        if self.eat("}") {
+            return Ok(Filter::Object(entries));
+        }
+        loop {
+            let token = self.peek().cloned();
+            self.position += 1;
+            let (key, shorthand) = match token {
+                Some(Token::Ident(name)) => (Filter::Literal(JsonValue::String(name.clone())), Some(name)),
+                Some(Token::Literal(JsonValue::String(name))) => (Filter::Literal(JsonValue::String(name.clone())), Some(name)),
+                Some(Token::Punct("(")) => {
+                    let key = self.pipe()?;
+                    self.expect(")")?;
+                    (key, None)
+                },
+                Some(token) => return Err(format!("expected an object key but found {}", describe(&token))),
+                None => return Err("unexpected end of the query".to_string()),
+            };
+            let value = if self.eat(":") {
+                self.or()?
+            } else {
+                match shorthand {
+                    // `{a}` is short for `{a: .a}`.
+                    Some(name) => Filter::Index(Box::new(Filter::Identity), Box::new(Filter::Literal(JsonValue::String(name)))),
+                    None => return self.expect(":").map(|_| Filter::Identity),
+                }
+            };
+            entries.push((key, value));
+            if self.eat("}") {
+                return Ok(Filter::Object(entries));
+            }
+            self.expect(",")?;
+        }
+    }
+}
+
+fn truthy(value: &JsonValue) -> bool {
+    !matches!(value, JsonValue::Null | JsonValue::Boolean(false))
+}
+
+/// Orders values like jq: null < false < true < numbers < strings < arrays < objects.
+fn compare(a: &JsonValue, b: &JsonValue) -> Ordering {
+    fn rank(value: &JsonValue) -> u8 {
+        match value {
+            JsonValue::Null => 0,
+            JsonValue::Boolean(false) => 1,
+            JsonValue::Boolean(true) => 2,
+            JsonValue::String(_) => 4,
+            JsonValue::Array(_) => 5,
+            JsonValue::Objects(_) => 6,
+            _ => 3,
+        }
+    }
+    match (a, b) {
+        _ if a.is_number() && b.is_number() => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+        (JsonValue::String(a), JsonValue::String(b)) => a.cmp(b),
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            a.iter().zip(b).map(|(a, b)| compare(a, b)).find(|o| o.is_ne()).unwrap_or(a.len().cmp(&b.len()))
+        },
+        (JsonValue::Objects(_), JsonValue::Objects(_)) => {
+            let (a_keys, b_keys) = (sorted_keys(a), sorted_keys(b));
+            a_keys.cmp(&b_keys).then_with(|| {
+                a_keys.iter()
+                    .map(|k| compare(a.get(k).unwrap(), b.get(k).unwrap()))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        },
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn sorted_keys(value: &JsonValue) -> Vec<String> {
+    let mut keys: Vec<String> = value.as_objects().map_or(Vec::new(), |o| o.iter().map(|o| o.name.clone()).collect());
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+fn index(value: &JsonValue, index: &JsonValue) -> Result<JsonValue, String> {
+    match (value, index) {
+        (JsonValue::Null, _) if index.is_string() || index.is_number() => Ok(JsonValue::Null),
+        (JsonValue::Objects(_), JsonValue::String(name)) => Ok(value.get(name).cloned().unwrap_or(JsonValue::Null)),
+        (JsonValue::Array(values), _) if index.is_number() => {
+            let i = index.as_f64().unwrap().floor();
+            let i = if i < 0.0 { values.len() as f64 + i } else { i };
+            Ok(if i >= 0.0 { values.get(i as usize).cloned().unwrap_or(JsonValue::Null) } else { JsonValue::Null })
+        },
+        _ => Err(format!("cannot index {} with {}", value.type_name(), index)),
+    }
+}
+
+fn recurse(value: &JsonValue, out: &mut Vec<JsonValue>) {
+    out.push(value.clone());
+    match value {
+        JsonValue::Array(values) => values.iter().for_each(|v| recurse(v, out)),
+        JsonValue::Objects(objects) => objects.iter().for_each(|o| recurse(&o.value, out)),
+        _ => {},
+    }
+}
+
+fn eval(filter: &Filter, input: &JsonValue) -> Result<Vec<JsonValue>, String> {
+    Ok(match filter {
+        Filter::Identity => vec![input.clone()],
+        Filter::Recurse => {
+            let mut out = Vec::new();
+            recurse(input, &mut out);
+            out
+        },
+        Filter::Literal(value) => vec![value.clone()],
+        Filter::Index(base, i) => {
+            let mut out = Vec::new();
+            for value in eval(base, input)? {
+                for i in eval(i, input)? {
+                    out.push(index(&value, &i)?);
+                }
+            }
+            out
+        },
+        Filter::Iterate(base) => {
+            let mut out = Vec::new();
+            for value in eval(base, input)? {
+                match value {
+                    JsonValue::Array(values) => out.extend(values),
+                    JsonValue::Objects(objects) => out.extend(objects.into_iter().map(|o| o.value)),
+                    value => return Err(format!("cannot iterate over {}", value.type_name())),
+                }
+            }
+            out
+        },
+        Filter::Pipe(left, right) => {
+            let mut out = Vec::new();
+            for value in eval(left, input)? {
+                out.extend(eval(right, &value)?);
+            }
+            out
+        },
+        Filter::Comma(left, right) => {
+            let mut out = eval(left, input)?;
+            out.extend(eval(right, input)?);
+            out
+        },
+        Filter::Array(None) => vec![JsonValue::Array(Vec::new())],
+        Filter::Array(Some(f)) => vec![JsonValue::Array(eval(f, input)?)],
+        Filter::Object(entries) => {
+            // Every combination of the outputs of the keys and values.
+            let mut objects = vec![Vec::new()];
+            for (key, value) in entries {
+                let mut next = Vec::new();
+                for object in &objects {
+                    for name in eval(key, input)? {
+                        let name = match name {
+                            JsonValue::String(name) => name,
+                            name => return Err(format!("object keys must be strings, not {}", name.type_name())),
+                        };
+                        for value in eval(value, input)? {
+                            let mut object: Vec<JsonObject> = object.clone();
+                            object.retain(|o| o.name != name);
+                            object.push(JsonObject { name: name.clone(), value });
+                            next.push(object);
+                        }
+                    }
+                }
+                objects = next;
+            }
+            objects.into_iter().map(JsonValue::Objects).collect()
+        },
+        Filter::Binary(left, op, right) => {
+            let mut out = Vec::new();
+            for l in eval(left, input)? {
+                if *op == BinaryOp::And && !truthy(&l) || *op == BinaryOp::Or && truthy(&l) {
+                    out.push(JsonValue::Boolean(*op == BinaryOp::Or));
+                    continue;
+                }
+                for r in eval(right, input)? {
+                    let ordering = compare(&l, &r);
+                    out.push(JsonValue::Boolean(match op {
+                        BinaryOp::Eq => l.equivalent(&r),
+                        BinaryOp::Ne => !l.equivalent(&r),
+                        BinaryOp::Lt => ordering.is_lt(),
+                        BinaryOp::Le => ordering.is_le(),
+                        BinaryOp::Gt => ordering.is_gt(),
+                        BinaryOp::Ge => ordering.is_ge(),
+                        BinaryOp::And | BinaryOp::Or => truthy(&r),
+                    }));
+                }
+            }
+            out
+        },
+        Filter::Map(f) => {
+            let mut out = Vec::new();
+            for value in eval(&Filter::Iterate(Box::new(Filter::Identity)), input)? {
+                out.extend(eval(f, &value)?);
+            }
+            vec![JsonValue::Array(out)]
+        },
+        Filter::Select(f) => {
+            let conditions = eval(f, input)?;
+            conditions.iter().filter(|c| truthy(c)).map(|_| input.clone()).collect()
+        },
+        Filter::Keys => match input {
+            JsonValue::Objects(_) => vec![JsonValue::Array(sorted_keys(input).into_iter().map(JsonValue::String).collect())],
+            JsonValue::Array(values) => vec![JsonValue::Array((0..values.len()).map(|i| JsonValue::Number(i as f64)).collect())],
+            value => return Err(format!("{} has no keys", value.type_name())),
+        },
+        Filter::Length => vec![JsonValue::Number(match input {
+            JsonValue::Null => 0.0,
+            JsonValue::Boolean(_) => return Err("boolean has no length".to_string()),
+            JsonValue::String(s) => s.chars().count() as f64,
+            JsonValue::Array(values) => values.len() as f64,
+            JsonValue::Objects(objects) => objects.len() as f64,
+            number => number.as_f64().unwrap().abs(),
+        })],
+        Filter::Not => vec![JsonValue::Boolean(!truthy(input))],
+    })
+}
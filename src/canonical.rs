@@ -0,0 +1,79 @@
+//! Canonical JSON ([RFC 8785](https://www.rfc-editor.org/rfc/rfc8785) JCS) serialization.
+//!
+//! The canonical form has no whitespace, object members sorted by the UTF-16 code units
+//! of their names, ECMAScript number formatting and minimal string escaping, so equal
+//! values always serialize to the same bytes.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::error::{CanonicalError, CanonicalErrorKind};
+use crate::json::{JsonObject, JsonValue};
+use crate::pointer;
+use crate::writer;
+
+/// Serializes `value` in canonical form.
+///
+/// Fails on NaN or infinite numbers and on objects with duplicate member names.
+pub fn to_string(value: &JsonValue) -> Result<String, CanonicalError> {
+    let mut buf = String::new();
+    write_value(&mut buf, value, &mut String::new())?;
+    Ok(buf)
+}
+
+fn write_value(buf: &mut String, value: &JsonValue, path: &mut String) -> Result<(), CanonicalError> {
+    match value {
+        JsonValue::Number(n) => write_number(buf, *n, path)?,
+        // The canonical form of a number is that of the nearest double.
+        #[cfg(feature = "arbitrary_precision")]
+        JsonValue::Decimal(d) => write_number(buf, d.to_f64(), path)?,
+        JsonValue::Array(values) => {
+            buf.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    buf.push(',');
+                }
+                let len = path.len();
+                path.push_str(&format!("/{}", i));
+                write_value(buf, value, path)?;
+                path.truncate(len);
+            }
+            buf.push(']');
+        },
+        JsonValue::Objects(objects) => {
+            let mut members: Vec<&JsonObject> = objects.iter().collect();
+            members.sort_by(|a, b| a.name.encode_utf16().cmp(b.name.encode_utf16()));
+            if let Some(pair) = members.windows(2).find(|pair| pair[0].name == pair[1].name) {
+                return Err(CanonicalError {
+                    kind: CanonicalErrorKind::DuplicateKey(pair[0].name.clone()),
+                    pointer: path.clone(),
+                });
+            }
+            buf.push('{');
+            for (i, member) in members.iter().enumerate() {
+                if i > 0 {
+                    buf.push(',');
+                }
+                writer::write_string(buf, &member.name).unwrap();
+                buf.push(':');
+                let len = path.len();
+                path.push('/');
+                path.push_str(&pointer::escape(&member.name));
+                write_value(buf, &member.value, path)?;
+                path.truncate(len);
+            }
+            buf.push('}');
+        },
+        _ => writer::write_value(buf, value, &writer::WriteOptions::default()).unwrap(),
+    }
+    Ok(())
+}
+
+fn write_number(buf: &mut String, n: f64, path: &str) -> Result<(), CanonicalError> {
+    if !n.is_finite() {
+        return Err(CanonicalError { kind: CanonicalErrorKind::NonFiniteNumber, pointer: path.to_string() });
+    }
+    writer::write_es_number(buf, n).unwrap();
+    Ok(())
+}
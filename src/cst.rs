@@ -0,0 +1,315 @@
+//! A lossless syntax tree for editing JSON text without reformatting it.
+//!
+//! A [`SyntaxTree`] keeps the source exactly as written, including whitespace and, in JSONC
+//! mode, comments. Edits are addressed by JSON Pointer and only rewrite the text of the
+//! affected value, so the rest of the document keeps its layout.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::{EditError, EditErrorKind, JsonParseError};
+use crate::json::JsonValue;
+use crate::lexer::JsonLexer;
+use crate::parser::JsonParser;
+use crate::pointer;
+use crate::span::Span;
+use crate::tokenizer::{Token, TokenKind, Tokenizer};
+use crate::writer;
+
+#[derive(Debug, Clone)]
+pub struct SyntaxTree {
+    source: String,
+    jsonc: bool,
+    value: JsonValue,
+    root: Node,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Object { span: Span, entries: Vec<Entry> },
+    Array { span: Span, entries: Vec<Entry> },
+    Scalar(Span),
+}
+
+/// A member of an object or an element of an array.
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Member name and the span of its string token; `None` for array elements.
+    key: Option<(String, Span)>,
+    value: Node,
+    /// The comma following the entry.
+    comma: Option<Span>,
+}
+
+impl Node {
+    fn span(&self) -> Span {
+        match self {
+            Node::Object { span, .. } | Node::Array { span, .. } | Node::Scalar(span) => *span,
+        }
+    }
+
+    fn entries(&self) -> Option<&[Entry]> {
+        match self {
+            Node::Object { entries, .. } | Node::Array { entries, .. } => Some(entries),
+            Node::Scalar(_) => None,
+        }
+    }
+
+    /// Index of the entry named by a reference token.
+    fn position(&self, token: &str) -> Option<usize> {
+        match self {
+            Node::Object { entries, .. } => entries.iter().position(|e| matches!(&e.key, Some((name, _)) if name == token)),
+            Node::Array { entries, .. } => pointer::parse_index(token).filter(|i| *i < entries.len()),
+            Node::Scalar(_) => None,
+        }
+    }
+}
+
+impl Entry {
+    fn span(&self) -> Span {
+        let start = self.key.as_ref().map_or(self.value.span().start, |(_, span)| span.start);
+        Span::new(start, self.value.span().end)
+    }
+}
+
+impl SyntaxTree {
+    /// Parses a JSON document.
+    pub fn parse(source: &str) -> Result<SyntaxTree, JsonParseError> {
+        SyntaxTree::build(source.to_string(), false)
+    }
+
+    /// Parses a JSONC document, which may contain `//` and `/* */` comments.
+    pub fn parse_jsonc(source: &str) -> Result<SyntaxTree, JsonParseError> {
+        SyntaxTree::build(source.to_string(), true)
+    }
+
+    fn build(source: String, jsonc: bool) -> Result<SyntaxTree, JsonParseError> {
+        let mut lexer = if jsonc { JsonLexer::with_comments(&source) } else { JsonLexer::new(&source) };
+        let value = JsonParser::new(lexer.tokenize()?, source.len()).get_value()?;
+        let root = {
+            let tokenizer = if jsonc { Tokenizer::jsonc(&source) } else { Tokenizer::new(&source) };
+            let tokens: Vec<Token> = tokenizer.filter(|t| !t.is_trivia()).collect();
+            read_node(&tokens, &mut 0)
+        };
+        Ok(SyntaxTree { source, jsonc, value, root })
+    }
+
+    /// The current text of the document.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The current value of the document.
+    pub fn value(&self) -> &JsonValue {
+        &self.value
+    }
+
+    /// Span of the value at `pointer`.
+    pub fn span(&self, pointer: &str) -> Option<Span> {
+        let tokens = pointer::parse(pointer).ok()?;
+        Some(self.find(&tokens)?.span())
+    }
+
+    /// The value at `pointer` exactly as written in the source.
+    pub fn raw(&self, pointer: &str) -> Option<&str> {
+        let span = self.span(pointer)?;
+        Some(&self.source[span.start..span.end])
+    }
+
+    fn find<S: AsRef<str>>(&self, tokens: &[S]) -> Option<&Node> {
+        let mut node = &self.root;
+        for token in tokens {
+            let i = node.position(token.as_ref())?;
+            node = &node.entries()?[i].value;
+        }
+        Some(node)
+    }
+
+    /// Sets the value at `pointer`.
+    ///
+    /// An existing value is replaced. A missing object member is added, and `-` or the length
+    /// of an array appends an element.
+    pub fn set(&mut self, pointer: &str, value: &JsonValue) -> Result<(), EditError> {
+        let error = |kind| EditError { kind, pointer: pointer.to_string() };
+        let tokens = pointer::parse(pointer).map_err(|_| error(EditErrorKind::InvalidPointer))?;
+        let text = writer::to_string(value);
+        let (last, parent) = match tokens.split_last() {
+            Some(split) => split,
+            None => {
+                self.splice(self.root.span(), &text);
+                return Ok(());
+            },
+        };
+        let parent = self.find(parent).ok_or_else(|| error(EditErrorKind::NotFound))?;
+        let (span, text) = match (parent, parent.position(last)) {
+            (Node::Scalar(_), _) => return Err(error(EditErrorKind::NotAContainer)),
+            (_, Some(i)) => (parent.entries().unwrap()[i].value.span(), text),
+            (Node::Object { .. }, None) => self.append(parent, Some(last), &text),
+            (Node::Array { entries, .. }, None) if last == "-" || pointer::parse_index(last) == Some(entries.len()) => {
+                self.append(parent, None, &text)
+            },
+            (Node::Array { .. }, None) => return Err(error(EditErrorKind::NotFound)),
+        };
+        self.splice(span, &text);
+        Ok(())
+    }
+
+    /// Adds the member `key` to the object at `pointer`, after its last member.
+    ///
+    /// The new member copies the indentation and colon spacing of the existing members.
+    pub fn insert(&mut self, pointer: &str, key: &str, value: &JsonValue) -> Result<(), EditError> {
+        let error = |kind| EditError { kind, pointer: pointer.to_string() };
+        let tokens = pointer::parse(pointer).map_err(|_| error(EditErrorKind::InvalidPointer))?;
+        let object = self.find(&tokens).ok_or_else(|| error(EditErrorKind::NotFound))?;
+        if !matches!(object, Node::Object { .. }) {
+            return Err(error(EditErrorKind::NotAContainer));
+        }
+        if object.position(key).is_some() {
+            return Err(error(EditErrorKind::KeyExists(key.to_string())));
+        }
+        let (span, text) = self.append(object, Some(key), &writer::to_string(value));
+        self.splice(span, &text);
+        Ok(())
+    }
+
+    /// Removes the member or element at `pointer` together with its comma.
+    ///
+    /// Comments between the removed entry and the next one are removed as well. When the last
+    /// entry is removed, only the comma before it goes, so comments after that comma stay with
+    /// the entry they follow.
+    pub fn remove(&mut self, pointer: &str) -> Result<(), EditError> {
+        let error = |kind| EditError { kind, pointer: pointer.to_string() };
+        let tokens = pointer::parse(pointer).map_err(|_| error(EditErrorKind::InvalidPointer))?;
+        let (last, parent) = tokens.split_last().ok_or_else(|| error(EditErrorKind::InvalidPointer))?;
+        let parent = self.find(parent).ok_or_else(|| error(EditErrorKind::NotFound))?;
+        let entries = parent.entries().ok_or_else(|| error(EditErrorKind::NotAContainer))?;
+        let i = parent.position(last).ok_or_else(|| error(EditErrorKind::NotFound))?;
+        let entry = &entries[i];
+        let end = self.comment_end(entry.span().end);
+        let spans = match (entry.comma, entries.get(i + 1), i.checked_sub(1).and_then(|p| entries[p].comma)) {
+            (Some(_), Some(next), _) => vec![Span::new(entry.span().start, next.span().start)],
+            (_, _, Some(comma)) if self.source[comma.end..entry.span().start].contains('\n') => {
+                // Take the entry's line with it, but not what follows the comma on the line before.
+                let before = self.source[..entry.span().start].trim_end_matches([' ', '\t']);
+                let start = match before.strip_suffix('\n') {
+                    Some(line) => line.strip_suffix('\r').unwrap_or(line).len(),
+                    None => before.len(),
+                };
+                vec![comma, Span::new(start, end)]
+            },
+            (_, _, Some(comma)) => vec![Span::new(comma.start, end)],
+            _ => vec![Span::new(entry.span().start, end)],
+        };
+        let mut source = core::mem::take(&mut self.source);
+        for span in spans.iter().rev() {
+            source.replace_range(span.start..span.end, "");
+        }
+        *self = SyntaxTree::build(source, self.jsonc).expect("edits keep the document valid");
+        Ok(())
+    }
+
+    /// Extends the end of an entry over a comment that follows it on the same line.
+    fn comment_end(&self, end: usize) -> usize {
+        if !self.jsonc {
+            return end;
+        }
+        let rest = &self.source[end..];
+        let comment = rest.trim_start_matches([' ', '\t']);
+        let start = end + rest.len() - comment.len();
+        if comment.starts_with("//") {
+            start + comment.find(['\n', '\r']).unwrap_or(comment.len())
+        } else if comment.starts_with("/*") {
+            comment.find("*/").map_or(end, |i| start + i + 2)
+        } else {
+            end
+        }
+    }
+
+    /// Returns the edit that adds an entry after the last entry of `container`.
+    fn append(&self, container: &Node, key: Option<&str>, value: &str) -> (Span, String) {
+        let entries = container.entries().unwrap_or_default();
+        let span = container.span();
+        let mut entry = String::new();
+        if let Some(key) = key {
+            writer::write_string(&mut entry, key).unwrap();
+            let colon = entries.last()
+                .and_then(|e| e.key.as_ref().map(|(_, key)| &self.source[key.end..e.value.span().start]))
+                .filter(|gap| gap.chars().all(|c| c == ':' || c.is_ascii_whitespace()))
+                .unwrap_or(": ");
+            entry.push_str(colon);
+        }
+        entry.push_str(value);
+        match entries.last() {
+            None => {
+                let (open, close) = if key.is_some() { ('{', '}') } else { ('[', ']') };
+                (span, format!("{}{}{}", open, entry, close))
+            },
+            Some(last) => {
+                // Indent like the last entry: the whitespace between it and what precedes it.
+                let start = match entries.len() {
+                    1 => span.start + 1,
+                    n => entries[n - 2].comma.map_or(span.start + 1, |c| c.end),
+                };
+                let gap = &self.source[start..last.span().start];
+                let indent = &gap[gap.trim_end_matches(|c: char| c.is_ascii_whitespace()).len()..];
+                let end = last.span().end;
+                (Span::new(end, end), format!(",{}{}", indent, entry))
+            },
+        }
+    }
+
+    /// Replaces the text at `span` and re-reads the document.
+    fn splice(&mut self, span: Span, text: &str) {
+        let mut source = core::mem::take(&mut self.source);
+        source.replace_range(span.start..span.end, text);
+        *self = SyntaxTree::build(source, self.jsonc).expect("edits keep the document valid");
+    }
+}
+
+impl core::fmt::Display for SyntaxTree {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Reads a value from the significant tokens of a valid document.
+fn read_node(tokens: &[Token], position: &mut usize) -> Node {
+    let open = tokens[*position];
+    *position += 1;
+    let close = match open.kind {
+        TokenKind::BeginObject => TokenKind::EndObject,
+        TokenKind::BeginArray => TokenKind::EndArray,
+        _ => return Node::Scalar(open.span),
+    };
+    let mut entries: Vec<Entry> = Vec::new();
+    loop {
+        let token = tokens[*position];
+        if token.kind == close {
+            *position += 1;
+            let span = Span::new(open.span.start, token.span.end);
+            return if close == TokenKind::EndObject { Node::Object { span, entries } } else { Node::Array { span, entries } };
+        }
+        if token.kind == TokenKind::ValueSeparator {
+            *position += 1;
+            if let Some(last) = entries.last_mut() {
+                last.comma = Some(token.span);
+            }
+            continue;
+        }
+        let key = if close == TokenKind::EndObject {
+            let name = match token.text.parse() {
+                Ok(JsonValue::String(name)) => name,
+                _ => unreachable!("member names are strings"),
+            };
+            // Skip the name and the colon.
+            *position += 2;
+            Some((name, token.span))
+        } else {
+            None
+        };
+        let value = read_node(tokens, position);
+        entries.push(Entry { key, value, comma: None });
+    }
+}
@@ -0,0 +1,468 @@
+//! Exact decimal numbers for the `arbitrary_precision` feature.
+//!
+//! With the feature enabled the parser keeps every number as a [`Decimal`] built from the
+//! digits of the source text, so amounts such as `12345678901234567890.123456789` survive a
+//! parse and print unchanged. Addition, subtraction and multiplication are exact; division
+//! is left out because its results generally are not. Exponents in number text are limited
+//! to ±100000, since adding numbers writes out the zeros between their exponents.
+//!
+//! ```
+//! use crystalrake::decimal::Decimal;
+//!
+//! let price: Decimal = "19.99".parse()?;
+//! let total = &price * &Decimal::from(3) + "0.03".parse::<Decimal>()?;
+//! assert_eq!(total.to_string(), "60.00");
+//! assert_eq!(total.with_scale(0).to_string(), "60");
+//! # Ok::<(), crystalrake::error::JsonParseError>(())
+//! ```
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt::{Debug, Display, Write};
+use core::ops::{Add, Mul, Neg, Sub};
+use core::str::FromStr;
+
+use crate::error::{JsonParseError, ParseErrorKind};
+use crate::lexer::{JsonLexer, JsonNumberToken, JsonToken};
+use crate::span::Span;
+
+/// The most zeros printed between the decimal point and the digits; smaller values use an
+/// exponent, as in `1e-7`, so the text stays about as long as the digits.
+const MAX_LEADING_ZEROS: usize = 5;
+
+/// Largest exponent accepted in number text, so that `1e-2000000000 + 1` cannot ask for
+/// billions of digits.
+const MAX_EXPONENT: i64 = 100_000;
+
+/// Each limb holds nine decimal digits.
+const BASE: u64 = 1_000_000_000;
+const LIMB_DIGITS: usize = 9;
+
+/// A decimal number `coefficient × 10^-scale`, stored exactly.
+///
+/// The scale is kept as written, so `1.50` prints as `1.50`; comparisons are by value, so it
+/// still equals `1.5`. A negative scale stands for trailing zeros, as in `12e3`.
+#[derive(Clone)]
+pub struct Decimal {
+    negative: bool,
+    /// Little-endian limbs of the coefficient, without zero limbs at the top.
+    magnitude: Vec<u32>,
+    scale: i64,
+}
+
+impl Decimal {
+    /// Zero with the given scale.
+    pub fn zero(scale: i64) -> Decimal {
+        Decimal { negative: false, magnitude: Vec::new(), scale }
+    }
+
+    /// Builds `coefficient × 10^-scale`, so `Decimal::new(1999, 2)` is `19.99`.
+    pub fn new(coefficient: i128, scale: i64) -> Decimal {
+        Decimal::from_magnitude(coefficient < 0, from_u128(coefficient.unsigned_abs()), scale)
+    }
+
+    fn from_magnitude(negative: bool, magnitude: Vec<u32>, scale: i64) -> Decimal {
+        // Zero has no sign.
+        Decimal { negative: negative && !magnitude.is_empty(), magnitude, scale }
+    }
+
+    /// Builds the exact value of a number token, or `None` if its exponent is beyond
+    /// ±[`MAX_EXPONENT`].
+    pub(crate) fn from_token(number: &JsonNumberToken) -> Option<Decimal> {
+        let (exp_negative, exp_digits) = match number.exp.strip_prefix(['+', '-']) {
+            Some(digits) => (number.exp.starts_with('-'), digits),
+            None => (false, number.exp.as_str()),
+        };
+        let exp = if exp_digits.is_empty() { 0 } else { exp_digits.parse::<i32>().ok()? as i64 };
+        if exp > MAX_EXPONENT {
+            return None;
+        }
+        let scale = number.frac.len() as i64 - if exp_negative { -exp } else { exp };
+        let digits = [number.integer.as_str(), number.frac.as_str()].concat();
+        Some(Decimal::from_magnitude(number.is_minus, parse_digits(&digits), scale))
+    }
+
+    /// The exact value of a finite `f64`, using the shortest digits that round-trip.
+    pub fn from_f64(n: f64) -> Option<Decimal> {
+        if !n.is_finite() {
+            return None;
+        }
+        let mut s = String::new();
+        write!(s, "{:e}", n).ok()?;
+        s.parse().ok()
+    }
+
+    /// The number of digits after the decimal point; negative for trailing zeros.
+    pub fn scale(&self) -> i64 {
+        self.scale
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Whether the value is a whole number.
+    pub fn is_integer(&self) -> bool {
+        self.scale <= 0 || self.is_zero() || digits(&self.magnitude).bytes().rev().take(self.scale as usize).all(|b| b == b'0')
+    }
+
+    /// The nearest `f64`; very large values become infinite.
+    pub fn to_f64(&self) -> f64 {
+        let mut s = String::new();
+        if self.negative {
+            s.push('-');
+        }
+        s.push_str(&digits(&self.magnitude));
+        write!(s, "e{}", -self.scale).unwrap();
+        s.parse().expect("a decimal is a valid float literal")
+    }
+
+    /// The value as an `i128`, if it is a whole number in range.
+    pub fn to_i128(&self) -> Option<i128> {
+        // An `i128` has at most 39 digits.
+        if !self.is_integer() || self.exponent() > 39 {
+            return None;
+        }
+        let whole = self.with_scale(0);
+        let mut n: i128 = 0;
+        for &limb in whole.magnitude.iter().rev() {
+            n = n.checked_mul(BASE as i128)?.checked_add(limb as i128)?;
+        }
+        Some(if whole.negative { -n } else { n })
+    }
+
+    /// Converts to `scale` digits after the decimal point.
+    ///
+    /// Digits are added as zeros; when digits are dropped the result is rounded half to even.
+    pub fn with_scale(&self, scale: i64) -> Decimal {
+        match scale.cmp(&self.scale) {
+            Ordering::Equal => self.clone(),
+            Ordering::Greater => Decimal::from_magnitude(self.negative, shift(&self.magnitude, (scale - self.scale) as u64), scale),
+            Ordering::Less => {
+                let dropped = (self.scale - scale) as usize;
+                let all = digits(&self.magnitude);
+                let keep = all.len().saturating_sub(dropped);
+                let (kept, rest) = all.split_at(keep);
+                let mut magnitude = parse_digits(kept);
+                // Digits beyond the coefficient are leading zeros, below one half.
+                let half = if rest.len() < dropped {
+                    Ordering::Less
+                } else if rest[1..].bytes().all(|b| b == b'0') {
+                    rest.as_bytes()[0].cmp(&b'5')
+                } else {
+                    rest.as_bytes()[0].cmp(&b'5').then(Ordering::Greater)
+                };
+                let odd = kept.bytes().last().is_some_and(|b| (b - b'0') % 2 == 1);
+                if half == Ordering::Greater || (half == Ordering::Equal && odd) {
+                    magnitude = add_magnitudes(&magnitude, &[1]);
+                }
+                Decimal::from_magnitude(self.negative, magnitude, scale)
+            },
+        }
+    }
+
+    /// The same value with trailing zeros after the decimal point removed.
+    pub fn normalized(&self) -> Decimal {
+        if self.scale <= 0 || self.is_zero() {
+            return self.clone();
+        }
+        let zeros = digits(&self.magnitude).bytes().rev().take_while(|b| *b == b'0').count() as i64;
+        self.with_scale(self.scale - zeros.min(self.scale))
+    }
+
+    /// The number of digits before the decimal point, counting from the first nonzero one;
+    /// negative for values below `0.1`.
+    fn exponent(&self) -> i64 {
+        digit_count(&self.magnitude) as i64 - self.scale
+    }
+
+    /// Both magnitudes at the larger of the two scales.
+    fn aligned(&self, other: &Decimal) -> (Vec<u32>, Vec<u32>, i64) {
+        let scale = self.scale.max(other.scale);
+        (shift(&self.magnitude, (scale - self.scale) as u64), shift(&other.magnitude, (scale - other.scale) as u64), scale)
+    }
+}
+
+impl Debug for Decimal {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Decimal({})", self)
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.negative {
+            f.write_char('-')?;
+        }
+        let digits = digits(&self.magnitude);
+        if self.scale <= 0 {
+            f.write_str(&digits)?;
+            if self.scale < 0 {
+                write!(f, "e{}", -self.scale)?;
+            }
+            return Ok(());
+        }
+        let scale = self.scale as usize;
+        if digits.len() > scale {
+            let (whole, frac) = digits.split_at(digits.len() - scale);
+            write!(f, "{}.{}", whole, frac)
+        } else if scale - digits.len() > MAX_LEADING_ZEROS {
+            write!(f, "{}e-{}", digits, scale)
+        } else {
+            f.write_str("0.")?;
+            for _ in digits.len()..scale {
+                f.write_char('0')?;
+            }
+            f.write_str(&digits)
+        }
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = JsonParseError;
+
+    /// Parses a JSON number.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lexer = JsonLexer::new(s);
+        match lexer.next_spanned() {
+            Some((Ok(JsonToken::Number(number)), span)) if span.end == s.len() => {
+                Decimal::from_token(&number).ok_or_else(|| JsonParseError::new(ParseErrorKind::NumberOutOfRange, span))
+            },
+            Some((Err(e), _)) => Err(e.into()),
+            Some((_, span)) => Err(JsonParseError { kind: ParseErrorKind::InvalidToken, span, expected: &["number"] }),
+            None => Err(JsonParseError { kind: ParseErrorKind::NonValue, span: Span::new(0, 0), expected: &["number"] }),
+        }
+    }
+}
+
+macro_rules! from_integer {
+    ($($t:ty)*) => {
+        $(
+            impl From<$t> for Decimal {
+                fn from(n: $t) -> Self {
+                    Decimal::new(n as i128, 0)
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Decimal) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => return Ordering::Greater,
+            (true, false) => return Ordering::Less,
+            _ => {},
+        }
+        if self.is_zero() || other.is_zero() {
+            return (!self.is_zero()).cmp(&!other.is_zero());
+        }
+        // Only align the digits when both values start at the same power of ten, so that the
+        // shift is bounded by their lengths rather than by the difference in scale.
+        let ordering = self.exponent().cmp(&other.exponent()).then_with(|| {
+            let (a, b, _) = self.aligned(other);
+            compare_magnitudes(&a, &b)
+        });
+        if self.negative { ordering.reverse() } else { ordering }
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        Decimal::from_magnitude(!self.negative, self.magnitude, self.scale)
+    }
+}
+
+impl Add<&Decimal> for &Decimal {
+    type Output = Decimal;
+
+    fn add(self, other: &Decimal) -> Decimal {
+        let (a, b, scale) = self.aligned(other);
+        if self.negative == other.negative {
+            return Decimal::from_magnitude(self.negative, add_magnitudes(&a, &b), scale);
+        }
+        match compare_magnitudes(&a, &b) {
+            Ordering::Less => Decimal::from_magnitude(other.negative, sub_magnitudes(&b, &a), scale),
+            _ => Decimal::from_magnitude(self.negative, sub_magnitudes(&a, &b), scale),
+        }
+    }
+}
+
+impl Sub<&Decimal> for &Decimal {
+    type Output = Decimal;
+
+    fn sub(self, other: &Decimal) -> Decimal {
+        self + &-other.clone()
+    }
+}
+
+impl Mul<&Decimal> for &Decimal {
+    type Output = Decimal;
+
+    fn mul(self, other: &Decimal) -> Decimal {
+        let mut product = vec![0; self.magnitude.len() + other.magnitude.len()];
+        for (i, &a) in self.magnitude.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.magnitude.iter().enumerate() {
+                let n = product[i + j] as u64 + a as u64 * b as u64 + carry;
+                product[i + j] = (n % BASE) as u32;
+                carry = n / BASE;
+            }
+            product[i + other.magnitude.len()] = carry as u32;
+        }
+        trim(&mut product);
+        Decimal::from_magnitude(self.negative != other.negative, product, self.scale + other.scale)
+    }
+}
+
+macro_rules! forward_owned {
+    ($($trait:ident $method:ident)*) => {
+        $(
+            impl $trait<Decimal> for Decimal {
+                type Output = Decimal;
+
+                fn $method(self, other: Decimal) -> Decimal {
+                    (&self).$method(&other)
+                }
+            }
+
+            impl $trait<&Decimal> for Decimal {
+                type Output = Decimal;
+
+                fn $method(self, other: &Decimal) -> Decimal {
+                    (&self).$method(other)
+                }
+            }
+        )*
+    };
+}
+
+forward_owned!(Add add Sub sub Mul mul);
+
+fn trim(magnitude: &mut Vec<u32>) {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+}
+
+fn from_u128(mut n: u128) -> Vec<u32> {
+    let mut magnitude = Vec::new();
+    while n > 0 {
+        magnitude.push((n % BASE as u128) as u32);
+        n /= BASE as u128;
+    }
+    magnitude
+}
+
+/// Reads a string of ASCII digits.
+fn parse_digits(s: &str) -> Vec<u32> {
+    let s = s.trim_start_matches('0');
+    let mut magnitude = Vec::with_capacity(s.len() / LIMB_DIGITS + 1);
+    let mut end = s.len();
+    while end > 0 {
+        let start = end.saturating_sub(LIMB_DIGITS);
+        magnitude.push(s[start..end].parse().expect("digits"));
+        end = start;
+    }
+    magnitude
+}
+
+/// The coefficient's digits, `"0"` for zero.
+fn digits(magnitude: &[u32]) -> String {
+    let mut s = match magnitude.last() {
+        Some(top) => top.to_string(),
+        None => return "0".to_string(),
+    };
+    for limb in magnitude.iter().rev().skip(1) {
+        write!(s, "{:09}", limb).unwrap();
+    }
+    s
+}
+
+fn digit_count(magnitude: &[u32]) -> usize {
+    match magnitude.last() {
+        Some(&top) => (magnitude.len() - 1) * LIMB_DIGITS + top.ilog10() as usize + 1,
+        None => 1,
+    }
+}
+
+/// Multiplies by `10^n`.
+fn shift(magnitude: &[u32], n: u64) -> Vec<u32> {
+    if magnitude.is_empty() || n == 0 {
+        return magnitude.to_vec();
+    }
+    let mut shifted = vec![0; (n / LIMB_DIGITS as u64) as usize];
+    shifted.extend_from_slice(magnitude);
+    let factor = 10u64.pow((n % LIMB_DIGITS as u64) as u32);
+    let mut carry = 0;
+    for limb in &mut shifted {
+        let n = *limb as u64 * factor + carry;
+        *limb = (n % BASE) as u32;
+        carry = n / BASE;
+    }
+    if carry > 0 {
+        shifted.push(carry as u32);
+    }
+    shifted
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut sum = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0;
+    for (i, &limb) in long.iter().enumerate() {
+        let n = limb as u64 + *short.get(i).unwrap_or(&0) as u64 + carry;
+        sum.push((n % BASE) as u32);
+        carry = n / BASE;
+    }
+    if carry > 0 {
+        sum.push(carry as u32);
+    }
+    sum
+}
+
+/// `a - b` where `a >= b`.
+fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, &limb) in a.iter().enumerate() {
+        let subtrahend = *b.get(i).unwrap_or(&0) as i64 + borrow;
+        let mut n = limb as i64 - subtrahend;
+        borrow = 0;
+        if n < 0 {
+            n += BASE as i64;
+            borrow = 1;
+        }
+        difference.push(n as u32);
+    }
+    trim(&mut difference);
+    difference
+}
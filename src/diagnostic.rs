@@ -0,0 +1,101 @@
+//! Rendering parse errors as compiler-style diagnostics.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use crate::error::{JsonParseError, LexErrorKind, ParseErrorKind};
+
+struct Style {
+    error: &'static str,
+    bold: &'static str,
+    gutter: &'static str,
+    reset: &'static str,
+}
+
+const PLAIN: Style = Style { error: "", bold: "", gutter: "", reset: "" };
+const COLORED: Style = Style { error: "\x1b[1;31m", bold: "\x1b[1m", gutter: "\x1b[1;34m", reset: "\x1b[0m" };
+
+impl JsonParseError {
+    /// A suggestion for fixing the error, if there is one.
+    pub fn hint(&self) -> Option<&'static str> {
+        match &self.kind {
+            ParseErrorKind::TrailingComma => Some("trailing comma not allowed in strict mode"),
+            ParseErrorKind::NonEndArray => Some("add `]` to close the array"),
+            ParseErrorKind::NonEndObject => Some("add `}` to close the object"),
+            ParseErrorKind::NoObjectName => Some("object member names must be double-quoted strings"),
+            ParseErrorKind::InvalidToken if self.expected.contains(&"`,`") => Some("a comma may be missing"),
+            ParseErrorKind::InvalidToken if self.expected.contains(&"`:`") => Some("a colon may be missing after the member name"),
+            ParseErrorKind::FromUtf16Error(_) => Some("surrogates escaped with `\\u` must form high-low pairs"),
+            ParseErrorKind::LexError(e) => match e.kind() {
+                LexErrorKind::InvalidChar('\'') => Some("strings must be enclosed in double quotes"),
+                LexErrorKind::InvalidChar(c) if c.is_control() => Some("control characters in strings must be escaped"),
+                LexErrorKind::InvalidChar(c) if c.is_alphabetic() => Some("the only literal names are `true`, `false` and `null`"),
+                LexErrorKind::NonQuotationMark => Some("add `\"` to close the string"),
+                LexErrorKind::NotDigit => Some("a minus sign must be followed by a digit"),
+                LexErrorKind::NonFracDigit => Some("add digits after the decimal point"),
+                LexErrorKind::LeadingZero => Some("numbers cannot have leading zeros"),
+                LexErrorKind::EmptyExponent => Some("add digits to the exponent"),
+                LexErrorKind::NotEscapeChar => Some("valid escapes are \\\", \\\\, \\/, \\b, \\f, \\n, \\r, \\t and \\uXXXX"),
+                LexErrorKind::UnterminatedComment => Some("add `*/` to close the comment"),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Renders the error with the offending line of `source`, the text it was parsed from,
+    /// and a caret under it:
+    ///
+    /// ```text
+    /// error: trailing comma is not allowed
+    ///  --> 1:7
+    ///   |
+    /// 1 | [1, 2,]
+    ///   |       ^
+    ///   = expected: value
+    ///   = hint: trailing comma not allowed in strict mode
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        self.render_with(source, "", &PLAIN)
+    }
+
+    /// Like [`render`](Self::render), with ANSI colors for terminals.
+    pub fn render_colored(&self, source: &str) -> String {
+        self.render_with(source, "", &COLORED)
+    }
+
+    /// Like [`render`](Self::render), with the location prefixed by `path`, e.g. `--> data.json:1:6`.
+    pub fn render_file(&self, source: &str, path: &str, colored: bool) -> String {
+        let origin = format!("{}:", path);
+        self.render_with(source, &origin, if colored { &COLORED } else { &PLAIN })
+    }
+
+    fn render_with(&self, source: &str, origin: &str, style: &Style) -> String {
+        let start = self.span.start.min(source.len());
+        let (line, column) = self.span.line_col(source);
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..].find('\n').map_or(source.len(), |i| start + i);
+        let text = source[line_start..line_end].trim_end_matches('\r');
+
+        // Keep tabs so that the caret lines up with the text above it.
+        let padding: String = source[line_start..start].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+        let carets = source[start..self.span.end.clamp(start, line_end)].chars().count().max(1);
+
+        let number = line.to_string();
+        let blank = " ".repeat(number.len());
+        let Style { error, bold, gutter, reset } = style;
+        let mut out = format!("{error}error{reset}{bold}: {}{reset}\n", self);
+        out += &format!("{blank}{gutter}-->{reset} {}{}:{}\n", origin, line, column);
+        out += &format!("{blank} {gutter}|{reset}\n");
+        out += &format!("{gutter}{number} |{reset} {}\n", text);
+        out += &format!("{blank} {gutter}|{reset} {}{error}{}{reset}\n", padding, "^".repeat(carets));
+        if let Some((last, rest)) = self.expected.split_last() {
+            let expected = if rest.is_empty() { last.to_string() } else { format!("{} or {}", rest.join(", "), last) };
+            out += &format!("{blank} {gutter}={reset} {bold}expected{reset}: {}\n", expected);
+        }
+        if let Some(hint) = self.hint() {
+            out += &format!("{blank} {gutter}={reset} {bold}hint{reset}: {}\n", hint);
+        }
+        out
+    }
+}
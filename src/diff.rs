@@ -0,0 +1,296 @@
+//! Structural differences between two `JsonValue`s.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use crate::json::JsonValue;
+use crate::pointer;
+
+/// A single difference, located by JSON Pointer.
+///
+/// `Removed` and the source of `Moved` point into the old value; all other
+/// pointers point into the new value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added { pointer: String, value: JsonValue },
+    Removed { pointer: String, value: JsonValue },
+    Changed { pointer: String, old: JsonValue, new: JsonValue },
+    /// An array element found at another index. Only reported with LCS alignment.
+    Moved { from: String, to: String, value: JsonValue },
+}
+
+impl Change {
+    /// Pointer to the changed location; for a move, its destination.
+    pub fn pointer(&self) -> &str {
+        match self {
+            Change::Added { pointer, .. } | Change::Removed { pointer, .. } | Change::Changed { pointer, .. } => pointer,
+            Change::Moved { to, .. } => to,
+        }
+    }
+}
+
+fn display_pointer(pointer: &str) -> &str {
+    if pointer.is_empty() { "(root)" } else { pointer }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Change::Added { pointer, value } => {
+                writeln!(f, "@@ {} @@", display_pointer(pointer))?;
+                writeln!(f, "+{}", value)
+            },
+            Change::Removed { pointer, value } => {
+                writeln!(f, "@@ {} @@", display_pointer(pointer))?;
+                writeln!(f, "-{}", value)
+            },
+            Change::Changed { pointer, old, new } => {
+                writeln!(f, "@@ {} @@", display_pointer(pointer))?;
+                writeln!(f, "-{}", old)?;
+                writeln!(f, "+{}", new)
+            },
+            Change::Moved { from, to, value } => {
+                writeln!(f, "@@ {} -> {} @@", from, to)?;
+                writeln!(f, " {}", value)
+            },
+        }
+    }
+}
+
+/// Options for [`diff_with`].
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    lcs: bool,
+}
+
+impl DiffOptions {
+    pub fn new() -> DiffOptions {
+        DiffOptions::default()
+    }
+
+    /// Aligns array elements by their longest common subsequence instead of by index,
+    /// so insertions and removals in the middle of an array do not shift every later element.
+    /// Elements that only changed position are reported as `Moved`. Memory stays linear in the
+    /// array lengths, and time grows with the lengths times the number of differences.
+    pub fn lcs(mut self, lcs: bool) -> DiffOptions {
+        self.lcs = lcs;
+        self
+    }
+}
+
+/// Lists the changes turning `old` into `new`, comparing arrays index by index.
+pub fn diff(old: &JsonValue, new: &JsonValue) -> Vec<Change> {
+    diff_with(old, new, &DiffOptions::default())
+}
+
+/// Lists the changes turning `old` into `new`.
+pub fn diff_with(old: &JsonValue, new: &JsonValue, options: &DiffOptions) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_value(old, new, String::new(), options, &mut changes);
+    changes
+}
+
+/// Renders changes as unified-diff style hunks, one per change.
+pub fn render(changes: &[Change]) -> String {
+    changes.iter().map(|change| change.to_string()).collect()
+}
+
+fn child(pointer: &str, token: &str) -> String {
+    format!("{}/{}", pointer, pointer::escape(token))
+}
+
+fn index(pointer: &str, i: usize) -> String {
+    format!("{}/{}", pointer, i)
+}
+
+fn diff_value(old: &JsonValue, new: &JsonValue, pointer: String, options: &DiffOptions, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (JsonValue::Objects(old_members), JsonValue::Objects(_)) => {
+            for member in old_members {
+                let path = child(&pointer, &member.name);
+                match new.get(&member.name) {
+                    Some(value) => diff_value(&member.value, value, path, options, changes),
+                    None => changes.push(Change::Removed { pointer: path, value: member.value.clone() }),
+                }
+            }
+            for member in new.as_objects().unwrap() {
+                if old.get(&member.name).is_none() {
+                    changes.push(Change::Added { pointer: child(&pointer, &member.name), value: member.value.clone() });
+                }
+            }
+        },
+        (JsonValue::Array(old_values), JsonValue::Array(new_values)) => {
+            if options.lcs {
+                diff_aligned(old_values, new_values, &pointer, options, changes);
+            } else {
+                diff_indexed(old_values, new_values, &pointer, options, changes);
+            }
+        },
+        _ => {
+            if old != new {
+                changes.push(Change::Changed { pointer, old: old.clone(), new: new.clone() });
+            }
+        },
+    }
+}
+
+fn diff_indexed(old: &[JsonValue], new: &[JsonValue], pointer: &str, options: &DiffOptions, changes: &mut Vec<Change>) {
+    for (i, (old, new)) in old.iter().zip(new).enumerate() {
+        diff_value(old, new, index(pointer, i), options, changes);
+    }
+    for (i, value) in old.iter().enumerate().skip(new.len()) {
+        changes.push(Change::Removed { pointer: index(pointer, i), value: value.clone() });
+    }
+    for (i, value) in new.iter().enumerate().skip(old.len()) {
+        changes.push(Change::Added { pointer: index(pointer, i), value: value.clone() });
+    }
+}
+
+/// Index pairs of a longest common subsequence of `old` and `new`.
+///
+/// Uses Myers' algorithm, splitting each range where the forward and backward searches for the
+/// shortest edit script meet, so memory stays linear and the work grows with the number of
+/// differences rather than with the product of the lengths.
+fn lcs(old: &[JsonValue], new: &[JsonValue]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    common_subsequence(old, new, 0, 0, &mut pairs);
+    pairs
+}
+
+/// Appends the pairs of a longest common subsequence of `old` and `new`, whose first elements
+/// are at `i` and `j` in the whole arrays.
+fn common_subsequence(old: &[JsonValue], new: &[JsonValue], i: usize, j: usize, pairs: &mut Vec<(usize, usize)>) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    pairs.extend((0..prefix).map(|k| (i + k, j + k)));
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let suffix = old.iter().rev().zip(new.iter().rev()).take_while(|(a, b)| a == b).count();
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+    let (i, j) = (i + prefix, j + prefix);
+    if let Some((x, y)) = middle(old, new) {
+        common_subsequence(&old[..x], &new[..y], i, j, pairs);
+        common_subsequence(&old[x..], &new[y..], i + x, j + y, pairs);
+    }
+    pairs.extend((0..suffix).map(|k| (i + old.len() + k, j + new.len() + k)));
+}
+
+/// Where a shortest edit script from `old` to `new` crosses its middle, or `None` if the two
+/// have no element in common.
+fn middle(old: &[JsonValue], new: &[JsonValue]) -> Option<(usize, usize)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    if n == 0 || m == 0 {
+        return None;
+    }
+    // `forward[offset + k]` is the furthest `x` reached on diagonal `k = x - y` from the start,
+    // `backward` the same counted from the ends of both slices.
+    let max = (n + m + 1) / 2;
+    let offset = max;
+    let mut forward = vec![-1isize; 2 * max as usize + 2];
+    let mut backward = forward.clone();
+    forward[offset as usize + 1] = 0;
+    backward[offset as usize + 1] = 0;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let (mut forward_start, mut forward_end, mut backward_start, mut backward_end) = (0, 0, 0, 0);
+    for d in 0..max {
+        for k in (-d + forward_start..=d - forward_end).step_by(2) {
+            let index = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && forward[index - 1] < forward[index + 1]) {
+                forward[index + 1]
+            } else {
+                forward[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index] = x;
+            if x > n {
+                forward_end += 2;
+            } else if y > m {
+                forward_start += 2;
+            } else if odd {
+                let other = offset + delta - k;
+                if other >= 0 && (other as usize) < backward.len() && backward[other as usize] != -1 && x >= n - backward[other as usize] {
+                    return Some((x as usize, y as usize));
+                }
+            }
+        }
+        for k in (-d + backward_start..=d - backward_end).step_by(2) {
+            let index = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && backward[index - 1] < backward[index + 1]) {
+                backward[index + 1]
+            } else {
+                backward[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[index] = x;
+            if x > n {
+                backward_end += 2;
+            } else if y > m {
+                backward_start += 2;
+            } else if !odd {
+                let other = offset + delta - k;
+                if other >= 0 && (other as usize) < forward.len() && forward[other as usize] != -1 {
+                    let forward_x = forward[other as usize];
+                    if forward_x >= n - x {
+                        return Some((forward_x as usize, (forward_x - (other - offset)) as usize));
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+fn diff_aligned(old: &[JsonValue], new: &[JsonValue], pointer: &str, options: &DiffOptions, changes: &mut Vec<Change>) {
+    let anchors = lcs(old, new);
+
+    // Elements outside the common subsequence, grouped by the gap between anchors they fall in.
+    let mut gaps = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for &(anchor_i, anchor_j) in anchors.iter().chain([(old.len(), new.len())].iter()) {
+        gaps.push(((i..anchor_i).collect::<Vec<_>>(), (j..anchor_j).collect::<Vec<_>>()));
+        i = anchor_i + 1;
+        j = anchor_j + 1;
+    }
+
+    // An unmatched old element equal to an unmatched new one has moved.
+    let mut moved_old = vec![false; old.len()];
+    let mut moved_new = vec![false; new.len()];
+    for (removed, _) in &gaps {
+        for &i in removed {
+            let target = gaps
+                .iter()
+                .flat_map(|(_, added)| added)
+                .find(|&&j| !moved_new[j] && old[i] == new[j]);
+            if let Some(&j) = target {
+                moved_old[i] = true;
+                moved_new[j] = true;
+                changes.push(Change::Moved { from: index(pointer, i), to: index(pointer, j), value: old[i].clone() });
+            }
+        }
+    }
+
+    // What remains in a gap was replaced in place, removed or added.
+    for (removed, added) in &gaps {
+        let removed: Vec<usize> = removed.iter().copied().filter(|&i| !moved_old[i]).collect();
+        let added: Vec<usize> = added.iter().copied().filter(|&j| !moved_new[j]).collect();
+        for (&i, &j) in removed.iter().zip(&added) {
+            diff_value(&old[i], &new[j], index(pointer, j), options, changes);
+        }
+        for &i in removed.iter().skip(added.len()) {
+            changes.push(Change::Removed { pointer: index(pointer, i), value: old[i].clone() });
+        }
+        for &j in added.iter().skip(removed.len()) {
+            changes.push(Change::Added { pointer: index(pointer, j), value: new[j].clone() });
+        }
+    }
+}
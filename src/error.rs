@@ -1,7 +1,11 @@
-use std::{num::{ParseIntError, ParseFloatError}, string::FromUtf16Error};
+use alloc::string::{FromUtf16Error, String, ToString};
+use alloc::vec::Vec;
+use core::num::{ParseIntError, ParseFloatError};
+
+use crate::span::Span;
 
 /// Enum to store the various types of errors that can cause tokenizing a JSON to fail.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum LexErrorKind {
     /// Contains an invalid char in a JSON.
     InvalidChar(char),
@@ -11,36 +15,55 @@ pub enum LexErrorKind {
     NotDigit,
     /// Fraction part didn't contain any digit.
     NonFracDigit,
+    /// Integer part started with `0` and was followed by another digit.
+    LeadingZero,
+    /// Exponent part didn't contain any digit.
+    EmptyExponent,
     /// Found an invalid char after reverse solidus.
     NotEscapeChar,
     /// Found `"\uXXXX"`(X is a hex digit) from JSON, but `XXXX` could not parse to `u16`.
-    ParseError(ParseIntError)
+    ParseError(ParseIntError),
+    /// A `/*` comment was not closed by `*/`.
+    UnterminatedComment,
+    /// Bytes that do not form valid UTF-8.
+    InvalidUtf8,
 }
 
 #[derive(Debug)]
 pub struct JsonLexerError {
     pub(crate) kind: LexErrorKind,
+    pub(crate) span: Span,
 }
 
 impl JsonLexerError {
     pub fn kind(&self) -> &LexErrorKind {
         &self.kind
     }
+
+    /// Location of the malformed text in the source.
+    pub fn span(&self) -> Span {
+        self.span
+    }
 }
 
-impl std::fmt::Display for JsonLexerError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for JsonLexerError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self.kind() {
             LexErrorKind::InvalidChar(c) => write!(f, "invalid charactor '{}' found from JSON", c),
             LexErrorKind::NonQuotationMark => write!(f, "cannot find a pair of quotation-mark from JSON string"),
             LexErrorKind::NonFracDigit => write!(f, "cannot find any digit after decimal-point"),
             LexErrorKind::NotDigit => write!(f, "cannot find any digit after minus sign"),
+            LexErrorKind::LeadingZero => write!(f, "number cannot have a leading zero"),
+            LexErrorKind::EmptyExponent => write!(f, "cannot find any digit in exponent part"),
             LexErrorKind::NotEscapeChar => write!(f, "invalid charactor found after reverse solidus"),
             LexErrorKind::ParseError(_) => write!(f, "cannot parse hex digit string to u16"),
+            LexErrorKind::UnterminatedComment => write!(f, "cannot find the end of a block comment"),
+            LexErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8 sequence found from JSON"),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for JsonLexerError {}
 
 /// Enums to store the various types of errors that can cause parsing a JSON to fail.
@@ -56,6 +79,12 @@ pub enum ParseErrorKind {
     NonEndArray,
     /// Could not find any JSON object name.
     NoObjectName,
+    /// Arrays and objects were nested deeper than the parser allows.
+    RecursionLimitExceeded,
+    /// A comma directly before a closing bracket.
+    TrailingComma,
+    /// A number's exponent is too large to keep exactly, with the `arbitrary_precision` feature.
+    NumberOutOfRange,
     /// A possible error value when converting a String from a UTF-16 byte slice.
     FromUtf16Error(FromUtf16Error),
     ParseFloatError(ParseFloatError),
@@ -65,22 +94,48 @@ pub enum ParseErrorKind {
 #[derive(Debug)]
 pub struct JsonParseError {
     pub(crate) kind: ParseErrorKind,
+    pub(crate) span: Span,
+    pub(crate) expected: &'static [&'static str],
 }
 
 impl JsonParseError {
+    pub(crate) fn new(kind: ParseErrorKind, span: Span) -> JsonParseError {
+        JsonParseError { kind, span, expected: &[] }
+    }
+
     pub fn kind(&self) -> &ParseErrorKind {
         &self.kind
     }
+
+    /// Location of the error in the source; empty at the end of the input.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// What the parser would have accepted where the error was found, such as `` `,` `` or `value`.
+    pub fn expected(&self) -> &[&'static str] {
+        self.expected
+    }
+}
+
+impl From<JsonLexerError> for JsonParseError {
+    fn from(e: JsonLexerError) -> Self {
+        let span = e.span;
+        JsonParseError::new(ParseErrorKind::LexError(e), span)
+    }
 }
 
-impl std::fmt::Display for JsonParseError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for JsonParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match &self.kind {
             ParseErrorKind::InvalidToken => write!(f, "invalid token found in JSON"),
             ParseErrorKind::NonValue => write!(f, "expect some value, but cannot find any JSON value"),
             ParseErrorKind::NonEndObject => write!(f, "expect end-of-object '}}', but cannot find any right curly bracket"),
             ParseErrorKind::NonEndArray => write!(f, "expect end-of-array ']', but cannot find right square bracket"),
             ParseErrorKind::NoObjectName => write!(f, "cannot find any object name"),
+            ParseErrorKind::RecursionLimitExceeded => write!(f, "arrays and objects are nested too deeply"),
+            ParseErrorKind::TrailingComma => write!(f, "trailing comma is not allowed"),
+            ParseErrorKind::NumberOutOfRange => write!(f, "number exponent is out of range"),
             ParseErrorKind::FromUtf16Error(e) => e.fmt(f),
             ParseErrorKind::ParseFloatError(e) => e.fmt(f),
             ParseErrorKind::LexError(e) => e.fmt(f),
@@ -88,6 +143,518 @@ impl std::fmt::Display for JsonParseError {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for JsonParseError {
     
-}
\ No newline at end of file
+}
+
+/// Error returned when a string is not a valid JSON Pointer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PointerError {
+    pub(crate) pointer: String,
+}
+
+impl core::fmt::Display for PointerError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "invalid JSON pointer '{}'", self.pointer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PointerError {}
+
+/// Error returned when a regular expression in a schema or a query cannot be compiled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexError {
+    pub(crate) pattern: String,
+    pub(crate) reason: &'static str,
+}
+
+impl core::fmt::Display for RegexError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "invalid regular expression '{}': {}", self.pattern, self.reason)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RegexError {}
+
+/// Enum to store the various types of errors that can cause compiling a JSON Schema to fail.
+#[derive(Debug)]
+pub enum SchemaErrorKind {
+    /// A schema was neither an object nor a boolean.
+    NotSchema,
+    /// A keyword had a value of the wrong type. Contains the keyword.
+    InvalidKeyword(String),
+    /// `$ref` pointed to a location that does not exist in the schema.
+    UnresolvedRef(String),
+    /// `$ref` pointed outside of the schema document.
+    UnsupportedRef(String),
+    /// `pattern` or `patternProperties` contained an invalid regular expression.
+    Regex(RegexError),
+}
+
+#[derive(Debug)]
+pub struct SchemaError {
+    pub(crate) kind: SchemaErrorKind,
+    pub(crate) pointer: String,
+}
+
+impl SchemaError {
+    pub fn kind(&self) -> &SchemaErrorKind {
+        &self.kind
+    }
+
+    /// JSON Pointer to the offending location in the schema.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+}
+
+impl core::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "at schema '{}': ", self.pointer)?;
+        match &self.kind {
+            SchemaErrorKind::NotSchema => write!(f, "schema must be an object or a boolean"),
+            SchemaErrorKind::InvalidKeyword(k) => write!(f, "invalid value for keyword '{}'", k),
+            SchemaErrorKind::UnresolvedRef(r) => write!(f, "cannot resolve reference '{}'", r),
+            SchemaErrorKind::UnsupportedRef(r) => write!(f, "only references within the schema are supported, found '{}'", r),
+            SchemaErrorKind::Regex(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SchemaError {}
+
+/// Enum to store the various types of errors that can cause parsing a JSONPath query to fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonPathErrorKind {
+    /// The query ended in the middle of an expression.
+    UnexpectedEnd,
+    /// Found a char that cannot appear at this position.
+    UnexpectedChar(char),
+    /// An index, slice bound or number literal was malformed or out of range.
+    InvalidNumber,
+    /// Found an invalid escape sequence in a string literal.
+    InvalidEscape,
+    /// Called a function that is not defined. Contains the function name.
+    UnknownFunction(String),
+    /// Called a function with the wrong number of arguments. Contains the function name.
+    ArgumentCount(String),
+    /// A function result or argument was used where its type is not allowed.
+    NotWellTyped,
+    /// A query in a comparison could select more than one node.
+    NonSingularQuery,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPathError {
+    pub(crate) kind: JsonPathErrorKind,
+    pub(crate) position: usize,
+}
+
+impl JsonPathError {
+    pub fn kind(&self) -> &JsonPathErrorKind {
+        &self.kind
+    }
+
+    /// Char offset in the query where the error was found.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl core::fmt::Display for JsonPathError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.kind {
+            JsonPathErrorKind::UnexpectedEnd => write!(f, "unexpected end of JSONPath query")?,
+            JsonPathErrorKind::UnexpectedChar(c) => write!(f, "unexpected charactor '{}' in JSONPath query", c)?,
+            JsonPathErrorKind::InvalidNumber => write!(f, "invalid number in JSONPath query")?,
+            JsonPathErrorKind::InvalidEscape => write!(f, "invalid escape sequence in JSONPath string literal")?,
+            JsonPathErrorKind::UnknownFunction(name) => write!(f, "unknown function '{}'", name)?,
+            JsonPathErrorKind::ArgumentCount(name) => write!(f, "wrong number of arguments for function '{}'", name)?,
+            JsonPathErrorKind::NotWellTyped => write!(f, "function expression is not well-typed")?,
+            JsonPathErrorKind::NonSingularQuery => write!(f, "comparison requires a singular query")?,
+        }
+        write!(f, " at position {}", self.position)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for JsonPathError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CanonicalErrorKind {
+    /// NaN and infinities have no JSON representation.
+    NonFiniteNumber,
+    /// An object has the same member name more than once.
+    DuplicateKey(String),
+}
+
+/// Error returned when a value cannot be written in canonical form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalError {
+    pub(crate) kind: CanonicalErrorKind,
+    pub(crate) pointer: String,
+}
+
+impl CanonicalError {
+    pub fn kind(&self) -> &CanonicalErrorKind {
+        &self.kind
+    }
+
+    /// JSON Pointer to the offending value.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+}
+
+impl core::fmt::Display for CanonicalError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.kind {
+            CanonicalErrorKind::NonFiniteNumber => write!(f, "non-finite number")?,
+            CanonicalErrorKind::DuplicateKey(name) => write!(f, "duplicate member name '{}'", name)?,
+        }
+        write!(f, " at '{}'", self.pointer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CanonicalError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditErrorKind {
+    /// The pointer is malformed, or points to the root where a member or element is needed.
+    InvalidPointer,
+    /// Nothing exists at the pointer, or its parent does not exist.
+    NotFound,
+    /// The parent of the pointer is neither an object nor an array.
+    NotAContainer,
+    /// The object already has a member with this name.
+    KeyExists(String),
+}
+
+/// Error returned when a syntax tree cannot be edited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditError {
+    pub(crate) kind: EditErrorKind,
+    pub(crate) pointer: String,
+}
+
+impl EditError {
+    pub fn kind(&self) -> &EditErrorKind {
+        &self.kind
+    }
+
+    /// JSON Pointer of the edit.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+}
+
+impl core::fmt::Display for EditError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.kind {
+            EditErrorKind::InvalidPointer => write!(f, "invalid pointer")?,
+            EditErrorKind::NotFound => write!(f, "no value found")?,
+            EditErrorKind::NotAContainer => write!(f, "parent is not an object or array")?,
+            EditErrorKind::KeyExists(name) => write!(f, "member '{}' already exists", name)?,
+        }
+        write!(f, " at '{}'", self.pointer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EditError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FromJsonErrorKind {
+    /// The value has a different JSON type than the target type accepts.
+    InvalidType { expected: &'static str, found: &'static str },
+    /// A number does not fit in the target integer type.
+    OutOfRange(&'static str),
+    /// A required object member is absent.
+    MissingField(String),
+    /// Any other error reported by a `FromJson` implementation.
+    Custom(String),
+}
+
+/// Error returned by `FromJson`, carrying the location of the offending value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FromJsonError {
+    pub(crate) kind: FromJsonErrorKind,
+    /// Path segments from the innermost value outward.
+    pub(crate) path: Vec<String>,
+}
+
+impl FromJsonError {
+    pub fn new(kind: FromJsonErrorKind) -> FromJsonError {
+        FromJsonError { kind, path: Vec::new() }
+    }
+
+    /// `expected` a value of some type, but found `found`.
+    pub fn invalid_type(expected: &'static str, found: &crate::json::JsonValue) -> FromJsonError {
+        FromJsonError::new(FromJsonErrorKind::InvalidType { expected, found: found.type_name() })
+    }
+
+    pub fn missing_field(name: &str) -> FromJsonError {
+        FromJsonError::new(FromJsonErrorKind::MissingField(name.to_string()))
+    }
+
+    pub fn custom<T: core::fmt::Display>(message: T) -> FromJsonError {
+        FromJsonError::new(FromJsonErrorKind::Custom(message.to_string()))
+    }
+
+    /// Prepends a path segment (a member name or array index) to the error location.
+    pub fn at<S: ToString>(mut self, segment: S) -> FromJsonError {
+        self.path.push(segment.to_string());
+        self
+    }
+
+    pub fn kind(&self) -> &FromJsonErrorKind {
+        &self.kind
+    }
+
+    /// JSON Pointer to the value that failed to convert.
+    pub fn pointer(&self) -> String {
+        crate::pointer::join(self.path.iter().rev())
+    }
+}
+
+impl core::fmt::Display for FromJsonError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "at {}: ", self.pointer())?;
+        }
+        match &self.kind {
+            FromJsonErrorKind::InvalidType { expected, found } => write!(f, "expected {}, found {}", expected, found),
+            FromJsonErrorKind::OutOfRange(ty) => write!(f, "number out of range for {}", ty),
+            FromJsonErrorKind::MissingField(name) => write!(f, "missing field '{}'", name),
+            FromJsonErrorKind::Custom(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromJsonError {}
+
+#[derive(Debug)]
+pub enum OnDemandErrorKind {
+    /// The text scanned to reach or read the value is not valid JSON.
+    Syntax(JsonParseError),
+    /// The object has no member with this name.
+    MissingField(String),
+    /// The array has no element at this index.
+    IndexOutOfRange(usize),
+    /// The value has a different JSON type than requested.
+    InvalidType { expected: &'static str, found: &'static str },
+}
+
+/// Error returned when a value cannot be read from an on-demand document.
+#[derive(Debug)]
+pub struct OnDemandError {
+    pub(crate) kind: OnDemandErrorKind,
+}
+
+impl OnDemandError {
+    pub fn kind(&self) -> &OnDemandErrorKind {
+        &self.kind
+    }
+}
+
+impl From<JsonParseError> for OnDemandError {
+    fn from(e: JsonParseError) -> Self {
+        OnDemandError { kind: OnDemandErrorKind::Syntax(e) }
+    }
+}
+
+impl From<JsonLexerError> for OnDemandError {
+    fn from(e: JsonLexerError) -> Self {
+        JsonParseError::from(e).into()
+    }
+}
+
+impl core::fmt::Display for OnDemandError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.kind {
+            OnDemandErrorKind::Syntax(e) => e.fmt(f),
+            OnDemandErrorKind::MissingField(name) => write!(f, "missing field '{}'", name),
+            OnDemandErrorKind::IndexOutOfRange(index) => write!(f, "no element at index {}", index),
+            OnDemandErrorKind::InvalidType { expected, found } => write!(f, "expected {}, found {}", expected, found),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OnDemandError {}
+
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum ReadErrorKind {
+    /// Reading the input failed.
+    Io(std::io::Error),
+    /// The input is not valid JSON.
+    Syntax(JsonParseError),
+}
+
+/// Error returned when JSON read from a reader cannot be parsed.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ReadError {
+    pub(crate) kind: ReadErrorKind,
+}
+
+#[cfg(feature = "std")]
+impl ReadError {
+    pub fn kind(&self) -> &ReadErrorKind {
+        &self.kind
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for ReadError {
+    fn from(e: std::io::Error) -> Self {
+        ReadError { kind: ReadErrorKind::Io(e) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<JsonParseError> for ReadError {
+    fn from(e: JsonParseError) -> Self {
+        ReadError { kind: ReadErrorKind::Syntax(e) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.kind {
+            ReadErrorKind::Io(e) => e.fmt(f),
+            ReadErrorKind::Syntax(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReadError {}
+
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum WriteErrorKind {
+    /// Writing to the output failed.
+    Io(std::io::Error),
+    /// A value inside an object that has no key before it.
+    MissingKey,
+    /// A key outside an object, or a second key before the value of the first.
+    UnexpectedKey,
+    /// `end` with no array or object open.
+    UnexpectedEnd,
+    /// An object closed right after a key.
+    MissingValue,
+    /// A second value at the top level.
+    TrailingValue,
+    /// The writer was finished before the value was complete.
+    Incomplete,
+    /// A NaN or infinite number, which JSON cannot represent.
+    NonFiniteNumber,
+}
+
+/// Error returned when a `JsonWriter` call would not produce valid JSON, or writing fails.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct WriteError {
+    pub(crate) kind: WriteErrorKind,
+}
+
+#[cfg(feature = "std")]
+impl WriteError {
+    pub fn kind(&self) -> &WriteErrorKind {
+        &self.kind
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<WriteErrorKind> for WriteError {
+    fn from(kind: WriteErrorKind) -> Self {
+        WriteError { kind }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for WriteError {
+    fn from(e: std::io::Error) -> Self {
+        WriteError { kind: WriteErrorKind::Io(e) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.kind {
+            WriteErrorKind::Io(e) => e.fmt(f),
+            WriteErrorKind::MissingKey => write!(f, "object member written without a key"),
+            WriteErrorKind::UnexpectedKey => write!(f, "key written where a value is expected"),
+            WriteErrorKind::UnexpectedEnd => write!(f, "end written with no array or object open"),
+            WriteErrorKind::MissingValue => write!(f, "object closed after a key without a value"),
+            WriteErrorKind::TrailingValue => write!(f, "more than one value written at the top level"),
+            WriteErrorKind::Incomplete => write!(f, "writer finished before the value was complete"),
+            WriteErrorKind::NonFiniteNumber => write!(f, "non-finite number"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WriteError {}
+
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum SerdeErrorKind {
+    /// A message reported by a `Serialize` or `Deserialize` implementation.
+    Message(String),
+    /// The input is not valid JSON.
+    Syntax(JsonParseError),
+    /// A map key could not be serialized as a JSON string.
+    KeyMustBeAString,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub struct SerdeError {
+    pub(crate) kind: SerdeErrorKind,
+}
+
+#[cfg(feature = "serde")]
+impl SerdeError {
+    pub fn kind(&self) -> &SerdeErrorKind {
+        &self.kind
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<JsonParseError> for SerdeError {
+    fn from(e: JsonParseError) -> Self {
+        SerdeError { kind: SerdeErrorKind::Syntax(e) }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<core::fmt::Error> for SerdeError {
+    fn from(e: core::fmt::Error) -> Self {
+        SerdeError { kind: SerdeErrorKind::Message(e.to_string()) }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl core::fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.kind {
+            SerdeErrorKind::Message(msg) => write!(f, "{}", msg),
+            SerdeErrorKind::Syntax(e) => e.fmt(f),
+            SerdeErrorKind::KeyMustBeAString => write!(f, "map key must be a string"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::error::Error for SerdeError {}
@@ -1,7 +1,13 @@
 #![allow(dead_code)]
-use std::{str::FromStr, fmt::Display};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::{str::FromStr, fmt::Display};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
-use crate::error::{JsonParseError, ParseErrorKind};
+use crate::error::{FromJsonError, FromJsonErrorKind, JsonParseError};
 
 #[derive(Debug, PartialEq)]
 pub struct JsonNumber {
@@ -10,14 +16,143 @@ pub struct JsonNumber {
     pub(crate) exp: i128
 }
 
+/// Conversion from a [`JsonValue`].
+///
+/// Implementations for containers prepend the index or member name of a failing element
+/// to its error with [`FromJsonError::at`], so errors report where in the document they happened.
 pub trait FromJson: Sized {
-    type Err;
-    fn from_json(json: &JsonValue) -> Result<Self, Self::Err>; 
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError>;
 }
 
-#[derive(Debug, PartialEq)]
+impl FromJson for JsonValue {
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        Ok(json.clone())
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        json.as_bool().ok_or_else(|| FromJsonError::invalid_type("boolean", json))
+    }
+}
+
+impl FromJson for String {
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        json.as_str().map(str::to_string).ok_or_else(|| FromJsonError::invalid_type("string", json))
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        json.as_f64().ok_or_else(|| FromJsonError::invalid_type("number", json))
+    }
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl FromJson for crate::decimal::Decimal {
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        json.as_decimal().ok_or_else(|| FromJsonError::invalid_type("number", json))
+    }
+}
+
+impl FromJson for f32 {
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        f64::from_json(json).map(|n| n as f32)
+    }
+}
+
+macro_rules! from_json_integer {
+    ($($ty:ident)*) => {
+        $(
+            impl FromJson for $ty {
+                fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+                    match json {
+                        JsonValue::Number(n) if is_integer(*n) => {
+                            if *n >= $ty::MIN as f64 && *n < $ty::MAX as f64 + 1.0 {
+                                Ok(*n as $ty)
+                            } else {
+                                Err(FromJsonError::new(FromJsonErrorKind::OutOfRange(stringify!($ty))))
+                            }
+                        },
+                        #[cfg(feature = "arbitrary_precision")]
+                        JsonValue::Decimal(d) if d.is_integer() => d.to_i128()
+                            .and_then(|n| $ty::try_from(n).ok())
+                            .ok_or_else(|| FromJsonError::new(FromJsonErrorKind::OutOfRange(stringify!($ty)))),
+                        _ => Err(FromJsonError::invalid_type("integer", json)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_json_integer!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        match json {
+            JsonValue::Null => Ok(None),
+            _ => T::from_json(json).map(Some),
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Box<T> {
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        T::from_json(json).map(Box::new)
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        let values = json.as_array().ok_or_else(|| FromJsonError::invalid_type("array", json))?;
+        values.iter()
+            .enumerate()
+            .map(|(i, value)| T::from_json(value).map_err(|e| e.at(i)))
+            .collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: FromJson> FromJson for HashMap<String, T> {
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        let objects = json.as_objects().ok_or_else(|| FromJsonError::invalid_type("object", json))?;
+        objects.iter()
+            .map(|o| Ok((o.name.clone(), T::from_json(&o.value).map_err(|e| e.at(&o.name))?)))
+            .collect()
+    }
+}
+
+impl<T: FromJson> FromJson for BTreeMap<String, T> {
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        let objects = json.as_objects().ok_or_else(|| FromJsonError::invalid_type("object", json))?;
+        objects.iter()
+            .map(|o| Ok((o.name.clone(), T::from_json(&o.value).map_err(|e| e.at(&o.name))?)))
+            .collect()
+    }
+}
+
+/// Conversion of a borrowed value into a [`JsonValue`]. The `json!` macro uses it for interpolated values.
+///
+/// It is implemented for every `T: Clone + Into<JsonValue>`, and can be implemented for your own types.
+pub trait ToJson {
+    fn to_json(&self) -> JsonValue;
+}
+
+impl<T> ToJson for T where T: Clone + Into<JsonValue> {
+    fn to_json(&self) -> JsonValue {
+        self.clone().into()
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "arbitrary_precision"), derive(PartialEq))]
 pub enum JsonValue {
     Number(f64),
+    /// A number kept exactly as written. The parser produces these instead of `Number`
+    /// with the `arbitrary_precision` feature.
+    #[cfg(feature = "arbitrary_precision")]
+    Decimal(crate::decimal::Decimal),
     String(String),
     Objects(Vec<JsonObject>),
     Boolean(bool),
@@ -31,54 +166,189 @@ impl JsonValue {
     }
 
     pub fn is_number(&self) -> bool {
-        if let JsonValue::Number(_) = self {
-            true
-        } else {
-            false
+        match self {
+            JsonValue::Number(_) => true,
+            #[cfg(feature = "arbitrary_precision")]
+            JsonValue::Decimal(_) => true,
+            _ => false,
         }
     }
 
     pub fn is_string(&self) -> bool {
-        if let JsonValue::String(_) = self {
-            true
-        } else {
-            false
-        }
+        matches!(self, JsonValue::String(_))
     }
 
     pub fn is_objects(&self) -> bool {
-        if let JsonValue::Objects(_) = self {
-            true
-        } else {
-            false
-        }
+        matches!(self, JsonValue::Objects(_))
     }
 
     pub fn is_bool(&self) -> bool {
-        if let JsonValue::Boolean(_) = self {
-            true
-        } else {
-            false
-        }
+        matches!(self, JsonValue::Boolean(_))
     }
 
     pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, JsonValue::Array(_))
+    }
+
+    /// Parses `s` without stopping at the first error.
+    ///
+    /// The parser resumes at the next comma or closing bracket after an error and returns
+    /// a best-effort value together with all the errors found.
+    pub fn parse_tolerant(s: &str) -> ParseRecovery {
+        let (tokens, lex_errors) = crate::lexer::JsonLexer::new(s).tokenize_tolerant();
+        let (value, placeholders, mut errors) = crate::parser::JsonParser::tolerant(tokens, s.len()).get_value_tolerant();
+        errors.extend(lex_errors.into_iter().map(JsonParseError::from));
+        errors.sort_by_key(|e| e.span.start);
+        ParseRecovery { value, placeholders, errors }
+    }
+
+    pub fn deserialize<F: FromJson>(&self) -> Result<F, FromJsonError> {
+        FromJson::from_json(self)
+    }
+
+    /// Converts the object member `name`, for use in `FromJson` implementations of structs.
+    ///
+    /// Errors inside the member are reported at its path.
+    pub fn field<F: FromJson>(&self, name: &str) -> Result<F, FromJsonError> {
+        if !self.is_objects() {
+            return Err(FromJsonError::invalid_type("object", self));
+        }
+        match self.get(name) {
+            Some(value) => F::from_json(value).map_err(|e| e.at(name)),
+            None => Err(FromJsonError::missing_field(name)),
+        }
+    }
+
+    /// Like [`field`](Self::field), but a missing member or `null` gives `None`.
+    pub fn optional_field<F: FromJson>(&self, name: &str) -> Result<Option<F>, FromJsonError> {
+        if !self.is_objects() {
+            return Err(FromJsonError::invalid_type("object", self));
+        }
+        match self.get(name) {
+            Some(value) => Option::<F>::from_json(value).map_err(|e| e.at(name)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
         match self {
-            JsonValue::Null => true,
-            _ => false
+            JsonValue::Number(n) => Some(*n),
+            #[cfg(feature = "arbitrary_precision")]
+            JsonValue::Decimal(d) => Some(d.to_f64()),
+            _ => None
         }
     }
 
-    pub fn is_array(&self) -> bool {
-        if let JsonValue::Array(_) = self {
-            true
-        } else {
-            false
+    /// The exact value of a number. A finite `Number` gives its shortest round-trip digits.
+    #[cfg(feature = "arbitrary_precision")]
+    pub fn as_decimal(&self) -> Option<crate::decimal::Decimal> {
+        match self {
+            JsonValue::Number(n) => crate::decimal::Decimal::from_f64(*n),
+            JsonValue::Decimal(d) => Some(d.clone()),
+            _ => None
         }
     }
 
-    pub fn deserialize<F: FromJson>(&self) -> Result<F, F::Err> {
-        FromJson::from_json(self)
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Boolean(b) => Some(*b),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(a) => Some(a),
+            _ => None
+        }
+    }
+
+    pub fn as_objects(&self) -> Option<&Vec<JsonObject>> {
+        match self {
+            JsonValue::Objects(o) => Some(o),
+            _ => None
+        }
+    }
+
+    /// Returns the value of the first object member named `name`.
+    pub fn get(&self, name: &str) -> Option<&JsonValue> {
+        self.as_objects()?.iter().find(|o| o.name == name).map(|o| &o.value)
+    }
+
+    /// Looks up a value by a JSON Pointer such as `/users/0/name`.
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue> {
+        let tokens = crate::pointer::parse(pointer).ok()?;
+        crate::pointer::resolve(self, &tokens)
+    }
+
+    /// Selects the nodes matched by a JSONPath query such as `$..book[?@.price < 10]`.
+    pub fn query(&self, path: &str) -> Result<crate::jsonpath::NodeList<'_>, crate::error::JsonPathError> {
+        Ok(crate::jsonpath::JsonPath::parse(path)?.query(self))
+    }
+
+    /// Returns the JSON type name of the value, such as `"object"` or `"number"`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            JsonValue::Number(_) => "number",
+            #[cfg(feature = "arbitrary_precision")]
+            JsonValue::Decimal(_) => "number",
+            JsonValue::String(_) => "string",
+            JsonValue::Objects(_) => "object",
+            JsonValue::Boolean(_) => "boolean",
+            JsonValue::Array(_) => "array",
+            JsonValue::Null => "null",
+        }
+    }
+
+    /// Compares two values as JSON data, ignoring the order of object members.
+    pub fn equivalent(&self, other: &JsonValue) -> bool {
+        match (self, other) {
+            (JsonValue::Objects(a), JsonValue::Objects(b)) => {
+                a.len() == b.len() && a.iter().all(|o| other.get(&o.name).is_some_and(|v| o.value.equivalent(v)))
+            },
+            (JsonValue::Array(a), JsonValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.equivalent(b))
+            },
+            _ => self == other
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl From<crate::decimal::Decimal> for JsonValue {
+    fn from(d: crate::decimal::Decimal) -> Self {
+        Self::Decimal(d)
+    }
+}
+
+/// Numbers are equal if they have the same value, whether `Number` or `Decimal`.
+#[cfg(feature = "arbitrary_precision")]
+impl PartialEq for JsonValue {
+    fn eq(&self, other: &JsonValue) -> bool {
+        match (self, other) {
+            (JsonValue::Number(a), JsonValue::Number(b)) => a == b,
+            (JsonValue::Decimal(_), _) | (_, JsonValue::Decimal(_)) => match (self.as_decimal(), other.as_decimal()) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            },
+            (JsonValue::String(a), JsonValue::String(b)) => a == b,
+            (JsonValue::Objects(a), JsonValue::Objects(b)) => a == b,
+            (JsonValue::Boolean(a), JsonValue::Boolean(b)) => a == b,
+            (JsonValue::Array(a), JsonValue::Array(b)) => a == b,
+            (JsonValue::Null, JsonValue::Null) => true,
+            _ => false,
+        }
     }
 }
 
@@ -88,15 +358,25 @@ impl From<f64> for JsonValue {
     }
 }
 
+macro_rules! from_number {
+    ($($t:ty)*) => {
+        $(
+            impl From<$t> for JsonValue {
+                fn from(v: $t) -> Self {
+                    Self::Number(v as f64)
+                }
+            }
+        )*
+    };
+}
+
+from_number!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize f32);
+
 impl TryInto<f64> for JsonValue {
     type Error = ();
 
     fn try_into(self) -> Result<f64, Self::Error> {
-        if let JsonValue::Number(n) = self {
-            Ok(n)
-        } else {
-            Err(())
-        }
+        self.as_f64().ok_or(())
     }
 }
 
@@ -152,34 +432,42 @@ impl<T> From<(String, T)> for JsonValue where T: Into<JsonValue> {
 }
 
 impl Display for JsonValue {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Number(n) => write!(f, "{}", n),
-            Self::String(s) => write!(f, "\"{}\"", s),
-            Self::Objects(o) => write!(f, "{{{}}}", o.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")),
-            Self::Boolean(b) => write!(f, "{}", b),
-            Self::Array(a) => write!(f, "[{}]", a.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")),
-            Self::Null => write!(f, "null"),
-        }
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::writer::write_value(f, self, &crate::writer::WriteOptions::default())
     }
 }
 
 impl FromStr for JsonValue {
     type Err = crate::error::JsonParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        #[cfg(feature = "simd")]
+        if let Some(tokens) = crate::lexer::JsonLexer::new(s).tokenize_indexed() {
+            return crate::parser::JsonParser::new(tokens, s.len()).get_value();
+        }
         let tokens = crate::lexer::JsonLexer::new(s).tokenize();
         match tokens {
             Ok(tokens) => {
-                return crate::parser::JsonParser::new(tokens).get_value();
+                crate::parser::JsonParser::new(tokens, s.len()).get_value()
             },
             Err(e) => {
-                return Err( JsonParseError { kind: ParseErrorKind::LexError(e)});
+                Err(e.into())
             }
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// Result of [`JsonValue::parse_tolerant`].
+#[derive(Debug)]
+pub struct ParseRecovery {
+    /// The value read, with `null` in place of anything that could not be parsed.
+    pub value: JsonValue,
+    /// JSON Pointers of the `null` placeholders in `value`.
+    pub placeholders: Vec<String>,
+    /// Every error found, in source order.
+    pub errors: Vec<JsonParseError>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct JsonObject {
     pub name: String,
     pub value: JsonValue
@@ -204,7 +492,31 @@ impl<T> From<(String, T)> for JsonObject where T: Into<JsonValue> {
 }
 
 impl Display for JsonObject {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "\"{}\":{}", self.name, self.value.to_string())
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        crate::writer::write_string(f, &self.name)?;
+        write!(f, ":{}", self.value)
     }
-}
\ No newline at end of file
+}
+/// Above this magnitude every `f64` is a whole number.
+const INTEGER_LIMIT: f64 = 4503599627370496.0;
+
+/// Whether `n` is a finite whole number. `f64::fract` is only available with `std`.
+pub(crate) fn is_integer(n: f64) -> bool {
+    n.is_finite() && (n.abs() >= INTEGER_LIMIT || n == n as i64 as f64)
+}
+
+/// Rounds half away from zero, like `f64::round`, which is only available with `std`.
+pub(crate) fn round(n: f64) -> f64 {
+    if !n.is_finite() || n.abs() >= INTEGER_LIMIT {
+        return n;
+    }
+    let truncated = n as i64 as f64;
+    let rest = n - truncated;
+    if rest >= 0.5 {
+        truncated + 1.0
+    } else if rest <= -0.5 {
+        truncated - 1.0
+    } else {
+        truncated
+    }
+}
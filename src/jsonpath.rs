@@ -0,0 +1,1021 @@
+//! JSONPath ([RFC 9535](https://www.rfc-editor.org/rfc/rfc9535)) queries.
+//!
+//! A query selects a list of nodes from a [`JsonValue`]. Each node borrows its value from the
+//! queried document and carries its normalized path, such as `$['store']['book'][0]`.
+//!
+//! ```
+//! use crystalrake::json::JsonValue;
+//! use crystalrake::jsonpath::JsonPath;
+//!
+//! let json: JsonValue = r#"{"book": [{"price": 8, "title": "A"}, {"price": 12, "title": "B"}]}"#.parse().unwrap();
+//! let path: JsonPath = "$.book[?@.price < 10].title".parse().unwrap();
+//! let nodes = path.query(&json);
+//! assert_eq!(nodes.values(), vec![&JsonValue::from("A")]);
+//! assert_eq!(nodes.iter().next().unwrap().path().to_string(), "$['book'][0]['title']");
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Display;
+use core::str::FromStr;
+
+use crate::error::{JsonPathError, JsonPathErrorKind};
+use crate::json::JsonValue;
+use crate::regex::Regex;
+
+/// Largest index allowed in a query, `2^53 - 1`.
+const MAX_INDEX: i64 = (1 << 53) - 1;
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Child(Vec<Selector>),
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Debug, Clone)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice { start: Option<i64>, end: Option<i64>, step: Option<i64> },
+    Filter(LogicalExpr),
+}
+
+#[derive(Debug, Clone)]
+struct Query {
+    relative: bool,
+    segments: Vec<Segment>,
+}
+
+impl Query {
+    /// A singular query selects at most one node: it only uses name and index selectors.
+    fn is_singular(&self) -> bool {
+        self.segments.iter().all(|segment| match segment {
+            Segment::Child(selectors) => selectors.len() == 1 && matches!(selectors[0], Selector::Name(_) | Selector::Index(_)),
+            Segment::Descendant(_) => false,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ComparisonOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum LogicalExpr {
+    Or(Vec<LogicalExpr>),
+    And(Vec<LogicalExpr>),
+    Not(Box<LogicalExpr>),
+    Comparison(Comparable, ComparisonOp, Comparable),
+    Query(Query),
+    Function(FunctionExpr),
+}
+
+#[derive(Debug, Clone)]
+enum Comparable {
+    Literal(JsonValue),
+    Query(Query),
+    Function(FunctionExpr),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Length,
+    Count,
+    Match,
+    Search,
+    Value,
+}
+
+/// The declared types of function parameters and results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FunctionType {
+    Value,
+    Logical,
+    Nodes,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        match name {
+            "length" => Some(Function::Length),
+            "count" => Some(Function::Count),
+            "match" => Some(Function::Match),
+            "search" => Some(Function::Search),
+            "value" => Some(Function::Value),
+            _ => None,
+        }
+    }
+
+    fn parameters(&self) -> &'static [FunctionType] {
+        match self {
+            Function::Length => &[FunctionType::Value],
+            Function::Count | Function::Value => &[FunctionType::Nodes],
+            Function::Match | Function::Search => &[FunctionType::Value, FunctionType::Value],
+        }
+    }
+
+    fn result(&self) -> FunctionType {
+        match self {
+            Function::Length | Function::Count | Function::Value => FunctionType::Value,
+            Function::Match | Function::Search => FunctionType::Logical,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Argument {
+    Literal(JsonValue),
+    Query(Query),
+    Function(FunctionExpr),
+}
+
+#[derive(Debug, Clone)]
+struct FunctionExpr {
+    function: Function,
+    arguments: Vec<Argument>,
+}
+
+/// One element of a normalized path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathElement {
+    Name(String),
+    Index(usize),
+}
+
+/// The location of a node in the queried value, such as `$['store']['book'][0]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct NormalizedPath {
+    elements: Vec<PathElement>,
+}
+
+impl NormalizedPath {
+    pub fn elements(&self) -> &[PathElement] {
+        &self.elements
+    }
+
+    /// Converts the path into a JSON Pointer such as `/store/book/0`.
+    pub fn to_pointer(&self) -> String {
+        crate::pointer::join(self.elements.iter().map(|element| match element {
+            PathElement::Name(name) => name.clone(),
+            PathElement::Index(index) => index.to_string(),
+        }))
+    }
+
+    fn child(&self, element: PathElement) -> NormalizedPath {
+        let mut elements = self.elements.clone();
+        elements.push(element);
+        NormalizedPath { elements }
+    }
+}
+
+impl Display for NormalizedPath {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "$")?;
+        for element in &self.elements {
+            match element {
+                PathElement::Index(index) => write!(f, "[{}]", index)?,
+                PathElement::Name(name) => {
+                    write!(f, "['")?;
+                    for c in name.chars() {
+                        match c {
+                            '\u{8}' => write!(f, "\\b")?,
+                            '\u{c}' => write!(f, "\\f")?,
+                            '\n' => write!(f, "\\n")?,
+                            '\r' => write!(f, "\\r")?,
+                            '\t' => write!(f, "\\t")?,
+                            '\'' => write!(f, "\\'")?,
+                            '\\' => write!(f, "\\\\")?,
+                            '\u{0}'..='\u{1f}' => write!(f, "\\u{:04x}", c as u32)?,
+                            _ => write!(f, "{}", c)?,
+                        }
+                    }
+                    write!(f, "']")?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A value selected by a query together with its location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node<'a> {
+    path: NormalizedPath,
+    value: &'a JsonValue,
+}
+
+impl<'a> Node<'a> {
+    pub fn path(&self) -> &NormalizedPath {
+        &self.path
+    }
+
+    pub fn value(&self) -> &'a JsonValue {
+        self.value
+    }
+}
+
+/// The nodes selected by a query, in document order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NodeList<'a> {
+    nodes: Vec<Node<'a>>,
+}
+
+impl<'a> NodeList<'a> {
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, Node<'a>> {
+        self.nodes.iter()
+    }
+
+    pub fn values(&self) -> Vec<&'a JsonValue> {
+        self.nodes.iter().map(|node| node.value).collect()
+    }
+
+    pub fn paths(&self) -> Vec<&NormalizedPath> {
+        self.nodes.iter().map(|node| &node.path).collect()
+    }
+}
+
+impl<'a> IntoIterator for NodeList<'a> {
+    type Item = Node<'a>;
+
+    type IntoIter = alloc::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.nodes.into_iter()
+    }
+}
+
+/// A parsed JSONPath query.
+#[derive(Debug, Clone)]
+pub struct JsonPath {
+    query: Query,
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<JsonPath, JsonPathError> {
+        let mut parser = PathParser { chars: path.chars().collect(), position: 0 };
+        let query = parser.root_query()?;
+        if parser.position < parser.chars.len() {
+            return Err(parser.unexpected());
+        }
+        Ok(JsonPath { query })
+    }
+
+    /// Selects the nodes of `value` matched by the query.
+    pub fn query<'a>(&self, value: &'a JsonValue) -> NodeList<'a> {
+        let root = Node { path: NormalizedPath::default(), value };
+        NodeList { nodes: evaluate_segments(&self.query.segments, vec![root], value) }
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = JsonPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        JsonPath::parse(s)
+    }
+}
+
+struct PathParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl PathParser {
+    fn error(&self, kind: JsonPathErrorKind) -> JsonPathError {
+        JsonPathError { kind, position: self.position }
+    }
+
+    fn unexpected(&self) -> JsonPathError {
+        match self.peek() {
+            Some(c) => self.error(JsonPathErrorKind::UnexpectedChar(c)),
+            None => self.error(JsonPathErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let len = s.chars().count();
+        if self.chars.len() >= self.position + len && s.chars().eq(self.chars[self.position..self.position + len].iter().copied()) {
+            self.position += len;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), JsonPathError> {
+        if self.eat(c) { Ok(()) } else { Err(self.unexpected()) }
+    }
+
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.position += 1;
+        }
+    }
+
+    /// Parses a top-level query, which must start at the root; `@` only exists inside filters.
+    fn root_query(&mut self) -> Result<Query, JsonPathError> {
+        if self.peek() != Some('$') {
+            return Err(self.unexpected());
+        }
+        self.query()
+    }
+
+    /// Parses `$` or `@` followed by segments.
+    fn query(&mut self) -> Result<Query, JsonPathError> {
+        let relative = match self.peek() {
+            Some('$') => false,
+            Some('@') => true,
+            _ => return Err(self.unexpected()),
+        };
+        self.position += 1;
+        let mut segments = Vec::new();
+        loop {
+            let save = self.position;
+            self.skip_blank();
+            match self.peek() {
+                Some('[') | Some('.') => segments.push(self.segment()?),
+                _ => {
+                    self.position = save;
+                    break;
+                }
+            }
+        }
+        Ok(Query { relative, segments })
+    }
+
+    fn segment(&mut self) -> Result<Segment, JsonPathError> {
+        if self.eat_str("..") {
+            match self.peek() {
+                Some('[') => Ok(Segment::Descendant(self.bracketed_selection()?)),
+                Some('*') => {
+                    self.position += 1;
+                    Ok(Segment::Descendant(vec![Selector::Wildcard]))
+                },
+                _ => Ok(Segment::Descendant(vec![Selector::Name(self.member_name()?)])),
+            }
+        } else if self.eat('.') {
+            if self.eat('*') {
+                Ok(Segment::Child(vec![Selector::Wildcard]))
+            } else {
+                Ok(Segment::Child(vec![Selector::Name(self.member_name()?)]))
+            }
+        } else {
+            Ok(Segment::Child(self.bracketed_selection()?))
+        }
+    }
+
+    fn member_name(&mut self) -> Result<String, JsonPathError> {
+        let is_first = |c: char| c.is_ascii_alphabetic() || c == '_' || !c.is_ascii();
+        match self.peek() {
+            Some(c) if is_first(c) => {},
+            _ => return Err(self.unexpected()),
+        }
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if is_first(c) || c.is_ascii_digit() {
+                name.push(c);
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+        Ok(name)
+    }
+
+    fn bracketed_selection(&mut self) -> Result<Vec<Selector>, JsonPathError> {
+        self.expect('[')?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_blank();
+            selectors.push(self.selector()?);
+            self.skip_blank();
+            if self.eat(']') {
+                return Ok(selectors);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, JsonPathError> {
+        match self.peek() {
+            Some('\'') | Some('"') => Ok(Selector::Name(self.string_literal()?)),
+            Some('*') => {
+                self.position += 1;
+                Ok(Selector::Wildcard)
+            },
+            Some('?') => {
+                self.position += 1;
+                self.skip_blank();
+                Ok(Selector::Filter(self.logical_or()?))
+            },
+            Some(':') | Some('-') | Some('0'..='9') => {
+                let start = if self.peek() == Some(':') { None } else { Some(self.integer()?) };
+                self.skip_blank();
+                if !self.eat(':') {
+                    return Ok(Selector::Index(start.unwrap()));
+                }
+                self.skip_blank();
+                let end = if matches!(self.peek(), Some('-' | '0'..='9')) { Some(self.integer()?) } else { None };
+                self.skip_blank();
+                let mut step = None;
+                if self.eat(':') {
+                    self.skip_blank();
+                    if matches!(self.peek(), Some('-' | '0'..='9')) {
+                        step = Some(self.integer()?);
+                    }
+                }
+                Ok(Selector::Slice { start, end, step })
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64, JsonPathError> {
+        let start = self.position;
+        let negative = self.eat('-');
+        let digits_start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        let digits = String::from_iter(&self.chars[digits_start..self.position]);
+        let leading_zero = digits.len() > 1 && digits.starts_with('0');
+        if digits.is_empty() || leading_zero || (negative && digits == "0") {
+            self.position = start;
+            return Err(self.error(JsonPathErrorKind::InvalidNumber));
+        }
+        match digits.parse::<i64>() {
+            Ok(n) if n <= MAX_INDEX => Ok(if negative { -n } else { n }),
+            _ => {
+                self.position = start;
+                Err(self.error(JsonPathErrorKind::InvalidNumber))
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonPathError> {
+        if self.position + 4 > self.chars.len() || !self.chars[self.position..self.position + 4].iter().all(|c| c.is_ascii_hexdigit()) {
+            return Err(self.error(JsonPathErrorKind::InvalidEscape));
+        }
+        let hex = String::from_iter(&self.chars[self.position..self.position + 4]);
+        self.position += 4;
+        Ok(u32::from_str_radix(&hex, 16).unwrap())
+    }
+
+    fn string_literal(&mut self) -> Result<String, JsonPathError> {
+        let quote = self.peek().unwrap();
+        self.position += 1;
+        let mut buf = String::new();
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error(JsonPathErrorKind::UnexpectedEnd)),
+            };
+            self.position += 1;
+            match c {
+                c if c == quote => return Ok(buf),
+                '\\' => {
+                    let escaped = match self.peek() {
+                        Some(c) => c,
+                        None => return Err(self.error(JsonPathErrorKind::UnexpectedEnd)),
+                    };
+                    self.position += 1;
+                    match escaped {
+                        'b' => buf.push('\u{8}'),
+                        'f' => buf.push('\u{c}'),
+                        'n' => buf.push('\n'),
+                        'r' => buf.push('\r'),
+                        't' => buf.push('\t'),
+                        '/' => buf.push('/'),
+                        '\\' => buf.push('\\'),
+                        c if c == quote => buf.push(c),
+                        'u' => {
+                            let high = self.hex4()?;
+                            let code = if (0xD800..0xDC00).contains(&high) {
+                                if !self.eat_str("\\u") {
+                                    return Err(self.error(JsonPathErrorKind::InvalidEscape));
+                                }
+                                let low = self.hex4()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(self.error(JsonPathErrorKind::InvalidEscape));
+                                }
+                                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                            } else {
+                                high
+                            };
+                            match char::from_u32(code) {
+                                Some(c) => buf.push(c),
+                                None => return Err(self.error(JsonPathErrorKind::InvalidEscape)),
+                            }
+                        },
+                        _ => return Err(self.error(JsonPathErrorKind::InvalidEscape)),
+                    }
+                },
+                '\u{0}'..='\u{1f}' => {
+                    self.position -= 1;
+                    return Err(self.unexpected());
+                },
+                c => buf.push(c),
+            }
+        }
+    }
+
+    fn logical_or(&mut self) -> Result<LogicalExpr, JsonPathError> {
+        let mut operands = vec![self.logical_and()?];
+        loop {
+            let save = self.position;
+            self.skip_blank();
+            if self.eat_str("||") {
+                self.skip_blank();
+                operands.push(self.logical_and()?);
+            } else {
+                self.position = save;
+                break;
+            }
+        }
+        Ok(if operands.len() == 1 { operands.pop().unwrap() } else { LogicalExpr::Or(operands) })
+    }
+
+    fn logical_and(&mut self) -> Result<LogicalExpr, JsonPathError> {
+        let mut operands = vec![self.basic_expr()?];
+        loop {
+            let save = self.position;
+            self.skip_blank();
+            if self.eat_str("&&") {
+                self.skip_blank();
+                operands.push(self.basic_expr()?);
+            } else {
+                self.position = save;
+                break;
+            }
+        }
+        Ok(if operands.len() == 1 { operands.pop().unwrap() } else { LogicalExpr::And(operands) })
+    }
+
+    fn basic_expr(&mut self) -> Result<LogicalExpr, JsonPathError> {
+        if self.eat('!') {
+            self.skip_blank();
+            let expr = if self.peek() == Some('(') {
+                self.paren_expr()?
+            } else {
+                let start = self.position;
+                match self.comparable()? {
+                    Comparable::Query(query) => LogicalExpr::Query(query),
+                    Comparable::Function(function) => self.test_function(function, start)?,
+                    Comparable::Literal(_) => {
+                        self.position = start;
+                        return Err(self.unexpected());
+                    }
+                }
+            };
+            return Ok(LogicalExpr::Not(Box::new(expr)));
+        }
+        if self.peek() == Some('(') {
+            return self.paren_expr();
+        }
+        let start = self.position;
+        let left = self.comparable()?;
+        let save = self.position;
+        self.skip_blank();
+        let op = if self.eat_str("==") {
+            ComparisonOp::Eq
+        } else if self.eat_str("!=") {
+            ComparisonOp::Ne
+        } else if self.eat_str("<=") {
+            ComparisonOp::Le
+        } else if self.eat_str(">=") {
+            ComparisonOp::Ge
+        } else if self.eat('<') {
+            ComparisonOp::Lt
+        } else if self.eat('>') {
+            ComparisonOp::Gt
+        } else {
+            self.position = save;
+            return match left {
+                Comparable::Query(query) => Ok(LogicalExpr::Query(query)),
+                Comparable::Function(function) => self.test_function(function, start),
+                Comparable::Literal(_) => Err(self.unexpected()),
+            };
+        };
+        self.check_comparable(&left, start)?;
+        self.skip_blank();
+        let right_start = self.position;
+        let right = self.comparable()?;
+        self.check_comparable(&right, right_start)?;
+        Ok(LogicalExpr::Comparison(left, op, right))
+    }
+
+    fn paren_expr(&mut self) -> Result<LogicalExpr, JsonPathError> {
+        self.expect('(')?;
+        self.skip_blank();
+        let expr = self.logical_or()?;
+        self.skip_blank();
+        self.expect(')')?;
+        Ok(expr)
+    }
+
+    /// A function used as a test expression must return a logical value or nodes.
+    fn test_function(&self, function: FunctionExpr, start: usize) -> Result<LogicalExpr, JsonPathError> {
+        if function.function.result() == FunctionType::Value {
+            return Err(JsonPathError { kind: JsonPathErrorKind::NotWellTyped, position: start });
+        }
+        Ok(LogicalExpr::Function(function))
+    }
+
+    fn check_comparable(&self, comparable: &Comparable, start: usize) -> Result<(), JsonPathError> {
+        match comparable {
+            Comparable::Query(query) if !query.is_singular() => {
+                Err(JsonPathError { kind: JsonPathErrorKind::NonSingularQuery, position: start })
+            },
+            Comparable::Function(function) if function.function.result() != FunctionType::Value => {
+                Err(JsonPathError { kind: JsonPathErrorKind::NotWellTyped, position: start })
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn comparable(&mut self) -> Result<Comparable, JsonPathError> {
+        match self.peek() {
+            Some('$') | Some('@') => Ok(Comparable::Query(self.query()?)),
+            Some('\'') | Some('"') => Ok(Comparable::Literal(JsonValue::String(self.string_literal()?))),
+            Some('-') | Some('0'..='9') => Ok(Comparable::Literal(JsonValue::Number(self.number()?))),
+            Some(c) if c.is_ascii_lowercase() => {
+                let start = self.position;
+                let mut name = String::new();
+                while let Some(c) = self.peek().filter(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '_') {
+                    name.push(c);
+                    self.position += 1;
+                }
+                if self.peek() == Some('(') {
+                    return Ok(Comparable::Function(self.function(name, start)?));
+                }
+                match name.as_str() {
+                    "true" => Ok(Comparable::Literal(JsonValue::Boolean(true))),
+                    "false" => Ok(Comparable::Literal(JsonValue::Boolean(false))),
+                    "null" => Ok(Comparable::Literal(JsonValue::Null)),
+                    _ => {
+                        self.position = start;
+                        Err(self.unexpected())
+                    }
+                }
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, JsonPathError> {
+        let start = self.position;
+        self.eat('-');
+        let int_start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        let int_len = self.position - int_start;
+        let mut valid = int_len == 1 || (int_len > 1 && self.chars[int_start] != '0');
+        if self.eat('.') {
+            let frac_start = self.position;
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.position += 1;
+            }
+            valid &= self.position > frac_start;
+        }
+        if self.peek() == Some('e') || self.peek() == Some('E') {
+            self.position += 1;
+            if !self.eat('-') {
+                self.eat('+');
+            }
+            let exp_start = self.position;
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.position += 1;
+            }
+            valid &= self.position > exp_start;
+        }
+        let text = String::from_iter(&self.chars[start..self.position]);
+        match text.parse() {
+            Ok(n) if valid => Ok(n),
+            _ => {
+                self.position = start;
+                Err(self.error(JsonPathErrorKind::InvalidNumber))
+            }
+        }
+    }
+
+    fn function(&mut self, name: String, start: usize) -> Result<FunctionExpr, JsonPathError> {
+        let function = match Function::from_name(&name) {
+            Some(function) => function,
+            None => return Err(JsonPathError { kind: JsonPathErrorKind::UnknownFunction(name), position: start }),
+        };
+        self.expect('(')?;
+        self.skip_blank();
+        let mut arguments = Vec::new();
+        if !self.eat(')') {
+            loop {
+                let argument_start = self.position;
+                let argument = self.argument()?;
+                let parameter = function.parameters().get(arguments.len()).copied();
+                self.check_argument(&argument, parameter, argument_start, &name)?;
+                arguments.push(argument);
+                self.skip_blank();
+                if self.eat(')') {
+                    break;
+                }
+                self.expect(',')?;
+                self.skip_blank();
+            }
+        }
+        if arguments.len() != function.parameters().len() {
+            return Err(JsonPathError { kind: JsonPathErrorKind::ArgumentCount(name), position: start });
+        }
+        Ok(FunctionExpr { function, arguments })
+    }
+
+    fn argument(&mut self) -> Result<Argument, JsonPathError> {
+        Ok(match self.comparable()? {
+            Comparable::Literal(value) => Argument::Literal(value),
+            Comparable::Query(query) => Argument::Query(query),
+            Comparable::Function(function) => Argument::Function(function),
+        })
+    }
+
+    fn check_argument(&self, argument: &Argument, parameter: Option<FunctionType>, start: usize, name: &str) -> Result<(), JsonPathError> {
+        let well_typed = match (parameter, argument) {
+            (None, _) => return Err(JsonPathError { kind: JsonPathErrorKind::ArgumentCount(name.to_string()), position: start }),
+            (Some(FunctionType::Value), Argument::Literal(_)) => true,
+            (Some(FunctionType::Value), Argument::Query(query)) => query.is_singular(),
+            (Some(FunctionType::Value), Argument::Function(f)) => f.function.result() == FunctionType::Value,
+            (Some(FunctionType::Nodes), Argument::Query(_)) => true,
+            (Some(FunctionType::Logical), Argument::Query(_)) => true,
+            (Some(FunctionType::Logical), Argument::Function(f)) => f.function.result() != FunctionType::Value,
+            _ => false,
+        };
+        if well_typed {
+            Ok(())
+        } else {
+            Err(JsonPathError { kind: JsonPathErrorKind::NotWellTyped, position: start })
+        }
+    }
+}
+
+fn evaluate_segments<'a>(segments: &[Segment], mut nodes: Vec<Node<'a>>, root: &'a JsonValue) -> Vec<Node<'a>> {
+    for segment in segments {
+        let mut selected = Vec::new();
+        for node in &nodes {
+            match segment {
+                Segment::Child(selectors) => {
+                    for selector in selectors {
+                        select(selector, node, root, &mut selected);
+                    }
+                },
+                Segment::Descendant(selectors) => {
+                    let mut nodes = Vec::new();
+                    descendants(node, &mut nodes);
+                    for descendant in nodes {
+                        for selector in selectors {
+                            select(selector, &descendant, root, &mut selected);
+                        }
+                    }
+                },
+            }
+        }
+        nodes = selected;
+    }
+    nodes
+}
+
+/// Pushes `node` followed by all of its descendants in document order.
+fn descendants<'a>(node: &Node<'a>, out: &mut Vec<Node<'a>>) {
+    out.push(node.clone());
+    for child in children(node) {
+        descendants(&child, out);
+    }
+}
+
+fn children<'a>(node: &Node<'a>) -> Vec<Node<'a>> {
+    match node.value {
+        JsonValue::Objects(members) => members.iter()
+            .map(|member| Node { path: node.path.child(PathElement::Name(member.name.clone())), value: &member.value })
+            .collect(),
+        JsonValue::Array(values) => values.iter().enumerate()
+            .map(|(i, value)| Node { path: node.path.child(PathElement::Index(i)), value })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn normalize_index(index: i64, len: usize) -> Option<usize> {
+    let len = len as i64;
+    let index = if index < 0 { len + index } else { index };
+    if 0 <= index && index < len { Some(index as usize) } else { None }
+}
+
+fn select<'a>(selector: &Selector, node: &Node<'a>, root: &'a JsonValue, selected: &mut Vec<Node<'a>>) {
+    match (selector, node.value) {
+        (Selector::Name(name), JsonValue::Objects(members)) => {
+            if let Some(member) = members.iter().find(|member| member.name == *name) {
+                selected.push(Node { path: node.path.child(PathElement::Name(name.clone())), value: &member.value });
+            }
+        },
+        (Selector::Wildcard, _) => selected.extend(children(node)),
+        (Selector::Index(index), JsonValue::Array(values)) => {
+            if let Some(i) = normalize_index(*index, values.len()) {
+                selected.push(Node { path: node.path.child(PathElement::Index(i)), value: &values[i] });
+            }
+        },
+        (Selector::Slice { start, end, step }, JsonValue::Array(values)) => {
+            let len = values.len() as i64;
+            let step = step.unwrap_or(1);
+            if step == 0 {
+                return;
+            }
+            let normalize = |i: i64| if i >= 0 { i } else { len + i };
+            let push = |i: i64, selected: &mut Vec<Node<'a>>| {
+                let i = i as usize;
+                selected.push(Node { path: node.path.child(PathElement::Index(i)), value: &values[i] });
+            };
+            if step > 0 {
+                let lower = start.map_or(0, normalize).clamp(0, len);
+                let upper = end.map_or(len, normalize).clamp(0, len);
+                let mut i = lower;
+                while i < upper {
+                    push(i, selected);
+                    i += step;
+                }
+            } else {
+                let upper = start.map_or(len - 1, normalize).clamp(-1, len - 1);
+                let lower = end.map_or(-len - 1, normalize).clamp(-1, len - 1);
+                let mut i = upper;
+                while lower < i {
+                    push(i, selected);
+                    i += step;
+                }
+            }
+        },
+        (Selector::Filter(expr), JsonValue::Objects(_) | JsonValue::Array(_)) => {
+            for child in children(node) {
+                if test(expr, child.value, root) {
+                    selected.push(child);
+                }
+            }
+        },
+        _ => {},
+    }
+}
+
+fn query_nodes<'a>(query: &Query, current: &'a JsonValue, root: &'a JsonValue) -> Vec<Node<'a>> {
+    let start = if query.relative { current } else { root };
+    evaluate_segments(&query.segments, vec![Node { path: NormalizedPath::default(), value: start }], root)
+}
+
+fn test(expr: &LogicalExpr, current: &JsonValue, root: &JsonValue) -> bool {
+    match expr {
+        LogicalExpr::Or(operands) => operands.iter().any(|e| test(e, current, root)),
+        LogicalExpr::And(operands) => operands.iter().all(|e| test(e, current, root)),
+        LogicalExpr::Not(e) => !test(e, current, root),
+        LogicalExpr::Query(query) => !query_nodes(query, current, root).is_empty(),
+        LogicalExpr::Function(function) => match call(function, current, root) {
+            FunctionResult::Logical(b) => b,
+            FunctionResult::Value(_) => false,
+        },
+        LogicalExpr::Comparison(left, op, right) => {
+            let left = comparable_value(left, current, root);
+            let right = comparable_value(right, current, root);
+            compare(left.as_deref(), *op, right.as_deref())
+        },
+    }
+}
+
+fn comparable_value<'a>(comparable: &'a Comparable, current: &'a JsonValue, root: &'a JsonValue) -> Option<Cow<'a, JsonValue>> {
+    match comparable {
+        Comparable::Literal(value) => Some(Cow::Borrowed(value)),
+        Comparable::Query(query) => query_nodes(query, current, root).first().map(|node| Cow::Borrowed(node.value)),
+        Comparable::Function(function) => match call(function, current, root) {
+            FunctionResult::Value(value) => value,
+            _ => None,
+        },
+    }
+}
+
+fn compare(left: Option<&JsonValue>, op: ComparisonOp, right: Option<&JsonValue>) -> bool {
+    match op {
+        ComparisonOp::Eq => equal(left, right),
+        ComparisonOp::Ne => !equal(left, right),
+        ComparisonOp::Lt => less(left, right),
+        ComparisonOp::Le => less(left, right) || equal(left, right),
+        ComparisonOp::Gt => less(right, left),
+        ComparisonOp::Ge => less(right, left) || equal(left, right),
+    }
+}
+
+fn equal(left: Option<&JsonValue>, right: Option<&JsonValue>) -> bool {
+    match (left, right) {
+        (None, None) => true,
+        (Some(left), Some(right)) => left.equivalent(right),
+        _ => false,
+    }
+}
+
+fn less(left: Option<&JsonValue>, right: Option<&JsonValue>) -> bool {
+    match (left, right) {
+        (Some(JsonValue::Number(a)), Some(JsonValue::Number(b))) => a < b,
+        #[cfg(feature = "arbitrary_precision")]
+        (Some(a), Some(b)) if a.is_number() && b.is_number() => matches!((a.as_decimal(), b.as_decimal()), (Some(a), Some(b)) if a < b),
+        (Some(JsonValue::String(a)), Some(JsonValue::String(b))) => a < b,
+        _ => false,
+    }
+}
+
+enum FunctionResult<'a> {
+    Value(Option<Cow<'a, JsonValue>>),
+    Logical(bool),
+}
+
+fn value_argument<'a>(argument: &'a Argument, current: &'a JsonValue, root: &'a JsonValue) -> Option<Cow<'a, JsonValue>> {
+    match argument {
+        Argument::Literal(value) => Some(Cow::Borrowed(value)),
+        Argument::Query(query) => query_nodes(query, current, root).first().map(|node| Cow::Borrowed(node.value)),
+        Argument::Function(function) => match call(function, current, root) {
+            FunctionResult::Value(value) => value,
+            _ => None,
+        },
+    }
+}
+
+fn nodes_argument<'a>(argument: &'a Argument, current: &'a JsonValue, root: &'a JsonValue) -> Vec<Node<'a>> {
+    match argument {
+        Argument::Query(query) => query_nodes(query, current, root),
+        _ => Vec::new(),
+    }
+}
+
+fn regex_test(function: Function, text: Option<&JsonValue>, pattern: Option<&JsonValue>) -> bool {
+    match (text, pattern) {
+        (Some(JsonValue::String(text)), Some(JsonValue::String(pattern))) => match Regex::new(pattern) {
+            Ok(regex) if function == Function::Match => regex.is_full_match(text),
+            Ok(regex) => regex.is_match(text),
+            Err(_) => false,
+        },
+        _ => false,
+    }
+}
+
+fn call<'a>(function: &'a FunctionExpr, current: &'a JsonValue, root: &'a JsonValue) -> FunctionResult<'a> {
+    let args = &function.arguments;
+    match function.function {
+        Function::Length => {
+            let length = match value_argument(&args[0], current, root).as_deref() {
+                Some(JsonValue::String(s)) => Some(s.chars().count()),
+                Some(JsonValue::Array(a)) => Some(a.len()),
+                Some(JsonValue::Objects(o)) => Some(o.len()),
+                _ => None,
+            };
+            FunctionResult::Value(length.map(|n| Cow::Owned(JsonValue::Number(n as f64))))
+        },
+        Function::Count => {
+            let count = nodes_argument(&args[0], current, root).len();
+            FunctionResult::Value(Some(Cow::Owned(JsonValue::Number(count as f64))))
+        },
+        Function::Match | Function::Search => {
+            let text = value_argument(&args[0], current, root);
+            let pattern = value_argument(&args[1], current, root);
+            FunctionResult::Logical(regex_test(function.function, text.as_deref(), pattern.as_deref()))
+        },
+        Function::Value => {
+            let nodes = nodes_argument(&args[0], current, root);
+            if nodes.len() == 1 {
+                FunctionResult::Value(Some(Cow::Borrowed(nodes[0].value)))
+            } else {
+                FunctionResult::Value(None)
+            }
+        },
+    }
+}
@@ -1,6 +1,9 @@
-use std::fmt::Display;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Display;
 
 use crate::error::{ JsonLexerError, LexErrorKind };
+use crate::span::Span;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum JsonCharToken {
@@ -11,14 +14,14 @@ pub enum JsonCharToken {
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct JsonNumberToken {
-    is_minus: bool,
-    integer: String,
-    frac: String,
-    exp: String
+    pub(crate) is_minus: bool,
+    pub(crate) integer: String,
+    pub(crate) frac: String,
+    pub(crate) exp: String
 }
 
 impl Display for JsonNumberToken {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let mut s = String::new();
         if self.is_minus { s.push('-'); }
         s.push_str(&self.integer);
@@ -32,11 +35,11 @@ impl Display for JsonNumberToken {
 
 impl JsonNumberToken {
     pub fn new(is_minus: bool) -> JsonNumberToken {
-        JsonNumberToken { 
-            is_minus, 
-            integer: String::new(), 
-            frac: String::new(), 
-            exp: String::new() 
+        JsonNumberToken {
+            is_minus,
+            integer: String::new(),
+            frac: String::new(),
+            exp: String::new()
         }
     }
 }
@@ -49,43 +52,30 @@ pub enum JsonToken {
     EndObject,
     NameSeparator,
     ValueSeparator,
-    Digit(char),
     Number(JsonNumberToken),
-    DecimalPoint,
     WhiteSpace(char),
-    Exponent,
-    Minus,
-    Plus,
     True,
     False,
     Null,
     //QuotationMark,
-    String(Vec<JsonCharToken>)
+    String(Vec<JsonCharToken>),
+    /// A `//` or `/* */` comment, only produced when comments are enabled.
+    Comment,
+    /// A malformed token skipped by [`JsonLexer::tokenize_tolerant`].
+    Invalid
 }
 
 #[derive(Debug)]
 pub struct JsonTokens {
-    pub tokens: Vec<JsonToken>
-}
-
-impl JsonTokens {
-    // pub fn ignore_whitespace(self) -> JsonTokens {
-    //     let mut tokens = Vec::with_capacity(self.tokens.len());
-    //     for token in self.tokens {
-    //         match token {
-    //             JsonToken::WhiteSpace(_) => {},
-    //             _ => tokens.push(token),
-    //         }
-            
-    //     }
-    //     JsonTokens { tokens }
-    // }
+    pub tokens: Vec<JsonToken>,
+    /// Source span of each token in `tokens`.
+    pub spans: Vec<Span>
 }
 
 impl IntoIterator for JsonTokens {
     type Item = JsonToken;
 
-    type IntoIter = std::vec::IntoIter<Self::Item>;
+    type IntoIter = alloc::vec::IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.tokens.into_iter()
@@ -93,58 +83,152 @@ impl IntoIterator for JsonTokens {
 }
 
 pub struct JsonLexer<'a> {
-    json_chars: std::iter::Peekable<std::str::Chars<'a>>,
+    source: &'a str,
+    json_chars: core::str::Chars<'a>,
+    comments: bool,
 }
 
-impl JsonLexer<'_> {
-    pub fn new(json_string: &str) -> JsonLexer {
-        JsonLexer { json_chars: json_string.chars().peekable() }
+impl<'a> JsonLexer<'a> {
+    pub fn new(json_string: &'a str) -> JsonLexer<'a> {
+        JsonLexer { source: json_string, json_chars: json_string.chars(), comments: false }
+    }
+
+    /// Creates a lexer that starts reading at byte `offset` of `source`, with spans relative to `source`.
+    pub(crate) fn starting_at(source: &'a str, offset: usize) -> JsonLexer<'a> {
+        JsonLexer { json_chars: source[offset..].chars(), ..JsonLexer::new(source) }
     }
-    
+
+    /// Creates a lexer that also accepts JSONC `//` line comments and `/* */` block comments.
+    pub fn with_comments(json_string: &'a str) -> JsonLexer<'a> {
+        JsonLexer { comments: true, ..JsonLexer::new(json_string) }
+    }
+
+    /// Tokenizes the whole input, stopping at the first malformed token.
     pub fn tokenize(&mut self) -> Result<JsonTokens, JsonLexerError> {
-        let mut tokens = Vec::new();
-        
-        while let Some(token) = self.next_token()? {
-            match token {
-                JsonToken::Digit(d) => {
-                    let mut num = JsonNumberToken::new(false);
-                    num.integer.push(d);
-                    let next_token = self.number_token(&mut num)?;
-                    tokens.push(JsonToken::Number(num));
-                    if let Some(token) = next_token {
-                        tokens.push(token);
-                    }
-                },
-                JsonToken::Minus => {
-                    if let Some(JsonToken::Digit(d)) = self.next_token()? {
-                        let mut num = JsonNumberToken::new(true);
-                        num.integer.push(d);
-
-                        let next_token = self.number_token(&mut num)?;
-                        tokens.push(JsonToken::Number(num));
-                        if let Some(token) = next_token {
-                            tokens.push(token);
-                        }
+        let (tokens, mut errors) = self.tokenize_tolerant();
+        if errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(errors.swap_remove(0))
+        }
+    }
+
+    /// Tokenizes the input from its structural index, skipping whitespace instead of reading it
+    /// and copying string bodies without escapes in one piece.
+    ///
+    /// Returns `None` if the input is malformed in any way, so that [`tokenize`](Self::tokenize)
+    /// can report the error.
+    #[cfg(feature = "simd")]
+    pub(crate) fn tokenize_indexed(&mut self) -> Option<JsonTokens> {
+        let source = self.source;
+        let index = crate::structural::index(source.as_bytes())?;
+        let mut strings = index.strings.into_iter();
+        let mut tokens = Vec::with_capacity(index.starts.len());
+        let mut spans = Vec::with_capacity(index.starts.len());
+        for (i, &start) in index.starts.iter().enumerate() {
+            let token = match source.as_bytes()[start] {
+                b'\"' => {
+                    let (end, escapes) = strings.next()?;
+                    let body = &source[start + 1..end];
+                    if escapes || body.bytes().any(|b| b < 0x20) {
+                        // Escapes and invalid characters are left to the lexer.
+                        self.json_chars = source[start..].chars();
+                        self.next_token().ok()??
                     } else {
-                        return Err(JsonLexerError { kind: LexErrorKind::NotDigit });
+                        self.json_chars = source[end + 1..].chars();
+                        JsonToken::String(alloc::vec![JsonCharToken::UnEscaped(body.to_string())])
                     }
-                    
                 },
-                _ => tokens.push(token),
+                _ => {
+                    self.json_chars = source[start..].chars();
+                    self.next_token().ok()??
+                },
+            };
+            let end = self.offset();
+            // Anything but whitespace up to the next start is text the index did not see.
+            let next = index.starts.get(i + 1).copied().unwrap_or(source.len());
+            if end > next || !source.as_bytes()[end..next].iter().all(|b| matches!(b, b' ' | b'\t' | b'\n' | b'\r')) {
+                return None;
             }
+            tokens.push(token);
+            spans.push(Span::new(start, end));
         }
+        self.json_chars = source[source.len()..].chars();
+        Some(JsonTokens { tokens, spans })
+    }
 
-        Ok(JsonTokens { tokens })
+    /// Tokenizes the whole input, replacing each malformed token with [`JsonToken::Invalid`]
+    /// and collecting its error.
+    pub fn tokenize_tolerant(&mut self) -> (JsonTokens, Vec<JsonLexerError>) {
+        let mut tokens = Vec::new();
+        let mut spans = Vec::new();
+        let mut errors = Vec::new();
+        while let Some((token, span)) = self.next_spanned() {
+            match token {
+                Ok(token) => tokens.push(token),
+                Err(e) => {
+                    errors.push(e);
+                    tokens.push(JsonToken::Invalid);
+                },
+            }
+            spans.push(span);
+        }
+        (JsonTokens { tokens, spans }, errors)
+    }
+
+    /// Reads the next token and its span. A malformed token is skipped and its error returned instead.
+    pub(crate) fn next_spanned(&mut self) -> Option<(Result<JsonToken, JsonLexerError>, Span)> {
+        let start = self.offset();
+        let token = match self.next_token() {
+            Ok(Some(token)) => Ok(token),
+            Ok(None) => return None,
+            Err(e) => {
+                self.recover(start);
+                Err(e)
+            },
+        };
+        Some((token, Span::new(start, self.offset())))
+    }
+
+    /// Byte offset of the next character.
+    fn offset(&self) -> usize {
+        self.source.len() - self.json_chars.as_str().len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.json_chars.clone().next()
+    }
+
+    fn next_if(&mut self, f: impl FnOnce(char) -> bool) -> Option<char> {
+        match self.peek() {
+            Some(c) if f(c) => self.json_chars.next(),
+            _ => None,
+        }
+    }
+
+    fn error(&self, kind: LexErrorKind, start: usize) -> JsonLexerError {
+        JsonLexerError { kind, span: Span::new(start, self.offset()) }
     }
 
     fn next_return_token(&mut self, token: JsonToken) -> Option<JsonToken> {
         self.json_chars.next();
         Some(token)
     }
-    
+
+    /// Skips `len` bytes.
+    fn advance(&mut self, len: usize) {
+        self.json_chars = self.json_chars.as_str()[len..].chars();
+    }
+
+    fn keyword(&mut self, keyword: &str, token: JsonToken) -> Option<JsonToken> {
+        self.advance(keyword.len());
+        Some(token)
+    }
+
     fn next_token(&mut self) -> Result<Option<JsonToken>, JsonLexerError> {
-        match self.json_chars.peek() {
-            Some(&c) => match c {
+        let start = self.offset();
+        match self.peek() {
+            Some(c) => match c {
                 ' ' | '\n' | '\t' | '\r' => {
                     Ok(self.next_return_token(JsonToken::WhiteSpace(c)))
                 },
@@ -168,46 +252,38 @@ impl JsonLexer<'_> {
                 },
                 '\"' => {
                     self.json_chars.next();
-                    self.string_token()
-                },
-                '-' => {
-                    Ok(self.next_return_token(JsonToken::Minus))
-                },
-                '+' => {
-                    Ok(self.next_return_token(JsonToken::Plus))
+                    self.string_token(start).map(Some)
                 },
-                '.' => {
-                    Ok(self.next_return_token(JsonToken::DecimalPoint))
+                '-' | '0'..='9' => {
+                    self.number_token(start).map(Some)
                 },
-                'e' | 'E' => {
-                    Ok(self.next_return_token(JsonToken::Exponent))
+                't' if self.json_chars.as_str().starts_with("true") => {
+                    Ok(self.keyword("true", JsonToken::True))
                 },
-                '0'..='9' => {
-                    Ok(self.next_return_token(JsonToken::Digit(c)))
+                'f' if self.json_chars.as_str().starts_with("false") => {
+                    Ok(self.keyword("false", JsonToken::False))
                 },
-                't' => {
-                    if self.json_chars.by_ref().take(4).eq(['t','r','u','e']) {
-                        Ok(Some(JsonToken::True))
-                    } else {
-                        Err(JsonLexerError { kind: LexErrorKind::InvalidChar(c) })
-                    }
+                'n' if self.json_chars.as_str().starts_with("null") => {
+                    Ok(self.keyword("null", JsonToken::Null))
                 },
-                'f' => {
-                    if self.json_chars.by_ref().take(5).eq(['f','a','l','s','e']) {
-                        Ok(Some(JsonToken::False))
-                    } else {
-                        Err(JsonLexerError { kind: LexErrorKind::InvalidChar(c) })
-                    }
+                '/' if self.comments && self.json_chars.as_str().starts_with("//") => {
+                    while self.next_if(|c| c != '\n').is_some() {}
+                    Ok(Some(JsonToken::Comment))
                 },
-                'n' => {
-                    if self.json_chars.by_ref().take(4).eq(['n','u','l','l']) {
-                        Ok(Some(JsonToken::Null))
-                    } else {
-                        Err(JsonLexerError { kind: LexErrorKind::InvalidChar(c) })
+                '/' if self.comments && self.json_chars.as_str().starts_with("/*") => {
+                    match self.json_chars.as_str()[2..].find("*/") {
+                        Some(end) => {
+                            self.advance(end + 4);
+                            Ok(Some(JsonToken::Comment))
+                        },
+                        None => {
+                            self.advance(self.json_chars.as_str().len());
+                            Err(self.error(LexErrorKind::UnterminatedComment, start))
+                        },
                     }
                 },
                 _ => {
-                    Err(JsonLexerError { kind: LexErrorKind::InvalidChar(c) })
+                    Err(JsonLexerError { kind: LexErrorKind::InvalidChar(c), span: Span::new(start, start + c.len_utf8()) })
                 }
             },
             None => {
@@ -216,17 +292,46 @@ impl JsonLexer<'_> {
         }
     }
 
-    fn string_token(&mut self) -> Result<Option<JsonToken>, JsonLexerError> {
+    /// Skips the rest of a malformed token starting at `start` so that lexing can resume after it.
+    fn recover(&mut self, start: usize) {
+        match self.source[start..].chars().next() {
+            // An unterminated string ends at the end of its line.
+            Some('\"') if !self.source[..self.offset()].ends_with('\n') => {
+                while let Some(c) = self.next_if(|c| c != '\n') {
+                    if c == '\"' {
+                        break;
+                    }
+                    if c == '\\' {
+                        self.next_if(|c| c != '\n');
+                    }
+                }
+            },
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                while self.next_if(|c| c.is_ascii_alphanumeric() || c == '.' || c == '+' || c == '-').is_some() {}
+            },
+            Some(c) if c.is_alphanumeric() => {
+                while self.next_if(|c| c.is_alphanumeric() || c == '_').is_some() {}
+            },
+            _ => {},
+        }
+        if self.offset() == start {
+            self.json_chars.next();
+        }
+    }
+
+    /// Reads a string after its opening quotation mark at `start`.
+    fn string_token(&mut self, start: usize) -> Result<JsonToken, JsonLexerError> {
         let mut chars = Vec::new();
-        while let Some(c) = self.json_chars.next() {
-            match c {
-                '\"' => {
-                    return Ok(Some(JsonToken::String(chars)));
+        loop {
+            let at = self.offset();
+            match self.json_chars.next() {
+                Some('\"') => {
+                    return Ok(JsonToken::String(chars));
                 },
-                '\u{20}'..='\u{21}' | '\u{23}'..='\u{5b}' | '\u{5d}'..='\u{10ffff}' => {
+                Some(c @ ('\u{20}'..='\u{21}' | '\u{23}'..='\u{5b}' | '\u{5d}'..='\u{10ffff}')) => {
                     chars.push(JsonCharToken::UnEscaped(c.to_string()));
                 },
-                '\\' => {
+                Some('\\') => {
                     match self.json_chars.next() {
                         Some(escaped) => {
                             match escaped {
@@ -234,98 +339,91 @@ impl JsonLexer<'_> {
                                 'r' => chars.push(JsonCharToken::Escape("\r".to_string())),
                                 'n' => chars.push(JsonCharToken::Escape("\n".to_string())),
                                 '\\' => chars.push(JsonCharToken::Escape("\\".to_string())),
-                                '\"' => chars.push(JsonCharToken::Escape("\"".to_string())), 
+                                '\"' => chars.push(JsonCharToken::Escape("\"".to_string())),
                                 '/' => chars.push(JsonCharToken::Escape("/".to_string())),
+                                'b' => chars.push(JsonCharToken::Escape("\u{8}".to_string())),
+                                'f' => chars.push(JsonCharToken::Escape("\u{c}".to_string())),
                                 'u' => {
-                                    match u16::from_str_radix(&String::from_iter(self.json_chars.by_ref().take(4)), 16) {
+                                    let hex = self.json_chars.as_str().get(..4).unwrap_or("");
+                                    if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                                        return Err(self.error(LexErrorKind::NotEscapeChar, at));
+                                    }
+                                    match u16::from_str_radix(hex, 16) {
                                         Ok(code) => {
                                             chars.push(JsonCharToken::Unicode(code));
                                         },
                                         Err(e) => {
-                                            return Err( JsonLexerError {kind: LexErrorKind::ParseError(e) });
+                                            return Err(self.error(LexErrorKind::ParseError(e), at));
                                         }
                                     }
-                                     
-                                }
-                                _ => return Err(JsonLexerError { kind: LexErrorKind::InvalidChar(c) }),
+                                    self.advance(4);
+                                },
+                                _ => return Err(self.error(LexErrorKind::NotEscapeChar, at)),
                             }
                         },
                         None => {
-                            return Err(JsonLexerError { kind: LexErrorKind::NonQuotationMark });
+                            return Err(self.error(LexErrorKind::NonQuotationMark, start));
                         }
-                    } 
+                    }
                 },
-                _ => {
-                    return Err(JsonLexerError { kind: LexErrorKind::InvalidChar(c) });
+                Some(c) => {
+                    return Err(self.error(LexErrorKind::InvalidChar(c), at));
+                },
+                None => {
+                    return Err(self.error(LexErrorKind::NonQuotationMark, start));
                 }
             }
         }
-        Err(JsonLexerError { kind: LexErrorKind::NonQuotationMark })
     }
 
-    fn number_token(&mut self, number : &mut JsonNumberToken) -> Result<Option<JsonToken>, JsonLexerError> {
-        while let Some(token) = self.next_token()? {
-            match token {
-                JsonToken::Digit(d) => {
-                    number.integer.push(d);
-                },
-                JsonToken::DecimalPoint =>{
-                    if let Some(JsonToken::Digit(d)) = self.next_token()? {
-                        number.frac.push(d);
-                        while let Some(token) = self.next_token()? {
-                            match token {
-                                JsonToken::Digit(d) => {
-                                    number.frac.push(d);
-                                },
-                                JsonToken::Exponent => {
-                                    let sign = self.next_token()?;
-                                    if let Some(JsonToken::Minus) = sign {
-                                        number.exp.push('-');
-                                    } else if let Some(JsonToken::Plus) = sign {
-                                        number.exp.push('+');
-                                    } 
-                                    while let Some(token) = self.next_token()? {
-                                        match token {
-                                            JsonToken::Digit(d) => number.exp.push(d),
-                                            _=> {
-                                                return Ok(Some(token));
-                                            }
-                                        }
-                                    }
-                                }
-                                _ => {
-                                    return Ok(Some(token));
-                                }
-                            }
-                        }
-                        break;
-                    } else {
-                        return Err(JsonLexerError { kind: LexErrorKind::NonFracDigit });
-                    }
-                },
-                JsonToken::Exponent =>{
-                    let sign = self.next_token()?;
-                    if let Some(JsonToken::Minus) = sign {
-                        number.exp.push('-');
-                    } else if let Some(JsonToken::Plus) = sign {
-                        number.exp.push('+');
-                    } 
-                    while let Some(token) = self.next_token()? {
-                        match token {
-                            JsonToken::Digit(d) => number.exp.push(d),
-                            _=> {
-                                return Ok(Some(token));
-                            }
-                        }
-                    }
-                    break;
-                },
-                _ => { 
-                    return Ok(Some(token));
-                }
+    fn take_digit(&mut self) -> Option<char> {
+        self.next_if(|c| c.is_ascii_digit())
+    }
+
+    /// Reads a number starting at `start`.
+    ///
+    /// The grammar follows RFC 8259 exactly:
+    /// `[ minus ] int [ frac ] [ exp ]` where `int` is `0` or a non-zero digit followed by digits,
+    /// `frac` is a decimal-point followed by one or more digits and
+    /// `exp` is `e`/`E`, an optional sign and one or more digits.
+    fn number_token(&mut self, start: usize) -> Result<JsonToken, JsonLexerError> {
+        let mut number = JsonNumberToken::new(self.next_if(|c| c == '-').is_some());
+        match self.take_digit() {
+            Some(d) => number.integer.push(d),
+            None => return Err(self.error(LexErrorKind::NotDigit, start)),
+        }
+        if number.integer == "0" {
+            if self.take_digit().is_some() {
+                return Err(self.error(LexErrorKind::LeadingZero, start));
+            }
+        } else {
+            while let Some(d) = self.take_digit() {
+                number.integer.push(d);
+            }
+        }
+
+        if self.next_if(|c| c == '.').is_some() {
+            while let Some(d) = self.take_digit() {
+                number.frac.push(d);
+            }
+            if number.frac.is_empty() {
+                return Err(self.error(LexErrorKind::NonFracDigit, start));
             }
-            
         }
-        Ok(None)
+
+        if self.next_if(|c| c == 'e' || c == 'E').is_some() {
+            if let Some(sign) = self.next_if(|c| c == '-' || c == '+') {
+                number.exp.push(sign);
+            }
+            let mut has_digit = false;
+            while let Some(d) = self.take_digit() {
+                number.exp.push(d);
+                has_digit = true;
+            }
+            if !has_digit {
+                return Err(self.error(LexErrorKind::EmptyExponent, start));
+            }
+        }
+        Ok(JsonToken::Number(number))
     }
-}
\ No newline at end of file
+}
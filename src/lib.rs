@@ -1,11 +1,48 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[macro_use]
+mod macros;
 pub mod json;
-mod error;
+#[cfg(feature = "arbitrary_precision")]
+pub mod decimal;
+pub mod error;
+pub mod span;
+pub mod tokenizer;
+pub mod pointer;
+pub mod jsonpath;
+pub mod writer;
+pub mod canonical;
+pub mod diff;
+pub mod cst;
+pub mod push;
+pub mod tape;
+pub mod ondemand;
+#[cfg(feature = "async")]
+pub mod async_io;
+#[cfg(feature = "serde")]
+pub mod serde;
+pub mod schema;
 mod parser;
 mod lexer;
+mod regex;
+mod diagnostic;
+#[cfg(feature = "simd")]
+mod structural;
+
+// Used by the exported macros, which must also work in `no_std` crates.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::string::ToString;
+    pub use alloc::vec;
+    pub use alloc::vec::Vec;
+}
 
 #[test]
 fn tokenize_null() {
     use lexer::*;
+    use alloc::vec::Vec;
     let mut lexer = JsonLexer::new("null");
     match lexer.tokenize() {
         Ok(tokens) => {
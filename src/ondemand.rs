@@ -0,0 +1,452 @@
+//! On-demand access to a JSON text without parsing all of it.
+//!
+//! A [`Value`] is only a position in the source. Looking up a member or an element scans
+//! forward from there; siblings that are passed over are checked only for matching brackets
+//! and quotation marks, and nothing is decoded or allocated for them. Strings without escapes
+//! are returned as slices of the source.
+//!
+//! ```
+//! use crystalrake::ondemand::Document;
+//!
+//! let doc = Document::new(r#"{"skipped": [1, {"x": "y"}], "a": {"b": "found"}}"#);
+//! assert_eq!(doc.get("a")?.get("b")?.as_str()?, "found");
+//! # Ok::<(), crystalrake::error::OnDemandError>(())
+//! ```
+//!
+//! Since only what is read is checked, errors in the rest of the text go unnoticed.
+//!
+//! With the `mmap` feature, `Document::open_mmap` reads a file through a memory map, so
+//! large files are never copied into a `String`. It is `unsafe`, because the file must not
+//! change while it is mapped.
+
+use alloc::borrow::Cow;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+#[cfg(feature = "mmap")]
+use crate::error::ReadError;
+use crate::error::{JsonLexerError, JsonParseError, LexErrorKind, OnDemandError, OnDemandErrorKind, ParseErrorKind};
+use crate::json::JsonValue;
+use crate::lexer::{JsonLexer, JsonToken};
+use crate::parser::{decode_string, JsonParser};
+use crate::span::Span;
+
+const VALUE: &[&str] = &["value"];
+const NAME: &[&str] = &["string"];
+const COLON: &[&str] = &["`:`"];
+
+/// A JSON text read on demand.
+#[derive(Debug, Clone, Copy)]
+pub struct Document<'a> {
+    source: &'a str,
+}
+
+impl<'a> Document<'a> {
+    pub fn new(source: &'a str) -> Document<'a> {
+        Document { source }
+    }
+
+    /// The top-level value.
+    pub fn root(&self) -> Result<Value<'a>, OnDemandError> {
+        Value::starting(self.source, skip_whitespace(self.source, 0))
+    }
+
+    /// Shorthand for `root()?.get(name)`.
+    pub fn get(&self, name: &str) -> Result<Value<'a>, OnDemandError> {
+        self.root()?.get(name)
+    }
+
+    /// Shorthand for `root()?.at(index)`.
+    pub fn at(&self, index: usize) -> Result<Value<'a>, OnDemandError> {
+        self.root()?.at(index)
+    }
+
+    /// Maps the file at `path` into memory, for reading with [`MappedFile::document`].
+    ///
+    /// Only the UTF-8 encoding is checked here; the JSON is read on demand as usual.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified, by this or any other process, until the returned
+    /// `MappedFile` is dropped. Changes would show through the map, so strings read from it
+    /// could stop being valid UTF-8. See [`memmap2::Mmap::map`].
+    #[cfg(feature = "mmap")]
+    pub unsafe fn open_mmap<P: AsRef<std::path::Path>>(path: P) -> Result<MappedFile, ReadError> {
+        let file = std::fs::File::open(path)?;
+        // Safety: the map is read-only, and the caller keeps the file unmodified while it is mapped.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        if let Err(e) = core::str::from_utf8(&map) {
+            let at = e.valid_up_to();
+            let span = Span::new(at, at + e.error_len().unwrap_or(1));
+            return Err(JsonParseError::from(JsonLexerError { kind: LexErrorKind::InvalidUtf8, span }).into());
+        }
+        Ok(MappedFile { map })
+    }
+}
+
+/// A memory-mapped JSON file. Documents read from it borrow their strings from the map.
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MappedFile {
+    /// Checked to be UTF-8 when mapped.
+    map: memmap2::Mmap,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedFile {
+    /// The file's contents.
+    pub fn source(&self) -> &str {
+        // Safety: `Document::open_mmap` checked the encoding, and its caller keeps the file
+        // from changing since.
+        unsafe { core::str::from_utf8_unchecked(&self.map) }
+    }
+
+    pub fn document(&self) -> Document<'_> {
+        Document::new(self.source())
+    }
+}
+
+/// A value in a [`Document`], read when one of its methods is called.
+#[derive(Debug, Clone, Copy)]
+pub struct Value<'a> {
+    source: &'a str,
+    start: usize,
+}
+
+impl<'a> Value<'a> {
+    /// The value starting at `start`, which must not be whitespace.
+    fn starting(source: &'a str, start: usize) -> Result<Value<'a>, OnDemandError> {
+        match source.as_bytes().get(start) {
+            Some(b',' | b':' | b']' | b'}') => Err(invalid(start, VALUE).into()),
+            Some(_) => Ok(Value { source, start }),
+            None => Err(JsonParseError { kind: ParseErrorKind::NonValue, span: Span::new(start, start), expected: VALUE }.into()),
+        }
+    }
+
+    /// The JSON type of the value, judged by its first character.
+    fn type_name(&self) -> Option<&'static str> {
+        Some(match self.source.as_bytes()[self.start] {
+            b'{' => "object",
+            b'[' => "array",
+            b'"' => "string",
+            b'-' | b'0'..=b'9' => "number",
+            b't' | b'f' => "boolean",
+            b'n' => "null",
+            _ => return None,
+        })
+    }
+
+    /// Fails unless the value looks like `expected`; malformed text is a syntax error.
+    fn expect(&self, expected: &'static str) -> Result<(), OnDemandError> {
+        match self.type_name() {
+            Some(found) if found == expected => Ok(()),
+            Some(found) => Err(OnDemandError { kind: OnDemandErrorKind::InvalidType { expected, found } }),
+            None => Err(self.token().unwrap_err()),
+        }
+    }
+
+    /// Lexes the scalar at the start of the value and checks what follows it.
+    fn token(&self) -> Result<JsonToken, OnDemandError> {
+        let mut lexer = JsonLexer::starting_at(self.source, self.start);
+        let (token, span) = lexer.next_spanned().unwrap();
+        let token = token?;
+        match self.source.as_bytes().get(span.end) {
+            None | Some(b' ' | b'\t' | b'\n' | b'\r' | b',' | b']' | b'}') => Ok(token),
+            Some(_) => {
+                let (_, next) = lexer.next_spanned().unwrap();
+                Err(JsonParseError { kind: ParseErrorKind::InvalidToken, span: next, expected: &["`,`", "`]`", "`}`"] }.into())
+            },
+        }
+    }
+
+    /// Byte offset of the end of the value, found by matching brackets and quotation marks.
+    fn end(&self) -> Result<usize, OnDemandError> {
+        Ok(value_end(self.source, self.start)?)
+    }
+
+    /// The value exactly as written in the source.
+    pub fn raw(&self) -> Result<&'a str, OnDemandError> {
+        Ok(&self.source[self.start..self.end()?])
+    }
+
+    /// Location of the value in the source.
+    pub fn span(&self) -> Result<Span, OnDemandError> {
+        Ok(Span::new(self.start, self.end()?))
+    }
+
+    pub fn is_null(&self) -> Result<bool, OnDemandError> {
+        match self.type_name() {
+            Some("null") => Ok(self.token()? == JsonToken::Null),
+            Some(_) => Ok(false),
+            None => Err(self.token().unwrap_err()),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, OnDemandError> {
+        self.expect("boolean")?;
+        Ok(self.token()? == JsonToken::True)
+    }
+
+    pub fn as_f64(&self) -> Result<f64, OnDemandError> {
+        self.expect("number")?;
+        let span = Span::new(self.start, self.start);
+        match self.token()? {
+            JsonToken::Number(number) => number.to_string().parse()
+                .map_err(|e| JsonParseError::new(ParseErrorKind::ParseFloatError(e), span).into()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// The string, borrowed from the source unless it has escapes.
+    pub fn as_str(&self) -> Result<Cow<'a, str>, OnDemandError> {
+        self.expect("string")?;
+        Ok(read_string(self.source, self.start)?.0)
+    }
+
+    /// Returns the value of the first object member named `name`.
+    pub fn get(&self, name: &str) -> Result<Value<'a>, OnDemandError> {
+        for member in self.members()? {
+            let (key, value) = member?;
+            if key == name {
+                return Ok(value);
+            }
+        }
+        Err(OnDemandError { kind: OnDemandErrorKind::MissingField(name.to_string()) })
+    }
+
+    /// Returns the `index`th element of an array.
+    pub fn at(&self, index: usize) -> Result<Value<'a>, OnDemandError> {
+        match self.elements()?.nth(index) {
+            Some(element) => element,
+            None => Err(OnDemandError { kind: OnDemandErrorKind::IndexOutOfRange(index) }),
+        }
+    }
+
+    /// The members of an object, read one at a time.
+    pub fn members(&self) -> Result<Members<'a>, OnDemandError> {
+        self.expect("object")?;
+        Ok(Members(Entries::new(self.source, self.start)))
+    }
+
+    /// The elements of an array, read one at a time.
+    pub fn elements(&self) -> Result<Elements<'a>, OnDemandError> {
+        self.expect("array")?;
+        Ok(Elements(Entries::new(self.source, self.start)))
+    }
+
+    /// Parses the whole value into a [`JsonValue`].
+    pub fn to_value(&self) -> Result<JsonValue, OnDemandError> {
+        let end = self.end()?;
+        let tokens = JsonLexer::starting_at(&self.source[..end], self.start).tokenize()?;
+        Ok(JsonParser::new(tokens, end).get_value()?)
+    }
+}
+
+/// A member name, or `None` for an array element, and the value.
+type Entry<'a> = (Option<Cow<'a, str>>, Value<'a>);
+
+/// Members of an object or elements of an array, skipping each value when moving past it.
+#[derive(Debug, Clone)]
+struct Entries<'a> {
+    source: &'a str,
+    /// Where to continue: after the opening bracket, or at the value returned last.
+    position: usize,
+    object: bool,
+    first: bool,
+    done: bool,
+}
+
+impl<'a> Entries<'a> {
+    fn new(source: &'a str, start: usize) -> Entries<'a> {
+        let object = source.as_bytes()[start] == b'{';
+        Entries { source, position: start + 1, object, first: true, done: false }
+    }
+
+    fn expected(&self) -> &'static [&'static str] {
+        if self.object { &["`,`", "`}`"] } else { &["`,`", "`]`"] }
+    }
+
+    fn unclosed(&self, at: usize) -> JsonParseError {
+        let kind = if self.object { ParseErrorKind::NonEndObject } else { ParseErrorKind::NonEndArray };
+        JsonParseError { kind, span: Span::new(at, at), expected: self.expected() }
+    }
+
+    /// Moves to the next entry; `None` at the closing bracket.
+    fn advance(&mut self) -> Result<Option<usize>, OnDemandError> {
+        let bytes = self.source.as_bytes();
+        let close = if self.object { b'}' } else { b']' };
+        let mut position = skip_whitespace(self.source, self.position);
+        if self.first {
+            self.first = false;
+            if bytes.get(position) == Some(&close) {
+                return Ok(None);
+            }
+        } else {
+            position = skip_whitespace(self.source, value_end(self.source, position)?);
+            match bytes.get(position) {
+                Some(b',') => {
+                    position = skip_whitespace(self.source, position + 1);
+                    if bytes.get(position) == Some(&close) {
+                        let expected = if self.object { NAME } else { VALUE };
+                        return Err(JsonParseError { kind: ParseErrorKind::TrailingComma, span: Span::new(position - 1, position), expected }.into());
+                    }
+                },
+                Some(b) if *b == close => return Ok(None),
+                Some(_) => return Err(invalid(position, self.expected()).into()),
+                None => return Err(self.unclosed(position).into()),
+            }
+        }
+        if bytes.get(position).is_none() {
+            return Err(self.unclosed(position).into());
+        }
+        Ok(Some(position))
+    }
+
+    fn next_entry(&mut self) -> Option<Result<Entry<'a>, OnDemandError>> {
+        if self.done {
+            return None;
+        }
+        let entry = self.read();
+        if !matches!(entry, Ok(Some(_))) {
+            self.done = true;
+        }
+        entry.transpose()
+    }
+
+    fn read(&mut self) -> Result<Option<Entry<'a>>, OnDemandError> {
+        let mut position = match self.advance()? {
+            Some(position) => position,
+            None => return Ok(None),
+        };
+        let mut name = None;
+        if self.object {
+            if self.source.as_bytes()[position] != b'"' {
+                return Err(JsonParseError { kind: ParseErrorKind::NoObjectName, span: Span::new(position, position + 1), expected: NAME }.into());
+            }
+            let (key, end) = read_string(self.source, position)?;
+            name = Some(key);
+            position = skip_whitespace(self.source, end);
+            if self.source.as_bytes().get(position) != Some(&b':') {
+                return Err(invalid(position, COLON).into());
+            }
+            position = skip_whitespace(self.source, position + 1);
+        }
+        let value = Value::starting(self.source, position)?;
+        self.position = position;
+        Ok(Some((name, value)))
+    }
+}
+
+/// Iterator over the members of an object [`Value`].
+///
+/// After an error it returns `None`.
+#[derive(Debug, Clone)]
+pub struct Members<'a>(Entries<'a>);
+
+impl<'a> Iterator for Members<'a> {
+    type Item = Result<(Cow<'a, str>, Value<'a>), OnDemandError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_entry().map(|entry| entry.map(|(name, value)| (name.unwrap(), value)))
+    }
+}
+
+/// Iterator over the elements of an array [`Value`].
+///
+/// After an error it returns `None`.
+#[derive(Debug, Clone)]
+pub struct Elements<'a>(Entries<'a>);
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = Result<Value<'a>, OnDemandError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_entry().map(|entry| entry.map(|(_, value)| value))
+    }
+}
+
+fn invalid(at: usize, expected: &'static [&'static str]) -> JsonParseError {
+    JsonParseError { kind: ParseErrorKind::InvalidToken, span: Span::new(at, at + 1), expected }
+}
+
+fn skip_whitespace(source: &str, mut position: usize) -> usize {
+    let bytes = source.as_bytes();
+    while matches!(bytes.get(position), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+        position += 1;
+    }
+    position
+}
+
+/// Offset after the closing quotation mark of the string starting at `start`.
+fn string_end(source: &str, start: usize) -> Result<usize, JsonLexerError> {
+    let bytes = source.as_bytes();
+    let mut position = start + 1;
+    while let Some(b) = bytes.get(position) {
+        match b {
+            b'"' => return Ok(position + 1),
+            b'\\' => position += 2,
+            _ => position += 1,
+        }
+    }
+    Err(JsonLexerError { kind: LexErrorKind::NonQuotationMark, span: Span::new(start, source.len()) })
+}
+
+/// Decodes the string starting at `start`, returning it and the offset after it.
+fn read_string(source: &str, start: usize) -> Result<(Cow<'_, str>, usize), OnDemandError> {
+    let end = string_end(source, start)?;
+    let raw = &source[start + 1..end - 1];
+    if !raw.bytes().any(|b| b == b'\\' || b < 0x20) {
+        return Ok((Cow::Borrowed(raw), end));
+    }
+    // The lexer checks the escapes and reports malformed ones.
+    let mut lexer = JsonLexer::starting_at(&source[..end], start);
+    match lexer.next_spanned() {
+        Some((Ok(JsonToken::String(chars)), span)) => {
+            let s = decode_string(&chars).map_err(|e| JsonParseError::new(e.kind, span))?;
+            Ok((Cow::Owned(s), end))
+        },
+        Some((Err(e), _)) => Err(e.into()),
+        _ => unreachable!(),
+    }
+}
+
+/// Offset after the value starting at `start`, checking only brackets and quotation marks.
+fn value_end(source: &str, start: usize) -> Result<usize, JsonParseError> {
+    let bytes = source.as_bytes();
+    match bytes.get(start) {
+        Some(b'"') => Ok(string_end(source, start)?),
+        Some(b'{' | b'[') => {
+            let mut closers = Vec::new();
+            let mut position = start;
+            while let Some(&b) = bytes.get(position) {
+                match b {
+                    b'"' => {
+                        position = string_end(source, position)?;
+                        continue;
+                    },
+                    b'{' => closers.push(b'}'),
+                    b'[' => closers.push(b']'),
+                    b'}' | b']' => {
+                        let expected = closers.pop();
+                        if expected != Some(b) {
+                            return Err(invalid(position, if expected == Some(b'}') { &["`}`"] } else { &["`]`"] }));
+                        }
+                        if closers.is_empty() {
+                            return Ok(position + 1);
+                        }
+                    },
+                    _ => {},
+                }
+                position += 1;
+            }
+            let kind = if closers.last() == Some(&b'}') { ParseErrorKind::NonEndObject } else { ParseErrorKind::NonEndArray };
+            Err(JsonParseError::new(kind, Span::new(source.len(), source.len())))
+        },
+        _ => {
+            let length = bytes[start..].iter()
+                .position(|b| matches!(b, b' ' | b'\t' | b'\n' | b'\r' | b',' | b']' | b'}'))
+                .unwrap_or(bytes.len() - start);
+            Ok(start + length)
+        },
+    }
+}
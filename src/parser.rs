@@ -1,15 +1,59 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 use crate::json::{JsonValue, JsonObject};
 use crate::error::{JsonParseError, ParseErrorKind};
 use crate::lexer::*;
+use crate::span::Span;
+
+/// Maximum nesting depth of arrays and objects.
+pub(crate) const MAX_DEPTH: usize = 128;
+
+const VALUE: &[&str] = &["value"];
+const NAME: &[&str] = &["string"];
+const COLON: &[&str] = &["`:`"];
 
+/// Parses tokens into a `JsonValue`.
+///
+/// In strict mode the first error aborts parsing. In tolerant mode every error is recorded,
+/// parsing resumes at the next comma or closing bracket, and values that could not be read
+/// are replaced by `null` placeholders whose JSON Pointers are recorded.
 pub struct JsonParser {
     tokens : Vec<JsonToken>,
-    position : usize
+    spans : Vec<Span>,
+    position : usize,
+    depth : usize,
+    /// Length of the source, where errors at the end of the input are located.
+    end : usize,
+    /// Diagnostics collected in tolerant mode; `None` in strict mode.
+    errors : Option<Vec<JsonParseError>>,
+    /// Closing brackets of the arrays and objects being parsed.
+    open : Vec<char>,
+    /// Pointer tokens of the value being parsed, kept only in tolerant mode.
+    path : Vec<String>,
+    placeholders : Vec<String>,
 }
 
 impl JsonParser {
-    pub fn new(tokens: JsonTokens) -> JsonParser {
-        JsonParser { tokens : tokens.tokens, position: 0 }
+    pub fn new(tokens: JsonTokens, end: usize) -> JsonParser {
+        let mut parser = JsonParser {
+            tokens: tokens.tokens,
+            spans: tokens.spans,
+            position: 0,
+            depth: 0,
+            end,
+            errors: None,
+            open: Vec::new(),
+            path: Vec::new(),
+            placeholders: Vec::new(),
+        };
+        parser.skip_trivia();
+        parser
+    }
+
+    /// Creates a parser that recovers from errors instead of stopping at the first one.
+    pub fn tolerant(tokens: JsonTokens, end: usize) -> JsonParser {
+        JsonParser { errors: Some(Vec::new()), ..JsonParser::new(tokens, end) }
     }
 
     fn peek(&self) -> Option<&JsonToken> {
@@ -17,237 +61,350 @@ impl JsonParser {
     }
 
     fn next(&mut self) -> Option<&JsonToken> {
-        let current = self.tokens.get(self.position);
+        let current = self.position;
         self.position += 1;
-        current
-    }
-
-    // fn skip_whitespace(&mut self) {
-    //     while let Some(token) = self.peek()  {
-    //         match token {
-    //             JsonToken::WhiteSpace(_) => {
-    //                 self.next();
-    //                 continue;
-    //             },
-    //             _ => {
-    //                 break;
-    //             }
-    //         }
-    //     }
-    // }
-
-    fn token_to_string(s : &Vec<JsonCharToken>) -> Result<Option<JsonValue>, JsonParseError> {
-        let mut buf = String::new();
-        let mut utf16  = Vec::new();
-        for c in s {
-            match c {
-                JsonCharToken::Escape(c) | JsonCharToken::UnEscaped(c) =>{
-                    if !utf16.is_empty() {
-                        match String::from_utf16(&utf16) {
-                            Ok(utf16_str) => {
-                                buf.push_str(&utf16_str);
-                                utf16.clear();
-                            },
-                            Err(e) => {
-                                return Err( JsonParseError{ kind: ParseErrorKind::FromUtf16Error(e)});
-                            }
-                        }
-                    } 
-                    buf.push_str(c);
-                },
-                JsonCharToken::Unicode(c) => {
-                    utf16.push(*c);
-                }
-            }
+        self.skip_trivia();
+        self.tokens.get(current)
+    }
+
+    /// Moves past whitespace and comments, which are skipped as they are reached rather than
+    /// filtered out up front, to avoid copying the tokens of large documents.
+    fn skip_trivia(&mut self) {
+        while let Some(JsonToken::WhiteSpace(_) | JsonToken::Comment) = self.tokens.get(self.position) {
+            self.position += 1;
         }
-        if !utf16.is_empty() {
-            match String::from_utf16(&utf16) {
-                Ok(utf16_str) => {
-                    buf.push_str(&utf16_str);
-                },
-                Err(e) => {
-                    return Err( JsonParseError{ kind: ParseErrorKind::FromUtf16Error(e)});
-                }
-            }
+    }
+
+    /// Span of the next token, or an empty span at the end of the input.
+    fn span(&self) -> Span {
+        self.spans.get(self.position).copied().unwrap_or(Span::new(self.end, self.end))
+    }
+
+    /// Reports an error: strict mode returns it, tolerant mode records it and carries on.
+    fn report(&mut self, kind: ParseErrorKind, span: Span, expected: &'static [&'static str]) -> Result<(), JsonParseError> {
+        let error = JsonParseError { kind, span, expected };
+        match &mut self.errors {
+            Some(errors) => {
+                errors.push(error);
+                Ok(())
+            },
+            None => Err(error),
         }
-        return Ok(Some(JsonValue::String(buf))); 
     }
 
+    fn placeholder(&mut self) -> JsonValue {
+        self.placeholders.push(crate::pointer::join(&self.path));
+        JsonValue::Null
+    }
+
+    /// Parses a whole document in strict mode.
     pub fn get_value(&mut self) -> Result<JsonValue, JsonParseError> {
-        let mut ret = None;
-        while let Some(value) = self.next_value()? {
-            if ret.is_none() {
-                ret = Some(value);
-            } else {
-                return Err( JsonParseError{ kind: ParseErrorKind::InvalidToken} );
+        let value = self.next_value()?;
+        if self.peek().is_some() {
+            self.report(ParseErrorKind::InvalidToken, self.span(), &["end of input"])?;
+        }
+        Ok(value)
+    }
+
+    /// Parses a whole document in tolerant mode, returning the value, the placeholder
+    /// pointers and the errors.
+    pub fn get_value_tolerant(mut self) -> (JsonValue, Vec<String>, Vec<JsonParseError>) {
+        let value = self.get_value().unwrap_or(JsonValue::Null);
+        (value, self.placeholders, self.errors.unwrap_or_default())
+    }
+
+    fn next_value(&mut self) -> Result<JsonValue, JsonParseError> {
+        let span = self.span();
+        match self.peek() {
+            Some(JsonToken::BeginObject) | Some(JsonToken::BeginArray) => {
+                let object = matches!(self.next(), Some(JsonToken::BeginObject));
+                if self.depth >= MAX_DEPTH {
+                    self.report(ParseErrorKind::RecursionLimitExceeded, span, &[])?;
+                    self.skip_nested();
+                    return Ok(self.placeholder());
+                }
+                self.depth += 1;
+                self.open.push(if object { '}' } else { ']' });
+                let value = if object { self.get_objects()? } else { self.get_array()? };
+                self.open.pop();
+                self.depth -= 1;
+                Ok(value)
+            },
+            #[cfg(feature = "arbitrary_precision")]
+            Some(JsonToken::Number(number)) => {
+                let decimal = crate::decimal::Decimal::from_token(number);
+                self.next();
+                match decimal {
+                    Some(d) => Ok(JsonValue::Decimal(d)),
+                    None => {
+                        self.report(ParseErrorKind::NumberOutOfRange, span, &[])?;
+                        Ok(self.placeholder())
+                    }
+                }
+            },
+            #[cfg(not(feature = "arbitrary_precision"))]
+            Some(JsonToken::Number(number)) => {
+                let number = number.to_string();
+                self.next();
+                match number.parse() {
+                    Ok(v) => Ok(JsonValue::Number(v)),
+                    Err(e) => {
+                        self.report(ParseErrorKind::ParseFloatError(e), span, &[])?;
+                        Ok(self.placeholder())
+                    }
+                }
+            },
+            Some(JsonToken::True) => {
+                self.next();
+                Ok(JsonValue::Boolean(true))
+            },
+            Some(JsonToken::False) => {
+                self.next();
+                Ok(JsonValue::Boolean(false))
+            },
+            Some(JsonToken::Null) => {
+                self.next();
+                Ok(JsonValue::Null)
+            },
+            Some(JsonToken::String(_)) => {
+                match self.next_string()? {
+                    Some(s) => Ok(JsonValue::String(s)),
+                    None => Ok(self.placeholder()),
+                }
+            },
+            Some(JsonToken::Invalid) => {
+                // Already reported by the lexer.
+                self.next();
+                Ok(self.placeholder())
+            },
+            Some(token) => {
+                // Leave separators and closing brackets to the enclosing array or object.
+                if !matches!(token, JsonToken::ValueSeparator | JsonToken::EndArray | JsonToken::EndObject) {
+                    self.next();
+                }
+                self.report(ParseErrorKind::InvalidToken, span, VALUE)?;
+                Ok(self.placeholder())
+            },
+            None => {
+                self.report(ParseErrorKind::NonValue, span, VALUE)?;
+                Ok(self.placeholder())
             }
         }
-        match ret {
-            Some(v) => Ok(v),
-            None => Err( JsonParseError{ kind: ParseErrorKind::InvalidToken} )
+    }
+
+    /// Reads a string token; `None` if its escapes do not form valid UTF-16 in tolerant mode.
+    fn next_string(&mut self) -> Result<Option<String>, JsonParseError> {
+        let span = self.span();
+        let decoded = match self.next() {
+            Some(JsonToken::String(s)) => decode_string(s),
+            _ => unreachable!(),
+        };
+        match decoded {
+            Ok(s) => Ok(Some(s)),
+            Err(e) => {
+                self.report(e.kind, span, &[])?;
+                Ok(None)
+            }
         }
     }
 
-    fn next_value(&mut self) -> Result<Option<JsonValue>, JsonParseError> {
+    /// Skips the rest of an array or object whose opening bracket was just read.
+    fn skip_nested(&mut self) {
+        let mut depth = 1;
         while let Some(token) = self.next() {
             match token {
-                JsonToken::BeginObject => {
-                    let mut objects = Vec::new();
-                    while let Some(token) = self.peek()  {
-                        match token {
-                            JsonToken::WhiteSpace(_) => {
-                                self.next();
-                                continue;
-                            },
-                            JsonToken::EndObject => {
-                                self.next();
-                                return Ok(Some(JsonValue::Objects(objects)));
-                            },
-                            _ => {
-                                objects.push(self.get_object()?);
-                                while let Some(token) = self.peek() {
-                                    match token {
-                                        JsonToken::WhiteSpace(_) => { 
-                                            self.next();
-                                            continue; 
-                                        },
-                                        JsonToken::ValueSeparator => {
-                                            self.next();
-                                            objects.push(self.get_object()?);
-                                        },
-                                        JsonToken::EndObject => {
-                                            self.next();
-                                            return Ok(Some(JsonValue::Objects(objects)));
-                                        },
-                                        _ => {
-                                            
-                                            return Err( JsonParseError{ kind: ParseErrorKind::InvalidToken} );
-                                        }
-                                    }
-                                }
-                            }
-
-                        }
+                JsonToken::BeginArray | JsonToken::BeginObject => depth += 1,
+                JsonToken::EndArray | JsonToken::EndObject => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
                     }
-                    return Ok(Some(JsonValue::Objects(objects)));
                 },
-                JsonToken::BeginArray => {
-                    let mut values = Vec::new(); 
-                    while let Some(token) = self.peek() {
-                        match token {
-                            JsonToken::WhiteSpace(_) => {
-                                self.next();
-                                continue;
-                            },
-                            JsonToken::EndArray => {
-                                self.next();
-                                return Ok(Some(JsonValue::Array(values)));
-                            },
-                            _ => {
-                                if let Some(value) = self.next_value()? {
-                                    values.push(value);
-                                } else {
-                                    return Ok(Some(JsonValue::Array(values)));
-                                }
-
-                                while let Some(token) = self.peek() {
-                                    match token {
-                                        JsonToken::WhiteSpace(_) => {
-                                            self.next();
-                                            continue;
-                                        },
-                                        JsonToken::EndArray =>{
-                                            self.next();
-                                            return Ok(Some(JsonValue::Array(values)));
-                                        },
-                                        JsonToken::ValueSeparator => {
-                                            self.next();
-                                            if let Some(value) = self.next_value()? {
-                                                values.push(value);
-                                            } else {
-                                                return Err( JsonParseError{ kind: ParseErrorKind::NonValue} );
-                                            }
-                                        },
-                                        _ => {
-                                            return Err( JsonParseError{ kind: ParseErrorKind::InvalidToken} );
-                                        }
-                                    }
-                                }
-                                return Err( JsonParseError{ kind: ParseErrorKind::NonEndArray} );
-                            }
-                        }
-                    }
-                    unreachable!()
+                _ => {},
+            }
+        }
+    }
+
+    /// Skips tokens up to the next comma or closing bracket at the current level.
+    fn skip_to_separator(&mut self) {
+        while let Some(token) = self.peek() {
+            match token {
+                JsonToken::ValueSeparator | JsonToken::EndArray | JsonToken::EndObject => return,
+                JsonToken::BeginArray | JsonToken::BeginObject => {
+                    self.next();
+                    self.skip_nested();
                 },
-                JsonToken::WhiteSpace(_) => {
-                    //self.next();
-                    return self.next_value();
+                _ => {
+                    self.next();
                 },
-                JsonToken::Number(number) => {
-                    let number = number.to_string();
-                    match number.parse() {
-                        Ok(v) => {
-                            //self.next();
-                            return Ok(Some(JsonValue::Number(v)));
-                        },
-                        Err(e) => {
-                            return Err(JsonParseError{ kind: ParseErrorKind::ParseFloatError(e)});
-                        }
+            }
+        }
+    }
+
+    /// Reads what follows an element of an array or object: a comma, or the closing bracket `close`.
+    ///
+    /// Returns `true` if another element follows.
+    fn next_separator(&mut self, close: JsonToken) -> Result<bool, JsonParseError> {
+        let (unclosed, expected, element): (fn() -> ParseErrorKind, _, _) = if close == JsonToken::EndArray {
+            (|| ParseErrorKind::NonEndArray, &["`,`", "`]`"], VALUE)
+        } else {
+            (|| ParseErrorKind::NonEndObject, &["`,`", "`}`"], NAME)
+        };
+        loop {
+            let span = self.span();
+            match self.peek() {
+                Some(JsonToken::ValueSeparator) => {
+                    self.next();
+                    if self.peek() == Some(&close) {
+                        self.report(ParseErrorKind::TrailingComma, span, element)?;
+                        self.next();
+                        return Ok(false);
                     }
+                    return Ok(true);
                 },
-                JsonToken::True => {
-                    return Ok(Some(JsonValue::Boolean(true)));
+                Some(token) if *token == close => {
+                    self.next();
+                    return Ok(false);
                 },
-                JsonToken::False => {
-                    return Ok(Some(JsonValue::Boolean(false)));
+                Some(JsonToken::EndArray) | Some(JsonToken::EndObject) => {
+                    let closer = if self.peek() == Some(&JsonToken::EndArray) { ']' } else { '}' };
+                    if self.open.contains(&closer) {
+                        // Closes an enclosing array or object, so this one is unclosed.
+                        self.report(unclosed(), span, expected)?;
+                        return Ok(false);
+                    }
+                    self.report(ParseErrorKind::InvalidToken, span, expected)?;
+                    self.next();
                 },
-                JsonToken::Null => {
-                    return Ok(Some(JsonValue::Null));
+                Some(_) => {
+                    // Most likely a missing comma.
+                    self.report(ParseErrorKind::InvalidToken, span, expected)?;
+                    return Ok(true);
                 },
-                JsonToken::String(s) => {
-                    return JsonParser::token_to_string(s); 
+                None => {
+                    self.report(unclosed(), span, expected)?;
+                    return Ok(false);
                 },
-                _ => {
-                    return Err(JsonParseError{ kind: ParseErrorKind::InvalidToken}); 
-                }
             }
         }
-        Ok(None)
     }
 
-    fn get_object(&mut self) -> Result<JsonObject, JsonParseError> {
-        while let Some(token) = self.peek() {
-            match token {
-                JsonToken::WhiteSpace(_) => {
-                    self.next();
-                    continue;
-                },
-                JsonToken::String(s) => {
-                    if let Some(JsonValue::String(key)) = JsonParser::token_to_string(s)?{ 
-                        self.next();
-                        while let Some(token) = self.peek() {
-                            match token {
-                                JsonToken::WhiteSpace(_) => { 
-                                    self.next();
-                                    continue;
-                                },
-                                JsonToken::NameSeparator => {
-                                    self.next();
-                                    return Ok(JsonObject::new(&key, self.next_value()?));
-                                },
-                                _ => {
-                                    return Err(JsonParseError{ kind: ParseErrorKind::InvalidToken}); 
-                                },
-                            }
+    /// Reads the members of an object after its left curly bracket.
+    fn get_objects(&mut self) -> Result<JsonValue, JsonParseError> {
+        let mut objects = Vec::new();
+        if let Some(JsonToken::EndObject) = self.peek() {
+            self.next();
+            return Ok(JsonValue::Objects(objects));
+        }
+        loop {
+            if let Some(object) = self.get_object()? {
+                objects.push(object);
+            }
+            if !self.next_separator(JsonToken::EndObject)? {
+                return Ok(JsonValue::Objects(objects));
+            }
+        }
+    }
+
+    /// Reads the elements of an array after its left square bracket.
+    fn get_array(&mut self) -> Result<JsonValue, JsonParseError> {
+        let mut values = Vec::new();
+        if let Some(JsonToken::EndArray) = self.peek() {
+            self.next();
+            return Ok(JsonValue::Array(values));
+        }
+        let tolerant = self.errors.is_some();
+        loop {
+            if tolerant {
+                self.path.push(values.len().to_string());
+            }
+            let value = self.next_value()?;
+            if tolerant {
+                self.path.pop();
+            }
+            values.push(value);
+            if !self.next_separator(JsonToken::EndArray)? {
+                return Ok(JsonValue::Array(values));
+            }
+        }
+    }
+
+    /// Reads a member; `None` if its name is missing and the member was skipped.
+    fn get_object(&mut self) -> Result<Option<JsonObject>, JsonParseError> {
+        let span = self.span();
+        let name = match self.peek() {
+            Some(JsonToken::String(_)) => self.next_string()?.unwrap_or_default(),
+            _ => {
+                self.report(ParseErrorKind::NoObjectName, span, NAME)?;
+                self.skip_to_separator();
+                return Ok(None);
+            }
+        };
+        let tolerant = self.errors.is_some();
+        if tolerant {
+            self.path.push(name.clone());
+        }
+        let span = self.span();
+        let value = match self.peek() {
+            Some(JsonToken::NameSeparator) => {
+                self.next();
+                self.next_value()?
+            },
+            Some(JsonToken::ValueSeparator) | Some(JsonToken::EndArray) | Some(JsonToken::EndObject) => {
+                self.report(ParseErrorKind::InvalidToken, span, COLON)?;
+                self.placeholder()
+            },
+            Some(_) => {
+                // Most likely a missing colon.
+                self.report(ParseErrorKind::InvalidToken, span, COLON)?;
+                self.next_value()?
+            },
+            None => {
+                self.report(ParseErrorKind::NonValue, span, COLON)?;
+                self.placeholder()
+            },
+        };
+        if tolerant {
+            self.path.pop();
+        }
+        Ok(Some(JsonObject { name, value }))
+    }
+}
+
+/// Decodes the characters of a string token, joining UTF-16 escapes.
+pub(crate) fn decode_string(s: &[JsonCharToken]) -> Result<String, JsonParseError> {
+    let mut buf = String::new();
+    let mut utf16  = Vec::new();
+    for c in s {
+        match c {
+            JsonCharToken::Escape(c) | JsonCharToken::UnEscaped(c) =>{
+                if !utf16.is_empty() {
+                    match String::from_utf16(&utf16) {
+                        Ok(utf16_str) => {
+                            buf.push_str(&utf16_str);
+                            utf16.clear();
+                        },
+                        Err(e) => {
+                            return Err( JsonParseError::new(ParseErrorKind::FromUtf16Error(e), Span::default()));
                         }
                     }
-                    return Err(JsonParseError{ kind: ParseErrorKind::NoObjectName}); 
-                },
-                _ => {
-                    return Err(JsonParseError{ kind: ParseErrorKind::InvalidToken}); 
                 }
+                buf.push_str(c);
+            },
+            JsonCharToken::Unicode(c) => {
+                utf16.push(*c);
             }
         }
-        unreachable!()
     }
-}
\ No newline at end of file
+    if !utf16.is_empty() {
+        match String::from_utf16(&utf16) {
+            Ok(utf16_str) => {
+                buf.push_str(&utf16_str);
+            },
+            Err(e) => {
+                return Err( JsonParseError::new(ParseErrorKind::FromUtf16Error(e), Span::default()));
+            }
+        }
+    }
+    Ok(buf)
+}
@@ -0,0 +1,79 @@
+//! JSON Pointer ([RFC 6901](https://www.rfc-editor.org/rfc/rfc6901)) helpers.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::error::PointerError;
+use crate::json::JsonValue;
+
+/// Escapes a reference token, replacing `~` with `~0` and `/` with `~1`.
+pub fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Unescapes a reference token, replacing `~1` with `/` and `~0` with `~`.
+///
+/// Returns `None` if the token contains `~` that is not followed by `0` or `1`.
+pub fn unescape(token: &str) -> Option<String> {
+    let mut buf = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        if c == '~' {
+            match chars.next() {
+                Some('0') => buf.push('~'),
+                Some('1') => buf.push('/'),
+                _ => return None,
+            }
+        } else {
+            buf.push(c);
+        }
+    }
+    Some(buf)
+}
+
+/// Splits a JSON Pointer into its unescaped reference tokens.
+///
+/// The empty string points to the whole document and has no tokens.
+pub fn parse(pointer: &str) -> Result<Vec<String>, PointerError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return Err(PointerError { pointer: pointer.to_string() });
+    }
+    pointer[1..].split('/')
+        .map(|token| unescape(token).ok_or_else(|| PointerError { pointer: pointer.to_string() }))
+        .collect()
+}
+
+/// Builds a JSON Pointer from unescaped reference tokens.
+pub fn join<I, S>(tokens: I) -> String where I: IntoIterator<Item = S>, S: AsRef<str> {
+    let mut pointer = String::new();
+    for token in tokens {
+        pointer.push('/');
+        pointer.push_str(&escape(token.as_ref()));
+    }
+    pointer
+}
+
+/// Parses an array index token. Leading zeros and signs are not allowed.
+pub(crate) fn parse_index(token: &str) -> Option<usize> {
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+/// Looks up `tokens` in `value` one reference token at a time.
+pub(crate) fn resolve<'a, S: AsRef<str>>(value: &'a JsonValue, tokens: &[S]) -> Option<&'a JsonValue> {
+    let mut current = value;
+    for token in tokens {
+        let token = token.as_ref();
+        current = match current {
+            JsonValue::Objects(_) => current.get(token)?,
+            JsonValue::Array(values) => values.get(parse_index(token)?)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
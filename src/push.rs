@@ -0,0 +1,469 @@
+//! A push parser for JSON that arrives in chunks.
+//!
+//! [`PushParser::feed`] accepts any split of the input, even in the middle of a string, an
+//! escape, a number or a UTF-8 sequence, and keeps the partial token until the next chunk.
+//! Events are available as soon as the bytes that complete them have been fed, and
+//! [`PushParser::next_value`] assembles them into values.
+//!
+//! The input may hold several JSON texts separated by whitespace, as in newline-delimited JSON.
+
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::{JsonLexerError, JsonParseError, LexErrorKind, ParseErrorKind};
+use crate::json::{JsonObject, JsonValue};
+use crate::lexer::{JsonLexer, JsonToken};
+use crate::parser::{decode_string, MAX_DEPTH};
+use crate::span::Span;
+
+const VALUE: &[&str] = &["value"];
+const NAME: &[&str] = &["string"];
+const COLON: &[&str] = &["`:`"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    /// The name of an object member; its value follows.
+    Name(String),
+    String(String),
+    Number(f64),
+    /// An exact number, sent for every number when the `arbitrary_precision` feature is on.
+    #[cfg(feature = "arbitrary_precision")]
+    Decimal(crate::decimal::Decimal),
+    Boolean(bool),
+    Null,
+}
+
+/// The token being read when a chunk ends.
+#[derive(Debug)]
+enum Partial {
+    None,
+    /// `true`, `false` or `null`, of which `matched` bytes have been read.
+    Literal { start: usize, text: &'static [u8], matched: usize },
+    Number { start: usize, text: String, state: Number },
+    /// The raw bytes of a string, from its opening quotation mark.
+    String { start: usize, raw: Vec<u8>, escaped: bool },
+}
+
+/// Position in the number grammar after the bytes read so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Number {
+    Minus,
+    Zero,
+    Integer,
+    DecimalPoint,
+    Fraction,
+    Exponent,
+    ExponentSign,
+    ExponentDigits,
+}
+
+/// What the grammar allows next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    /// A value at the top level or after a colon.
+    Value,
+    /// A value or `]` after `[`.
+    FirstElement,
+    /// A value after a comma in an array.
+    Element,
+    /// A name or `}` after `{`.
+    FirstName,
+    /// A name after a comma in an object.
+    Name,
+    Colon,
+    /// A comma or the closing bracket after a value in an array or object.
+    Separator,
+}
+
+/// Assembles events into values.
+#[derive(Debug, Default)]
+struct Builder {
+    /// Open arrays and objects, with the member name each one is the value of.
+    stack: Vec<(JsonValue, Option<String>)>,
+    name: Option<String>,
+}
+
+impl Builder {
+    /// Adds an event; returns a top-level value once it is complete.
+    fn push(&mut self, event: Event) -> Option<JsonValue> {
+        let value = match event {
+            Event::BeginObject | Event::BeginArray => {
+                let container = if event == Event::BeginObject { JsonValue::Objects(Vec::new()) } else { JsonValue::Array(Vec::new()) };
+                self.stack.push((container, self.name.take()));
+                return None;
+            },
+            Event::Name(name) => {
+                self.name = Some(name);
+                return None;
+            },
+            Event::EndObject | Event::EndArray => {
+                let (container, name) = self.stack.pop()?;
+                self.name = name;
+                container
+            },
+            Event::String(s) => JsonValue::String(s),
+            Event::Number(n) => JsonValue::Number(n),
+            #[cfg(feature = "arbitrary_precision")]
+            Event::Decimal(d) => JsonValue::Decimal(d),
+            Event::Boolean(b) => JsonValue::Boolean(b),
+            Event::Null => JsonValue::Null,
+        };
+        match self.stack.last_mut() {
+            Some((JsonValue::Array(values), _)) => values.push(value),
+            Some((JsonValue::Objects(objects), _)) => objects.push(JsonObject { name: self.name.take().unwrap_or_default(), value }),
+            Some(_) => unreachable!(),
+            None => return Some(value),
+        }
+        None
+    }
+}
+
+/// An incremental parser that is fed bytes instead of reading them.
+///
+/// After an error has been returned, further input is ignored.
+#[derive(Debug)]
+pub struct PushParser {
+    partial: Partial,
+    expect: Expect,
+    /// Whether each open container is an object.
+    stack: Vec<bool>,
+    /// Offset of the next byte in the whole input.
+    offset: usize,
+    /// Number of complete top-level values.
+    values: usize,
+    /// Whether a second top-level value is an error, as in a single JSON text.
+    single: bool,
+    failed: bool,
+    events: VecDeque<Event>,
+    builder: Builder,
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        PushParser::new()
+    }
+}
+
+impl PushParser {
+    pub fn new() -> PushParser {
+        PushParser {
+            partial: Partial::None,
+            expect: Expect::Value,
+            stack: Vec::new(),
+            offset: 0,
+            values: 0,
+            single: false,
+            failed: false,
+            events: VecDeque::new(),
+            builder: Builder::default(),
+        }
+    }
+
+    /// Creates a parser for exactly one JSON text, like `str::parse`.
+    pub(crate) fn single() -> PushParser {
+        PushParser { single: true, ..PushParser::new() }
+    }
+
+    /// Parses the next chunk of input.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), JsonParseError> {
+        if self.failed {
+            return Ok(());
+        }
+        for &b in chunk {
+            if let Err(e) = self.byte(b) {
+                self.failed = true;
+                return Err(e);
+            }
+            self.offset += 1;
+        }
+        Ok(())
+    }
+
+    /// Ends the input, completing a trailing number and checking that every value is complete.
+    pub fn finish(&mut self) -> Result<(), JsonParseError> {
+        if self.failed {
+            return Ok(());
+        }
+        self.failed = true;
+        let end = Span::new(self.offset, self.offset);
+        match core::mem::replace(&mut self.partial, Partial::None) {
+            Partial::None => {},
+            Partial::Number { start, text, state } => self.end_number(start, &text, state)?,
+            Partial::String { start, .. } => return Err(self.lex_error(LexErrorKind::NonQuotationMark, start, self.offset)),
+            Partial::Literal { start, text, .. } => return Err(self.lex_error(LexErrorKind::InvalidChar(text[0] as char), start, self.offset)),
+        }
+        match self.stack.last() {
+            Some(&object) => {
+                let kind = if object { ParseErrorKind::NonEndObject } else { ParseErrorKind::NonEndArray };
+                Err(JsonParseError { kind, span: end, expected: self.expected() })
+            },
+            None if self.values == 0 => Err(JsonParseError { kind: ParseErrorKind::NonValue, span: end, expected: VALUE }),
+            None => Ok(()),
+        }
+    }
+
+    /// Takes the next event.
+    pub fn next_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Takes the next complete top-level value.
+    ///
+    /// Use either this or [`next_event`](Self::next_event): both take from the same events.
+    pub fn next_value(&mut self) -> Option<JsonValue> {
+        while let Some(event) = self.events.pop_front() {
+            if let Some(value) = self.builder.push(event) {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn lex_error(&self, kind: LexErrorKind, start: usize, end: usize) -> JsonParseError {
+        JsonLexerError { kind, span: Span::new(start, end) }.into()
+    }
+
+    fn byte(&mut self, b: u8) -> Result<(), JsonParseError> {
+        let offset = self.offset;
+        match &mut self.partial {
+            Partial::None => {},
+            Partial::String { raw, escaped, .. } => {
+                if b < 0x20 {
+                    return Err(self.lex_error(LexErrorKind::InvalidChar(b as char), offset, offset + 1));
+                }
+                raw.push(b);
+                if *escaped {
+                    *escaped = false;
+                } else if b == b'\\' {
+                    *escaped = true;
+                } else if b == b'"' {
+                    return self.end_string();
+                }
+                return Ok(());
+            },
+            Partial::Literal { start, text, matched } => {
+                let (start, text) = (*start, *text);
+                if text[*matched] != b {
+                    return Err(self.lex_error(LexErrorKind::InvalidChar(text[0] as char), start, start + 1));
+                }
+                *matched += 1;
+                if *matched == text.len() {
+                    self.partial = Partial::None;
+                    let event = match text {
+                        b"true" => Event::Boolean(true),
+                        b"false" => Event::Boolean(false),
+                        _ => Event::Null,
+                    };
+                    self.value(event, Span::new(start, offset + 1))?;
+                }
+                return Ok(());
+            },
+            Partial::Number { start, text, state } => {
+                let start = *start;
+                match number_step(Some(*state), b) {
+                    Ok(Some(next)) => {
+                        *state = next;
+                        text.push(b as char);
+                        return Ok(());
+                    },
+                    // The byte is not part of the number; read it as the start of the next token.
+                    Ok(None) => {
+                        let (text, state) = (core::mem::take(text), *state);
+                        self.partial = Partial::None;
+                        self.end_number(start, &text, state)?;
+                    },
+                    Err(LexErrorKind::LeadingZero) => return Err(self.lex_error(LexErrorKind::LeadingZero, start, offset + 1)),
+                    Err(kind) => return Err(self.lex_error(kind, start, offset)),
+                }
+            },
+        }
+        let span = Span::new(offset, offset + 1);
+        match b {
+            b' ' | b'\t' | b'\n' | b'\r' => Ok(()),
+            b'{' | b'[' => {
+                self.begin_value(span)?;
+                if self.stack.len() >= MAX_DEPTH {
+                    return Err(JsonParseError::new(ParseErrorKind::RecursionLimitExceeded, span));
+                }
+                let object = b == b'{';
+                self.stack.push(object);
+                self.expect = if object { Expect::FirstName } else { Expect::FirstElement };
+                self.events.push_back(if object { Event::BeginObject } else { Event::BeginArray });
+                Ok(())
+            },
+            b'}' | b']' => {
+                let object = b == b'}';
+                match self.expect {
+                    Expect::FirstName | Expect::Separator if object && self.stack.last() == Some(&true) => {},
+                    Expect::FirstElement | Expect::Separator if !object && self.stack.last() == Some(&false) => {},
+                    Expect::Name if object => return Err(JsonParseError { kind: ParseErrorKind::TrailingComma, span, expected: NAME }),
+                    Expect::Element if !object => return Err(JsonParseError { kind: ParseErrorKind::TrailingComma, span, expected: VALUE }),
+                    _ => return Err(self.invalid(span)),
+                }
+                self.stack.pop();
+                self.events.push_back(if object { Event::EndObject } else { Event::EndArray });
+                self.after_value();
+                Ok(())
+            },
+            b',' if self.expect == Expect::Separator => {
+                self.expect = if self.stack.last() == Some(&true) { Expect::Name } else { Expect::Element };
+                Ok(())
+            },
+            b':' if self.expect == Expect::Colon => {
+                self.expect = Expect::Value;
+                Ok(())
+            },
+            b',' | b':' => Err(self.invalid(span)),
+            b'"' => {
+                self.partial = Partial::String { start: offset, raw: vec![b], escaped: false };
+                Ok(())
+            },
+            b't' | b'f' | b'n' => {
+                let text: &'static [u8] = match b {
+                    b't' => b"true",
+                    b'f' => b"false",
+                    _ => b"null",
+                };
+                self.partial = Partial::Literal { start: offset, text, matched: 1 };
+                Ok(())
+            },
+            b'-' | b'0'..=b'9' => {
+                let state = number_step(None, b).unwrap().unwrap();
+                self.partial = Partial::Number { start: offset, text: (b as char).to_string(), state };
+                Ok(())
+            },
+            _ => {
+                // Only the first byte of a multi-byte character is known here.
+                let c = if b.is_ascii() { b as char } else { char::REPLACEMENT_CHARACTER };
+                Err(self.lex_error(LexErrorKind::InvalidChar(c), offset, offset + 1))
+            },
+        }
+    }
+
+    /// What the grammar allows in the current state, for error messages.
+    fn expected(&self) -> &'static [&'static str] {
+        match self.expect {
+            Expect::Value | Expect::Element => VALUE,
+            Expect::FirstElement => &["value", "`]`"],
+            Expect::FirstName => &["string", "`}`"],
+            Expect::Name => NAME,
+            Expect::Colon => COLON,
+            Expect::Separator if self.stack.last() == Some(&true) => &["`,`", "`}`"],
+            Expect::Separator => &["`,`", "`]`"],
+        }
+    }
+
+    fn invalid(&self, span: Span) -> JsonParseError {
+        JsonParseError { kind: ParseErrorKind::InvalidToken, span, expected: self.expected() }
+    }
+
+    /// Checks that a value may start here.
+    fn begin_value(&self, span: Span) -> Result<(), JsonParseError> {
+        match self.expect {
+            Expect::Value if self.single && self.values > 0 && self.stack.is_empty() => {
+                Err(JsonParseError { kind: ParseErrorKind::InvalidToken, span, expected: &["end of input"] })
+            },
+            Expect::Value | Expect::FirstElement | Expect::Element => Ok(()),
+            Expect::FirstName | Expect::Name => Err(JsonParseError { kind: ParseErrorKind::NoObjectName, span, expected: self.expected() }),
+            Expect::Colon | Expect::Separator => Err(self.invalid(span)),
+        }
+    }
+
+    fn after_value(&mut self) {
+        if self.stack.is_empty() {
+            self.values += 1;
+            self.expect = Expect::Value;
+        } else {
+            self.expect = Expect::Separator;
+        }
+    }
+
+    /// Emits a scalar value.
+    fn value(&mut self, event: Event, span: Span) -> Result<(), JsonParseError> {
+        self.begin_value(span)?;
+        self.events.push_back(event);
+        self.after_value();
+        Ok(())
+    }
+
+    fn end_number(&mut self, start: usize, text: &str, state: Number) -> Result<(), JsonParseError> {
+        let kind = match state {
+            Number::Minus => LexErrorKind::NotDigit,
+            Number::DecimalPoint => LexErrorKind::NonFracDigit,
+            Number::Exponent | Number::ExponentSign => LexErrorKind::EmptyExponent,
+            _ => {
+                let span = Span::new(start, start + text.len());
+                #[cfg(feature = "arbitrary_precision")]
+                return match text.parse() {
+                    Ok(d) => self.value(Event::Decimal(d), span),
+                    Err(_) => Err(JsonParseError::new(ParseErrorKind::NumberOutOfRange, span)),
+                };
+                #[cfg(not(feature = "arbitrary_precision"))]
+                return match text.parse() {
+                    Ok(n) => self.value(Event::Number(n), span),
+                    Err(e) => Err(JsonParseError::new(ParseErrorKind::ParseFloatError(e), span)),
+                };
+            },
+        };
+        Err(self.lex_error(kind, start, start + text.len()))
+    }
+
+    /// Decodes a string whose closing quotation mark was just read.
+    fn end_string(&mut self) -> Result<(), JsonParseError> {
+        let (start, raw) = match core::mem::replace(&mut self.partial, Partial::None) {
+            Partial::String { start, raw, .. } => (start, raw),
+            _ => unreachable!(),
+        };
+        let span = Span::new(start, start + raw.len());
+        let text = core::str::from_utf8(&raw).map_err(|e| {
+            let at = start + e.valid_up_to();
+            self.lex_error(LexErrorKind::InvalidUtf8, at, at + e.error_len().unwrap_or(1))
+        })?;
+        // The string is complete, so the lexer validates its escapes.
+        let chars = match JsonLexer::new(text).tokenize() {
+            Ok(mut tokens) => match tokens.tokens.pop() {
+                Some(JsonToken::String(chars)) => chars,
+                _ => unreachable!(),
+            },
+            Err(mut e) => {
+                e.span = Span::new(start + e.span.start, start + e.span.end);
+                return Err(e.into());
+            },
+        };
+        let s = decode_string(&chars).map_err(|e| JsonParseError::new(e.kind, span))?;
+        if matches!(self.expect, Expect::FirstName | Expect::Name) {
+            self.events.push_back(Event::Name(s));
+            self.expect = Expect::Colon;
+            Ok(())
+        } else {
+            self.value(Event::String(s), span)
+        }
+    }
+}
+
+/// Advances the number grammar by one byte; `Ok(None)` if the byte ends the number.
+fn number_step(state: Option<Number>, b: u8) -> Result<Option<Number>, LexErrorKind> {
+    let digit = b.is_ascii_digit();
+    Ok(Some(match (state, b) {
+        (None, b'-') => Number::Minus,
+        (None | Some(Number::Minus), b'0') => Number::Zero,
+        (None | Some(Number::Minus), _) if digit => Number::Integer,
+        (Some(Number::Minus), _) => return Err(LexErrorKind::NotDigit),
+        (Some(Number::Zero), _) if digit => return Err(LexErrorKind::LeadingZero),
+        (Some(Number::Integer), _) if digit => Number::Integer,
+        (Some(Number::Zero | Number::Integer), b'.') => Number::DecimalPoint,
+        (Some(Number::DecimalPoint | Number::Fraction), _) if digit => Number::Fraction,
+        (Some(Number::DecimalPoint), _) => return Err(LexErrorKind::NonFracDigit),
+        (Some(Number::Zero | Number::Integer | Number::Fraction), b'e' | b'E') => Number::Exponent,
+        (Some(Number::Exponent), b'+' | b'-') => Number::ExponentSign,
+        (Some(Number::Exponent | Number::ExponentSign | Number::ExponentDigits), _) if digit => Number::ExponentDigits,
+        (Some(Number::Exponent | Number::ExponentSign), _) => return Err(LexErrorKind::EmptyExponent),
+        _ => return Ok(None),
+    }))
+}
@@ -0,0 +1,522 @@
+//! A small regular expression engine.
+//!
+//! Patterns compile to a Thompson NFA that is run Pike-VM style, keeping every live state at
+//! once, so matching never backtracks or recurses and takes time linear in the input.
+//! It understands the subset of ECMA-262 regular expressions that JSON Schema `pattern`
+//! and the JSONPath `match`/`search` functions use in practice: literals, `.`, character
+//! classes, the `\d \w \s` shorthands, anchors, groups, alternation and greedy or lazy
+//! quantifiers. Look-around and back references are rejected.
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::RegexError;
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            ClassItem::Range(from, to) => from <= c && c <= to,
+            ClassItem::Digit(negated) => c.is_ascii_digit() != negated,
+            ClassItem::Word(negated) => is_word(c) != negated,
+            ClassItem::Space(negated) => is_space(c) != negated,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class { negated: bool, items: Vec<ClassItem> },
+    Start,
+    End,
+    WordBoundary(bool),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat { node: Box<Node>, min: u32, max: Option<u32> },
+}
+
+fn is_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_space(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\u{b}' | '\u{c}' | '\r' | ' ' | '\u{a0}' | '\u{1680}' | '\u{2000}'..='\u{200a}'
+        | '\u{2028}' | '\u{2029}' | '\u{202f}' | '\u{205f}' | '\u{3000}' | '\u{feff}')
+}
+
+fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+struct RegexParser<'a> {
+    pattern: &'a str,
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl RegexParser<'_> {
+    fn error(&self, reason: &'static str) -> RegexError {
+        RegexError { pattern: self.pattern.to_string(), reason }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn alternation(&mut self) -> Result<Node, RegexError> {
+        let mut alternatives = vec![self.concatenation()?];
+        while self.eat('|') {
+            alternatives.push(self.concatenation()?);
+        }
+        if alternatives.len() == 1 {
+            Ok(alternatives.pop().unwrap())
+        } else {
+            Ok(Node::Alt(alternatives))
+        }
+    }
+
+    fn concatenation(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantifier(atom)?);
+        }
+        match nodes.len() {
+            0 => Ok(Node::Empty),
+            1 => Ok(nodes.pop().unwrap()),
+            _ => Ok(Node::Concat(nodes)),
+        }
+    }
+
+    fn quantifier(&mut self, atom: Node) -> Result<Node, RegexError> {
+        let start = self.position;
+        let (min, max) = match self.peek() {
+            Some('*') => { self.next(); (0, None) },
+            Some('+') => { self.next(); (1, None) },
+            Some('?') => { self.next(); (0, Some(1)) },
+            Some('{') => {
+                self.next();
+                match self.bounds() {
+                    Some(bounds) => bounds,
+                    None => {
+                        // Not a quantifier, so the brace is read as a literal later.
+                        self.position = start;
+                        return Ok(atom);
+                    }
+                }
+            },
+            _ => return Ok(atom),
+        };
+        if matches!(atom, Node::Start | Node::End | Node::WordBoundary(_)) {
+            return Err(self.error("nothing to repeat"));
+        }
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error("numbers out of order in quantifier"));
+        }
+        // A lazy quantifier only changes which match is found, not whether there is one.
+        self.eat('?');
+        Ok(Node::Repeat { node: Box::new(atom), min, max })
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        String::from_iter(&self.chars[start..self.position]).parse().ok()
+    }
+
+    fn bounds(&mut self) -> Option<(u32, Option<u32>)> {
+        let min = self.number()?;
+        let max = if self.eat(',') {
+            if self.peek() == Some('}') { None } else { Some(self.number()?) }
+        } else {
+            Some(min)
+        };
+        if self.eat('}') { Some((min, max)) } else { None }
+    }
+
+    fn atom(&mut self) -> Result<Node, RegexError> {
+        match self.next() {
+            Some('(') => {
+                if self.eat('?') && !self.eat(':') {
+                    return Err(self.error("look-around and named groups are not supported"));
+                }
+                let node = self.alternation()?;
+                if !self.eat(')') {
+                    return Err(self.error("missing ')'"));
+                }
+                Ok(node)
+            },
+            Some('[') => self.class(),
+            Some('.') => Ok(Node::Any),
+            Some('^') => Ok(Node::Start),
+            Some('$') => Ok(Node::End),
+            Some('\\') => self.escape(),
+            Some('*') | Some('+') | Some('?') => Err(self.error("nothing to repeat")),
+            Some(c) => Ok(Node::Char(c)),
+            None => Err(self.error("unexpected end of pattern")),
+        }
+    }
+
+    fn escape(&mut self) -> Result<Node, RegexError> {
+        let item = match self.peek() {
+            Some('b') => { self.next(); return Ok(Node::WordBoundary(false)); },
+            Some('B') => { self.next(); return Ok(Node::WordBoundary(true)); },
+            Some('1'..='9') => return Err(self.error("back references are not supported")),
+            _ => self.class_escape(false)?,
+        };
+        match item {
+            ClassItem::Range(from, to) if from == to => Ok(Node::Char(from)),
+            item => Ok(Node::Class { negated: false, items: vec![item] }),
+        }
+    }
+
+    fn hex(&mut self, digits: usize) -> Result<u32, RegexError> {
+        let end = self.position + digits;
+        if end > self.chars.len() {
+            return Err(self.error("incomplete escape"));
+        }
+        let hex = String::from_iter(&self.chars[self.position..end]);
+        self.position = end;
+        u32::from_str_radix(&hex, 16).map_err(|_| self.error("invalid hex escape"))
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, RegexError> {
+        if self.eat('{') {
+            let start = self.position;
+            while self.peek().is_some_and(|c| c != '}') {
+                self.position += 1;
+            }
+            let hex = String::from_iter(&self.chars[start..self.position]);
+            if !self.eat('}') {
+                return Err(self.error("incomplete escape"));
+            }
+            let code = u32::from_str_radix(&hex, 16).map_err(|_| self.error("invalid unicode escape"))?;
+            return char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+        let high = self.hex(4)?;
+        if (0xD800..0xDC00).contains(&high) && self.chars.get(self.position..self.position + 2) == Some(&['\\', 'u']) {
+            let save = self.position;
+            self.position += 2;
+            let low = self.hex(4)?;
+            if (0xDC00..0xE000).contains(&low) {
+                let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                return char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"));
+            }
+            self.position = save;
+        }
+        char::from_u32(high).ok_or_else(|| self.error("lone surrogate in unicode escape"))
+    }
+
+    fn class_escape(&mut self, in_class: bool) -> Result<ClassItem, RegexError> {
+        let c = match self.next() {
+            Some(c) => c,
+            None => return Err(self.error("pattern ends with '\\'")),
+        };
+        let literal = match c {
+            'd' => return Ok(ClassItem::Digit(false)),
+            'D' => return Ok(ClassItem::Digit(true)),
+            'w' => return Ok(ClassItem::Word(false)),
+            'W' => return Ok(ClassItem::Word(true)),
+            's' => return Ok(ClassItem::Space(false)),
+            'S' => return Ok(ClassItem::Space(true)),
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'f' => '\u{c}',
+            'v' => '\u{b}',
+            '0' => '\0',
+            'b' if in_class => '\u{8}',
+            'x' => {
+                let code = self.hex(2)?;
+                char::from_u32(code).ok_or_else(|| self.error("invalid hex escape"))?
+            },
+            'u' => self.unicode_escape()?,
+            'p' | 'P' => return Err(self.error("unicode property escapes are not supported")),
+            c if c.is_ascii_alphanumeric() => return Err(self.error("unknown escape")),
+            c => c,
+        };
+        Ok(ClassItem::Range(literal, literal))
+    }
+
+    fn class_atom(&mut self) -> Result<ClassItem, RegexError> {
+        match self.next() {
+            Some('\\') => self.class_escape(true),
+            Some(c) => Ok(ClassItem::Range(c, c)),
+            None => Err(self.error("missing ']'")),
+        }
+    }
+
+    fn class(&mut self) -> Result<Node, RegexError> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        while !self.eat(']') {
+            let item = self.class_atom()?;
+            if self.peek() == Some('-') && self.chars.get(self.position + 1).is_some_and(|&c| c != ']') {
+                self.next();
+                let to = self.class_atom()?;
+                match (item, to) {
+                    (ClassItem::Range(from, _), ClassItem::Range(to, _)) => {
+                        if to < from {
+                            return Err(self.error("range out of order in character class"));
+                        }
+                        items.push(ClassItem::Range(from, to));
+                    },
+                    _ => return Err(self.error("invalid character class range")),
+                }
+            } else {
+                items.push(item);
+            }
+        }
+        Ok(Node::Class { negated, items })
+    }
+}
+
+/// The largest program a pattern may compile to; counted repetitions copy their operand.
+const MAX_PROGRAM: usize = 100_000;
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class { negated: bool, items: Vec<ClassItem> },
+    Start,
+    End,
+    WordBoundary(bool),
+    /// Continues at both targets.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+impl Inst {
+    /// Whether this instruction consumes `c`.
+    fn accepts(&self, c: char) -> bool {
+        match self {
+            Inst::Char(expected) => *expected == c,
+            Inst::Any => !is_line_terminator(c),
+            Inst::Class { negated, items } => items.iter().any(|item| item.matches(c)) != *negated,
+            _ => false,
+        }
+    }
+}
+
+struct Compiler<'a> {
+    pattern: &'a str,
+    program: Vec<Inst>,
+}
+
+impl Compiler<'_> {
+    fn push(&mut self, inst: Inst) -> Result<usize, RegexError> {
+        if self.program.len() >= MAX_PROGRAM {
+            return Err(RegexError { pattern: self.pattern.to_string(), reason: "pattern is too large" });
+        }
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    fn compile(&mut self, node: &Node) -> Result<(), RegexError> {
+        match node {
+            Node::Empty => {},
+            Node::Char(c) => { self.push(Inst::Char(*c))?; },
+            Node::Any => { self.push(Inst::Any)?; },
+            Node::Class { negated, items } => { self.push(Inst::Class { negated: *negated, items: items.clone() })?; },
+            Node::Start => { self.push(Inst::Start)?; },
+            Node::End => { self.push(Inst::End)?; },
+            Node::WordBoundary(negated) => { self.push(Inst::WordBoundary(*negated))?; },
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.compile(node)?;
+                }
+            },
+            Node::Alt(alternatives) => {
+                let mut jumps = Vec::new();
+                for (i, node) in alternatives.iter().enumerate() {
+                    if i + 1 < alternatives.len() {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.compile(node)?;
+                        jumps.push(self.push(Inst::Jump(0))?);
+                        self.program[split] = Inst::Split(split + 1, self.program.len());
+                    } else {
+                        self.compile(node)?;
+                    }
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+            },
+            Node::Repeat { node, min, max } => {
+                for _ in 0..*min {
+                    self.compile(node)?;
+                }
+                match max {
+                    None => {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.compile(node)?;
+                        self.push(Inst::Jump(split))?;
+                        self.program[split] = Inst::Split(split + 1, self.program.len());
+                    },
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(0, 0))?);
+                            self.compile(node)?;
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.program[split] = Inst::Split(split + 1, end);
+                        }
+                    },
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+/// The threads alive at one input position, as a sparse set of program counters.
+struct Threads {
+    dense: Vec<usize>,
+    sparse: Vec<usize>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Threads {
+        Threads { dense: Vec::with_capacity(len), sparse: vec![0; len] }
+    }
+
+    /// Adds `pc`, returning false if it was already present.
+    fn insert(&mut self, pc: usize) -> bool {
+        let i = self.sparse[pc];
+        if i < self.dense.len() && self.dense[i] == pc {
+            return false;
+        }
+        self.sparse[pc] = self.dense.len();
+        self.dense.push(pc);
+        true
+    }
+}
+
+/// A compiled regular expression.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Vec<Inst>,
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, RegexError> {
+        let mut parser = RegexParser { pattern, chars: pattern.chars().collect(), position: 0 };
+        let node = parser.alternation()?;
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("unmatched ')'"));
+        }
+        let mut compiler = Compiler { pattern, program: Vec::new() };
+        compiler.compile(&node)?;
+        compiler.push(Inst::Match)?;
+        Ok(Regex { program: compiler.program })
+    }
+
+    /// Returns true if the pattern matches anywhere in `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.run(text, false)
+    }
+
+    /// Returns true if the pattern matches the whole of `text`.
+    pub fn is_full_match(&self, text: &str) -> bool {
+        self.run(text, true)
+    }
+
+    fn run(&self, text: &str, full: bool) -> bool {
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        let mut stack = Vec::new();
+        let mut chars = text.chars();
+        let mut before = None;
+        let mut at = chars.next();
+        loop {
+            // A search may start at any position, a full match only at the first.
+            if (!full || before.is_none()) && self.add(&mut current, &mut stack, 0, before, at, full) {
+                return true;
+            }
+            let c = match at {
+                Some(c) => c,
+                None => return false,
+            };
+            let after = chars.next();
+            next.dense.clear();
+            for &pc in &current.dense {
+                if self.program[pc].accepts(c) && self.add(&mut next, &mut stack, pc + 1, Some(c), after, full) {
+                    return true;
+                }
+            }
+            if full && next.dense.is_empty() {
+                return false;
+            }
+            core::mem::swap(&mut current, &mut next);
+            before = Some(c);
+            at = after;
+        }
+    }
+
+    /// Adds the threads reachable from `pc` without consuming input, between the characters
+    /// `before` and `at`. Returns true once a thread reaches an accepting match.
+    fn add(&self, threads: &mut Threads, stack: &mut Vec<usize>, pc: usize, before: Option<char>, at: Option<char>,
+        full: bool) -> bool {
+        stack.push(pc);
+        while let Some(pc) = stack.pop() {
+            if !threads.insert(pc) {
+                continue;
+            }
+            match self.program[pc] {
+                Inst::Split(a, b) => {
+                    stack.push(b);
+                    stack.push(a);
+                },
+                Inst::Jump(target) => stack.push(target),
+                Inst::Start if before.is_none() => stack.push(pc + 1),
+                Inst::End if at.is_none() => stack.push(pc + 1),
+                Inst::WordBoundary(negated) if (before.is_some_and(is_word) != at.is_some_and(is_word)) != negated => {
+                    stack.push(pc + 1)
+                },
+                Inst::Match if !full || at.is_none() => {
+                    stack.clear();
+                    return true;
+                },
+                _ => {},
+            }
+        }
+        false
+    }
+}
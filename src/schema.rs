@@ -0,0 +1,649 @@
+//! JSON Schema (draft 2020-12) validation.
+//!
+//! A schema [`JsonValue`] is compiled once into a [`Schema`], which can then validate any
+//! number of instances. Validation reports every failure together with the JSON Pointer of
+//! the offending instance location and of the schema keyword that rejected it.
+//!
+//! ```
+//! use crystalrake::json::JsonValue;
+//! use crystalrake::schema::Schema;
+//!
+//! let schema: JsonValue = r#"{"type": "object", "required": ["id"]}"#.parse().unwrap();
+//! let schema = Schema::compile(&schema).unwrap();
+//! assert!(schema.is_valid(&r#"{"id": 1}"#.parse().unwrap()));
+//! assert!(!schema.is_valid(&"{}".parse().unwrap()));
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use crate::error::{SchemaError, SchemaErrorKind};
+use crate::json::{self, JsonValue};
+use crate::pointer;
+use crate::regex::Regex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TypeName {
+    Null,
+    Boolean,
+    Object,
+    Array,
+    Number,
+    Integer,
+    String,
+}
+
+impl TypeName {
+    fn from_name(name: &str) -> Option<TypeName> {
+        match name {
+            "null" => Some(TypeName::Null),
+            "boolean" => Some(TypeName::Boolean),
+            "object" => Some(TypeName::Object),
+            "array" => Some(TypeName::Array),
+            "number" => Some(TypeName::Number),
+            "integer" => Some(TypeName::Integer),
+            "string" => Some(TypeName::String),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            TypeName::Null => "null",
+            TypeName::Boolean => "boolean",
+            TypeName::Object => "object",
+            TypeName::Array => "array",
+            TypeName::Number => "number",
+            TypeName::Integer => "integer",
+            TypeName::String => "string",
+        }
+    }
+
+    fn matches(&self, value: &JsonValue) -> bool {
+        match (self, value) {
+            (TypeName::Null, JsonValue::Null) => true,
+            (TypeName::Boolean, JsonValue::Boolean(_)) => true,
+            (TypeName::Object, JsonValue::Objects(_)) => true,
+            (TypeName::Array, JsonValue::Array(_)) => true,
+            (TypeName::Number, _) => value.is_number(),
+            (TypeName::Integer, JsonValue::Number(n)) => json::is_integer(*n),
+            #[cfg(feature = "arbitrary_precision")]
+            (TypeName::Integer, JsonValue::Decimal(d)) => d.is_integer(),
+            (TypeName::String, JsonValue::String(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Keyword {
+    Ref(usize),
+    Type(Vec<TypeName>),
+    Enum(Vec<JsonValue>),
+    Const(JsonValue),
+    Minimum(f64),
+    Maximum(f64),
+    ExclusiveMinimum(f64),
+    ExclusiveMaximum(f64),
+    MultipleOf(f64),
+    MinLength(usize),
+    MaxLength(usize),
+    Pattern(Regex, String),
+    MinItems(usize),
+    MaxItems(usize),
+    UniqueItems,
+    PrefixItems(Vec<usize>),
+    Items { node: usize, skip: usize },
+    MinProperties(usize),
+    MaxProperties(usize),
+    Required(Vec<String>),
+    Properties(Vec<(String, usize)>),
+    PatternProperties(Vec<(Regex, String, usize)>),
+    AdditionalProperties { node: usize, names: Vec<String>, patterns: Vec<Regex> },
+    AllOf(Vec<usize>),
+    AnyOf(Vec<usize>),
+    OneOf(Vec<usize>),
+    Not(usize),
+}
+
+impl Keyword {
+    fn name(&self) -> &'static str {
+        match self {
+            Keyword::Ref(_) => "$ref",
+            Keyword::Type(_) => "type",
+            Keyword::Enum(_) => "enum",
+            Keyword::Const(_) => "const",
+            Keyword::Minimum(_) => "minimum",
+            Keyword::Maximum(_) => "maximum",
+            Keyword::ExclusiveMinimum(_) => "exclusiveMinimum",
+            Keyword::ExclusiveMaximum(_) => "exclusiveMaximum",
+            Keyword::MultipleOf(_) => "multipleOf",
+            Keyword::MinLength(_) => "minLength",
+            Keyword::MaxLength(_) => "maxLength",
+            Keyword::Pattern(..) => "pattern",
+            Keyword::MinItems(_) => "minItems",
+            Keyword::MaxItems(_) => "maxItems",
+            Keyword::UniqueItems => "uniqueItems",
+            Keyword::PrefixItems(_) => "prefixItems",
+            Keyword::Items { .. } => "items",
+            Keyword::MinProperties(_) => "minProperties",
+            Keyword::MaxProperties(_) => "maxProperties",
+            Keyword::Required(_) => "required",
+            Keyword::Properties(_) => "properties",
+            Keyword::PatternProperties(_) => "patternProperties",
+            Keyword::AdditionalProperties { .. } => "additionalProperties",
+            Keyword::AllOf(_) => "allOf",
+            Keyword::AnyOf(_) => "anyOf",
+            Keyword::OneOf(_) => "oneOf",
+            Keyword::Not(_) => "not",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum SchemaNode {
+    Bool(bool),
+    Keywords(Vec<Keyword>),
+}
+
+/// A compiled JSON Schema.
+#[derive(Debug)]
+pub struct Schema {
+    nodes: Vec<SchemaNode>,
+}
+
+/// Enum to store the reasons an instance can fail validation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationErrorKind {
+    /// The schema was `false`.
+    FalseSchema,
+    /// The instance type is not one of the expected types.
+    Type { expected: Vec<&'static str>, found: &'static str },
+    /// The instance is not one of the `enum` values.
+    Enum,
+    /// The instance is not equal to the `const` value.
+    Const,
+    Minimum(f64),
+    Maximum(f64),
+    ExclusiveMinimum(f64),
+    ExclusiveMaximum(f64),
+    MultipleOf(f64),
+    MinLength(usize),
+    MaxLength(usize),
+    /// The string does not match the pattern.
+    Pattern(String),
+    MinItems(usize),
+    MaxItems(usize),
+    /// The array contains equal items.
+    UniqueItems,
+    MinProperties(usize),
+    MaxProperties(usize),
+    /// A required property is missing.
+    Required(String),
+    /// A property is not allowed by `additionalProperties`.
+    AdditionalProperty(String),
+    /// None of the `anyOf` subschemas matched.
+    AnyOf,
+    /// A number of `oneOf` subschemas other than one matched. Contains the number.
+    OneOf(usize),
+    /// The `not` subschema matched.
+    Not,
+}
+
+/// A single validation failure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    instance_path: String,
+    schema_path: String,
+    kind: ValidationErrorKind,
+}
+
+impl ValidationError {
+    pub fn kind(&self) -> &ValidationErrorKind {
+        &self.kind
+    }
+
+    /// JSON Pointer to the instance location that failed.
+    pub fn instance_path(&self) -> &str {
+        &self.instance_path
+    }
+
+    /// JSON Pointer to the schema keyword that rejected the instance, following `$ref`s.
+    pub fn schema_path(&self) -> &str {
+        &self.schema_path
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "at '{}': ", self.instance_path)?;
+        match &self.kind {
+            ValidationErrorKind::FalseSchema => write!(f, "no value is allowed here")?,
+            ValidationErrorKind::Type { expected, found } => write!(f, "expected {}, found {}", expected.join(" or "), found)?,
+            ValidationErrorKind::Enum => write!(f, "value is not one of the enumerated values")?,
+            ValidationErrorKind::Const => write!(f, "value is not equal to the constant")?,
+            ValidationErrorKind::Minimum(n) => write!(f, "value is less than {}", n)?,
+            ValidationErrorKind::Maximum(n) => write!(f, "value is greater than {}", n)?,
+            ValidationErrorKind::ExclusiveMinimum(n) => write!(f, "value is not greater than {}", n)?,
+            ValidationErrorKind::ExclusiveMaximum(n) => write!(f, "value is not less than {}", n)?,
+            ValidationErrorKind::MultipleOf(n) => write!(f, "value is not a multiple of {}", n)?,
+            ValidationErrorKind::MinLength(n) => write!(f, "string is shorter than {} characters", n)?,
+            ValidationErrorKind::MaxLength(n) => write!(f, "string is longer than {} characters", n)?,
+            ValidationErrorKind::Pattern(p) => write!(f, "string does not match pattern '{}'", p)?,
+            ValidationErrorKind::MinItems(n) => write!(f, "array has fewer than {} items", n)?,
+            ValidationErrorKind::MaxItems(n) => write!(f, "array has more than {} items", n)?,
+            ValidationErrorKind::UniqueItems => write!(f, "array items are not unique")?,
+            ValidationErrorKind::MinProperties(n) => write!(f, "object has fewer than {} properties", n)?,
+            ValidationErrorKind::MaxProperties(n) => write!(f, "object has more than {} properties", n)?,
+            ValidationErrorKind::Required(name) => write!(f, "missing required property '{}'", name)?,
+            ValidationErrorKind::AdditionalProperty(name) => write!(f, "property '{}' is not allowed", name)?,
+            ValidationErrorKind::AnyOf => write!(f, "value does not match any of the subschemas")?,
+            ValidationErrorKind::OneOf(n) => write!(f, "value matches {} subschemas, expected exactly one", n)?,
+            ValidationErrorKind::Not => write!(f, "value must not match the subschema")?,
+        }
+        write!(f, " (schema '{}')", self.schema_path)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError {}
+
+struct Compiler<'a> {
+    root: &'a JsonValue,
+    nodes: Vec<SchemaNode>,
+    compiled: BTreeMap<String, usize>,
+}
+
+impl Compiler<'_> {
+    fn error(kind: SchemaErrorKind, pointer: &str) -> SchemaError {
+        SchemaError { kind, pointer: pointer.to_string() }
+    }
+
+    fn invalid(keyword: &str, pointer: &str) -> SchemaError {
+        Compiler::error(SchemaErrorKind::InvalidKeyword(keyword.to_string()), pointer)
+    }
+
+    /// Compiles the subschema at `location`, reusing it if it was already compiled.
+    fn compile(&mut self, location: &str) -> Result<usize, SchemaError> {
+        if let Some(&index) = self.compiled.get(location) {
+            return Ok(index);
+        }
+        let value = match self.root.pointer(location) {
+            Some(value) => value,
+            None => return Err(Compiler::error(SchemaErrorKind::UnresolvedRef(location.to_string()), location)),
+        };
+        // Reserve the slot first so that recursive references resolve to it.
+        let index = self.nodes.len();
+        self.nodes.push(SchemaNode::Bool(true));
+        self.compiled.insert(location.to_string(), index);
+        self.nodes[index] = match value {
+            JsonValue::Boolean(b) => SchemaNode::Bool(*b),
+            JsonValue::Objects(_) => SchemaNode::Keywords(self.keywords(value, location)?),
+            _ => return Err(Compiler::error(SchemaErrorKind::NotSchema, location)),
+        };
+        Ok(index)
+    }
+
+    fn number(value: &JsonValue, keyword: &str, location: &str) -> Result<f64, SchemaError> {
+        value.as_f64().ok_or_else(|| Compiler::invalid(keyword, location))
+    }
+
+    fn count(value: &JsonValue, keyword: &str, location: &str) -> Result<usize, SchemaError> {
+        match value.as_f64() {
+            Some(n) if n >= 0.0 && json::is_integer(n) => Ok(n as usize),
+            _ => Err(Compiler::invalid(keyword, location)),
+        }
+    }
+
+    fn regex(pattern: &str, location: &str) -> Result<Regex, SchemaError> {
+        Regex::new(pattern).map_err(|e| Compiler::error(SchemaErrorKind::Regex(e), location))
+    }
+
+    fn subschemas(&mut self, value: &JsonValue, keyword: &str, location: &str) -> Result<Vec<usize>, SchemaError> {
+        match value {
+            JsonValue::Array(values) if !values.is_empty() => {
+                (0..values.len()).map(|i| self.compile(&format!("{}/{}", location, i))).collect()
+            },
+            _ => Err(Compiler::invalid(keyword, location)),
+        }
+    }
+
+    fn resolve_ref(reference: &str, location: &str) -> Result<String, SchemaError> {
+        let fragment = match reference.strip_prefix('#') {
+            Some(fragment) => fragment,
+            None => return Err(Compiler::error(SchemaErrorKind::UnsupportedRef(reference.to_string()), location)),
+        };
+        match percent_decode(fragment) {
+            Some(pointer) if pointer.is_empty() || pointer.starts_with('/') => Ok(pointer),
+            _ => Err(Compiler::error(SchemaErrorKind::UnsupportedRef(reference.to_string()), location)),
+        }
+    }
+
+    fn keywords(&mut self, schema: &JsonValue, location: &str) -> Result<Vec<Keyword>, SchemaError> {
+        let mut keywords = Vec::new();
+        let members = schema.as_objects().unwrap();
+        for member in members {
+            let name = member.name.as_str();
+            let value = &member.value;
+            let at = format!("{}/{}", location, pointer::escape(name));
+            let keyword = match name {
+                "$ref" => {
+                    let reference = value.as_str().ok_or_else(|| Compiler::invalid(name, &at))?;
+                    let target = Compiler::resolve_ref(reference, &at)?;
+                    Keyword::Ref(self.compile(&target)?)
+                },
+                "type" => {
+                    let names = match value {
+                        JsonValue::String(s) => vec![s.as_str()],
+                        JsonValue::Array(values) => values.iter().map(|v| v.as_str()).collect::<Option<Vec<_>>>()
+                            .ok_or_else(|| Compiler::invalid(name, &at))?,
+                        _ => return Err(Compiler::invalid(name, &at)),
+                    };
+                    let types = names.into_iter().map(TypeName::from_name).collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| Compiler::invalid(name, &at))?;
+                    Keyword::Type(types)
+                },
+                "enum" => Keyword::Enum(value.as_array().ok_or_else(|| Compiler::invalid(name, &at))?.clone()),
+                "const" => Keyword::Const(value.clone()),
+                "minimum" => Keyword::Minimum(Compiler::number(value, name, &at)?),
+                "maximum" => Keyword::Maximum(Compiler::number(value, name, &at)?),
+                "exclusiveMinimum" => Keyword::ExclusiveMinimum(Compiler::number(value, name, &at)?),
+                "exclusiveMaximum" => Keyword::ExclusiveMaximum(Compiler::number(value, name, &at)?),
+                "multipleOf" => {
+                    let n = Compiler::number(value, name, &at)?;
+                    if n <= 0.0 {
+                        return Err(Compiler::invalid(name, &at));
+                    }
+                    Keyword::MultipleOf(n)
+                },
+                "minLength" => Keyword::MinLength(Compiler::count(value, name, &at)?),
+                "maxLength" => Keyword::MaxLength(Compiler::count(value, name, &at)?),
+                "pattern" => {
+                    let pattern = value.as_str().ok_or_else(|| Compiler::invalid(name, &at))?;
+                    Keyword::Pattern(Compiler::regex(pattern, &at)?, pattern.to_string())
+                },
+                "minItems" => Keyword::MinItems(Compiler::count(value, name, &at)?),
+                "maxItems" => Keyword::MaxItems(Compiler::count(value, name, &at)?),
+                "uniqueItems" => match value {
+                    JsonValue::Boolean(true) => Keyword::UniqueItems,
+                    JsonValue::Boolean(false) => continue,
+                    _ => return Err(Compiler::invalid(name, &at)),
+                },
+                "prefixItems" => Keyword::PrefixItems(self.subschemas(value, name, &at)?),
+                "items" => {
+                    let skip = schema.get("prefixItems").and_then(|v| v.as_array()).map_or(0, |v| v.len());
+                    Keyword::Items { node: self.compile(&at)?, skip }
+                },
+                "minProperties" => Keyword::MinProperties(Compiler::count(value, name, &at)?),
+                "maxProperties" => Keyword::MaxProperties(Compiler::count(value, name, &at)?),
+                "required" => {
+                    let names = value.as_array().and_then(|values| {
+                        values.iter().map(|v| v.as_str().map(|s| s.to_string())).collect::<Option<Vec<_>>>()
+                    });
+                    Keyword::Required(names.ok_or_else(|| Compiler::invalid(name, &at))?)
+                },
+                "properties" => {
+                    let members = value.as_objects().ok_or_else(|| Compiler::invalid(name, &at))?;
+                    let mut properties = Vec::new();
+                    for property in members {
+                        let node = self.compile(&format!("{}/{}", at, pointer::escape(&property.name)))?;
+                        properties.push((property.name.clone(), node));
+                    }
+                    Keyword::Properties(properties)
+                },
+                "patternProperties" => {
+                    let members = value.as_objects().ok_or_else(|| Compiler::invalid(name, &at))?;
+                    let mut properties = Vec::new();
+                    for property in members {
+                        let property_at = format!("{}/{}", at, pointer::escape(&property.name));
+                        let regex = Compiler::regex(&property.name, &property_at)?;
+                        properties.push((regex, property.name.clone(), self.compile(&property_at)?));
+                    }
+                    Keyword::PatternProperties(properties)
+                },
+                "additionalProperties" => {
+                    let names = schema.get("properties").and_then(|v| v.as_objects())
+                        .map_or(Vec::new(), |members| members.iter().map(|m| m.name.clone()).collect());
+                    let patterns = match schema.get("patternProperties").and_then(|v| v.as_objects()) {
+                        Some(members) => members.iter().map(|m| Compiler::regex(&m.name, &at)).collect::<Result<Vec<_>, _>>()?,
+                        None => Vec::new(),
+                    };
+                    Keyword::AdditionalProperties { node: self.compile(&at)?, names, patterns }
+                },
+                "allOf" => Keyword::AllOf(self.subschemas(value, name, &at)?),
+                "anyOf" => Keyword::AnyOf(self.subschemas(value, name, &at)?),
+                "oneOf" => Keyword::OneOf(self.subschemas(value, name, &at)?),
+                "not" => Keyword::Not(self.compile(&at)?),
+                _ => continue,
+            };
+            keywords.push(keyword);
+        }
+        Ok(keywords)
+    }
+}
+
+/// Decodes `%XX` sequences in a URI fragment.
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(core::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+struct Validator<'s> {
+    schema: &'s Schema,
+    instance_path: Vec<String>,
+    schema_path: Vec<String>,
+    errors: Vec<ValidationError>,
+    active_refs: Vec<(usize, *const JsonValue)>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, kind: ValidationErrorKind) {
+        self.errors.push(ValidationError {
+            instance_path: pointer::join(&self.instance_path),
+            schema_path: pointer::join(&self.schema_path),
+            kind,
+        });
+    }
+
+    /// Validates `instance` against a subschema reached through `segments` of the schema.
+    fn descend(&mut self, node: usize, instance: &JsonValue, segments: &[&str]) {
+        for segment in segments {
+            self.schema_path.push(segment.to_string());
+        }
+        self.validate(node, instance);
+        self.schema_path.truncate(self.schema_path.len() - segments.len());
+    }
+
+    /// Validates an instance member or item reached through `token`.
+    fn descend_instance(&mut self, node: usize, instance: &JsonValue, token: String, segments: &[&str]) {
+        self.instance_path.push(token);
+        self.descend(node, instance, segments);
+        self.instance_path.pop();
+    }
+
+    /// Returns true if `instance` is valid against the subschema, discarding any errors.
+    fn probe(&mut self, node: usize, instance: &JsonValue, segments: &[&str]) -> bool {
+        let len = self.errors.len();
+        self.descend(node, instance, segments);
+        let valid = self.errors.len() == len;
+        self.errors.truncate(len);
+        valid
+    }
+
+    fn validate(&mut self, node: usize, instance: &JsonValue) {
+        let keywords = match &self.schema.nodes[node] {
+            SchemaNode::Bool(true) => return,
+            SchemaNode::Bool(false) => {
+                self.report(ValidationErrorKind::FalseSchema);
+                return;
+            },
+            SchemaNode::Keywords(keywords) => keywords,
+        };
+        for keyword in keywords {
+            self.schema_path.push(keyword.name().to_string());
+            self.keyword(keyword, instance);
+            self.schema_path.pop();
+        }
+    }
+
+    fn keyword(&mut self, keyword: &Keyword, instance: &JsonValue) {
+        match (keyword, instance) {
+            (Keyword::Ref(node), _) => {
+                // A reference cycle that does not move into the instance can add nothing new.
+                let key = (*node, instance as *const JsonValue);
+                if !self.active_refs.contains(&key) {
+                    self.active_refs.push(key);
+                    self.descend(*node, instance, &[]);
+                    self.active_refs.pop();
+                }
+            },
+            (Keyword::Type(types), _) if !types.iter().any(|t| t.matches(instance)) => {
+                let expected = types.iter().map(|t| t.name()).collect();
+                self.report(ValidationErrorKind::Type { expected, found: instance.type_name() });
+            },
+            (Keyword::Enum(values), _) if !values.iter().any(|v| v.equivalent(instance)) => self.report(ValidationErrorKind::Enum),
+            (Keyword::Const(value), _) if !value.equivalent(instance) => self.report(ValidationErrorKind::Const),
+            // Numbers are compared as doubles, like the keyword values.
+            (Keyword::Minimum(m), _) if instance.as_f64().is_some_and(|n| n < *m) => self.report(ValidationErrorKind::Minimum(*m)),
+            (Keyword::Maximum(m), _) if instance.as_f64().is_some_and(|n| n > *m) => self.report(ValidationErrorKind::Maximum(*m)),
+            (Keyword::ExclusiveMinimum(m), _) if instance.as_f64().is_some_and(|n| n <= *m) => self.report(ValidationErrorKind::ExclusiveMinimum(*m)),
+            (Keyword::ExclusiveMaximum(m), _) if instance.as_f64().is_some_and(|n| n >= *m) => self.report(ValidationErrorKind::ExclusiveMaximum(*m)),
+            (Keyword::MultipleOf(m), _) if instance.is_number() => {
+                let quotient = instance.as_f64().unwrap() / m;
+                if !quotient.is_finite() || (quotient - json::round(quotient)).abs() > quotient.abs() * f64::EPSILON * 4.0 {
+                    self.report(ValidationErrorKind::MultipleOf(*m));
+                }
+            },
+            (Keyword::MinLength(min), JsonValue::String(s)) if s.chars().count() < *min => {
+                self.report(ValidationErrorKind::MinLength(*min));
+            },
+            (Keyword::MaxLength(max), JsonValue::String(s)) if s.chars().count() > *max => {
+                self.report(ValidationErrorKind::MaxLength(*max));
+            },
+            (Keyword::Pattern(regex, pattern), JsonValue::String(s)) if !regex.is_match(s) => {
+                self.report(ValidationErrorKind::Pattern(pattern.clone()));
+            },
+            (Keyword::MinItems(min), JsonValue::Array(a)) if a.len() < *min => self.report(ValidationErrorKind::MinItems(*min)),
+            (Keyword::MaxItems(max), JsonValue::Array(a)) if a.len() > *max => self.report(ValidationErrorKind::MaxItems(*max)),
+            (Keyword::UniqueItems, JsonValue::Array(a)) => {
+                let duplicated = a.iter().enumerate().any(|(i, x)| a[i + 1..].iter().any(|y| x.equivalent(y)));
+                if duplicated {
+                    self.report(ValidationErrorKind::UniqueItems);
+                }
+            },
+            (Keyword::PrefixItems(nodes), JsonValue::Array(a)) => {
+                for (i, (node, item)) in nodes.iter().zip(a).enumerate() {
+                    let index = i.to_string();
+                    self.descend_instance(*node, item, index.clone(), &[&index]);
+                }
+            },
+            (Keyword::Items { node, skip }, JsonValue::Array(a)) => {
+                for (i, item) in a.iter().enumerate().skip(*skip) {
+                    self.descend_instance(*node, item, i.to_string(), &[]);
+                }
+            },
+            (Keyword::MinProperties(min), JsonValue::Objects(o)) if o.len() < *min => {
+                self.report(ValidationErrorKind::MinProperties(*min));
+            },
+            (Keyword::MaxProperties(max), JsonValue::Objects(o)) if o.len() > *max => {
+                self.report(ValidationErrorKind::MaxProperties(*max));
+            },
+            (Keyword::Required(names), JsonValue::Objects(_)) => {
+                for name in names {
+                    if instance.get(name).is_none() {
+                        self.report(ValidationErrorKind::Required(name.clone()));
+                    }
+                }
+            },
+            (Keyword::Properties(properties), JsonValue::Objects(members)) => {
+                for member in members {
+                    if let Some((name, node)) = properties.iter().find(|(name, _)| *name == member.name) {
+                        self.descend_instance(*node, &member.value, member.name.clone(), &[name]);
+                    }
+                }
+            },
+            (Keyword::PatternProperties(properties), JsonValue::Objects(members)) => {
+                for member in members {
+                    for (regex, pattern, node) in properties {
+                        if regex.is_match(&member.name) {
+                            self.descend_instance(*node, &member.value, member.name.clone(), &[pattern]);
+                        }
+                    }
+                }
+            },
+            (Keyword::AdditionalProperties { node, names, patterns }, JsonValue::Objects(members)) => {
+                for member in members {
+                    if names.contains(&member.name) || patterns.iter().any(|r| r.is_match(&member.name)) {
+                        continue;
+                    }
+                    if matches!(self.schema.nodes[*node], SchemaNode::Bool(false)) {
+                        self.instance_path.push(member.name.clone());
+                        self.report(ValidationErrorKind::AdditionalProperty(member.name.clone()));
+                        self.instance_path.pop();
+                    } else {
+                        self.descend_instance(*node, &member.value, member.name.clone(), &[]);
+                    }
+                }
+            },
+            (Keyword::AllOf(nodes), _) => {
+                for (i, node) in nodes.iter().enumerate() {
+                    self.descend(*node, instance, &[&i.to_string()]);
+                }
+            },
+            (Keyword::AnyOf(nodes), _) => {
+                let matched = nodes.iter().enumerate().any(|(i, node)| self.probe(*node, instance, &[&i.to_string()]));
+                if !matched {
+                    self.report(ValidationErrorKind::AnyOf);
+                }
+            },
+            (Keyword::OneOf(nodes), _) => {
+                let matched = nodes.iter().enumerate().filter(|(i, node)| self.probe(**node, instance, &[&i.to_string()])).count();
+                if matched != 1 {
+                    self.report(ValidationErrorKind::OneOf(matched));
+                }
+            },
+            (Keyword::Not(node), _) if self.probe(*node, instance, &[]) => self.report(ValidationErrorKind::Not),
+            _ => {},
+        }
+    }
+}
+
+impl Schema {
+    /// Compiles a schema document.
+    ///
+    /// `$ref` may point anywhere inside the document with a JSON Pointer fragment such as
+    /// `#/$defs/address`, including back to an enclosing schema.
+    pub fn compile(schema: &JsonValue) -> Result<Schema, SchemaError> {
+        let mut compiler = Compiler { root: schema, nodes: Vec::new(), compiled: BTreeMap::new() };
+        compiler.compile("")?;
+        Ok(Schema { nodes: compiler.nodes })
+    }
+
+    /// Validates `instance`, returning every failure.
+    pub fn validate(&self, instance: &JsonValue) -> Result<(), Vec<ValidationError>> {
+        let mut validator = Validator { schema: self, instance_path: Vec::new(), schema_path: Vec::new(), errors: Vec::new(), active_refs: Vec::new() };
+        validator.validate(0, instance);
+        if validator.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(validator.errors)
+        }
+    }
+
+    pub fn is_valid(&self, instance: &JsonValue) -> bool {
+        self.validate(instance).is_ok()
+    }
+}
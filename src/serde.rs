@@ -0,0 +1,1064 @@
+//! serde integration, enabled by the `serde` feature.
+//!
+//! `JsonValue` implements `Serialize` and `Deserialize`, and [`from_str`] / [`to_string`]
+//! read and write any serde type using this crate's lexer and writer.
+
+use std::fmt::Write;
+use std::num::ParseFloatError;
+
+use ::serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use ::serde::ser::{self, Impossible, Serialize, SerializeMap, SerializeStruct};
+use ::serde::{forward_to_deserialize_any, Deserialize};
+
+use crate::error::{JsonParseError, ParseErrorKind, SerdeError, SerdeErrorKind};
+use crate::json::{JsonObject, JsonValue};
+use crate::lexer::{JsonLexer, JsonNumberToken, JsonToken};
+use crate::parser::{decode_string, MAX_DEPTH};
+use crate::span::Span;
+use crate::writer::{self, WriteOptions};
+
+type Result<T> = std::result::Result<T, SerdeError>;
+
+impl de::Error for SerdeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        SerdeError { kind: SerdeErrorKind::Message(msg.to_string()) }
+    }
+}
+
+impl ser::Error for SerdeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        SerdeError { kind: SerdeErrorKind::Message(msg.to_string()) }
+    }
+}
+
+/// Deserializes an instance of `T` from JSON text.
+///
+/// Strings are always decoded into owned buffers, so `T` cannot borrow from `s`.
+pub fn from_str<T: DeserializeOwned>(s: &str) -> Result<T> {
+    let mut deserializer = Deserializer::new(s)?;
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Serializes `value` as compact JSON text.
+pub fn to_string<T: ?Sized + Serialize>(value: &T) -> Result<String> {
+    to_string_with(value, WriteOptions::default())
+}
+
+/// Serializes `value` as compact JSON text, formatted according to `options`.
+pub fn to_string_with<T: ?Sized + Serialize>(value: &T, options: WriteOptions) -> Result<String> {
+    let mut serializer = Serializer::with_options(options);
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_inner())
+}
+
+/// Name of the struct, and of its only field, that serde_json's `arbitrary_precision` feature
+/// uses to carry a number as its exact text. This crate's serializer and serde_json's write the
+/// field as a bare number; other serializers see a struct with one string field.
+#[cfg(feature = "arbitrary_precision")]
+const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+impl Serialize for JsonValue {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            JsonValue::Number(n) => serializer.serialize_f64(*n),
+            #[cfg(feature = "arbitrary_precision")]
+            JsonValue::Decimal(d) => match d.to_i128().and_then(|n| i64::try_from(n).ok()) {
+                Some(n) if d.scale() == 0 => serializer.serialize_i64(n),
+                _ => {
+                    let mut number = serializer.serialize_struct(NUMBER_TOKEN, 1)?;
+                    number.serialize_field(NUMBER_TOKEN, &d.to_string())?;
+                    number.end()
+                },
+            },
+            JsonValue::String(s) => serializer.serialize_str(s),
+            JsonValue::Boolean(b) => serializer.serialize_bool(*b),
+            JsonValue::Null => serializer.serialize_unit(),
+            JsonValue::Array(values) => serializer.collect_seq(values),
+            JsonValue::Objects(objects) => {
+                let mut map = serializer.serialize_map(Some(objects.len()))?;
+                for object in objects {
+                    map.serialize_entry(&object.name, &object.value)?;
+                }
+                map.end()
+            },
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = JsonValue;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<JsonValue, E> {
+        Ok(JsonValue::Boolean(v))
+    }
+
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<JsonValue, E> {
+        Ok(JsonValue::Number(v as f64))
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<JsonValue, E> {
+        Ok(JsonValue::Decimal(v.into()))
+    }
+
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<JsonValue, E> {
+        Ok(JsonValue::Number(v as f64))
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<JsonValue, E> {
+        Ok(JsonValue::Decimal(v.into()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<JsonValue, E> {
+        Ok(JsonValue::Number(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<JsonValue, E> {
+        Ok(JsonValue::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<JsonValue, E> {
+        Ok(JsonValue::String(v))
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_none<E>(self) -> std::result::Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> std::result::Result<JsonValue, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<JsonValue, A::Error> {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(JsonValue::Array(values))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> std::result::Result<JsonValue, A::Error> {
+        let mut objects = Vec::new();
+        while let Some(name) = map.next_key::<String>()? {
+            #[cfg(feature = "arbitrary_precision")]
+            if objects.is_empty() && name == NUMBER_TOKEN {
+                let text = map.next_value::<String>()?;
+                return text.parse().map(JsonValue::Decimal).map_err(de::Error::custom);
+            }
+            objects.push(JsonObject { name, value: map.next_value()? });
+        }
+        Ok(JsonValue::Objects(objects))
+    }
+}
+
+/// A serde `Deserializer` over the tokens produced by the crate's lexer.
+pub struct Deserializer {
+    tokens: Vec<JsonToken>,
+    spans: Vec<Span>,
+    position: usize,
+    depth: usize,
+    /// Length of the source, where errors at the end of the input are located.
+    end: usize,
+}
+
+impl Deserializer {
+    pub fn new(json: &str) -> Result<Deserializer> {
+        let tokens = JsonLexer::new(json)
+            .tokenize()
+            .map_err(|e| SerdeError::from(JsonParseError::from(e)))?;
+        let (tokens, spans) = tokens.tokens.into_iter()
+            .zip(tokens.spans)
+            .filter(|(token, _)| !matches!(token, JsonToken::WhiteSpace(_)))
+            .unzip();
+        Ok(Deserializer { tokens, spans, position: 0, depth: 0, end: json.len() })
+    }
+
+    /// Checks that the whole input has been consumed.
+    pub fn end(&mut self) -> Result<()> {
+        match self.next() {
+            Some(_) => Err(self.error(ParseErrorKind::InvalidToken)),
+            None => Ok(()),
+        }
+    }
+
+    fn peek(&self) -> Option<&JsonToken> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&JsonToken> {
+        let current = self.tokens.get(self.position);
+        self.position += 1;
+        current
+    }
+
+    /// An error located at the last token read.
+    fn error(&self, kind: ParseErrorKind) -> SerdeError {
+        let span = self.spans.get(self.position - 1).copied().unwrap_or(Span::new(self.end, self.end));
+        JsonParseError::new(kind, span).into()
+    }
+
+    fn expect(&mut self, expected: JsonToken, eof: ParseErrorKind) -> Result<()> {
+        match self.next() {
+            Some(token) if *token == expected => Ok(()),
+            Some(_) => Err(self.error(ParseErrorKind::InvalidToken)),
+            None => Err(self.error(eof)),
+        }
+    }
+
+    fn enter(&mut self) -> Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error(ParseErrorKind::RecursionLimitExceeded));
+        }
+        Ok(())
+    }
+
+    fn next_key(&mut self) -> Result<String> {
+        match self.next() {
+            Some(JsonToken::String(s)) => {
+                let decoded = decode_string(s);
+                decoded.map_err(|e| self.error(e.kind))
+            },
+            Some(_) => Err(self.error(ParseErrorKind::InvalidToken)),
+            None => Err(self.error(ParseErrorKind::NoObjectName)),
+        }
+    }
+}
+
+enum Number {
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+}
+
+fn parse_number(number: &JsonNumberToken) -> std::result::Result<Number, ParseFloatError> {
+    if number.frac.is_empty() && number.exp.is_empty() {
+        if !number.is_minus {
+            if let Ok(n) = number.integer.parse() {
+                return Ok(Number::Unsigned(n));
+            }
+        } else if number.integer != "0" {
+            if let Ok(n) = format!("-{}", number.integer).parse() {
+                return Ok(Number::Signed(n));
+            }
+        }
+    }
+    number.to_string().parse().map(Number::Float)
+}
+
+/// Number methods that visit `u64`, `i64` or `f64` rather than the exact text that
+/// `deserialize_any` hands over.
+#[cfg(feature = "arbitrary_precision")]
+macro_rules! deserialize_primitive {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                let number = match self.peek() {
+                    Some(JsonToken::Number(number)) => parse_number(number),
+                    _ => return de::Deserializer::deserialize_any(self, visitor),
+                };
+                self.next();
+                match number {
+                    Ok(Number::Unsigned(n)) => visitor.visit_u64(n),
+                    Ok(Number::Signed(n)) => visitor.visit_i64(n),
+                    Ok(Number::Float(n)) => visitor.visit_f64(n),
+                    Err(e) => Err(self.error(ParseErrorKind::ParseFloatError(e))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.next() {
+            Some(JsonToken::Null) => visitor.visit_unit(),
+            Some(JsonToken::True) => visitor.visit_bool(true),
+            Some(JsonToken::False) => visitor.visit_bool(false),
+            Some(JsonToken::Number(number)) => match parse_number(number) {
+                Ok(Number::Unsigned(n)) => visitor.visit_u64(n),
+                Ok(Number::Signed(n)) => visitor.visit_i64(n),
+                // Like serde_json, hand over the text of numbers that `u64` and `i64` cannot hold,
+                // so that `JsonValue` and `serde_json::Value` keep their digits.
+                #[cfg(feature = "arbitrary_precision")]
+                Ok(Number::Float(_)) => visitor.visit_map(NumberAccess { text: Some(number.to_string()) }),
+                #[cfg(not(feature = "arbitrary_precision"))]
+                Ok(Number::Float(n)) => visitor.visit_f64(n),
+                Err(e) => Err(self.error(ParseErrorKind::ParseFloatError(e))),
+            },
+            Some(JsonToken::String(s)) => {
+                let decoded = decode_string(s);
+                visitor.visit_string(decoded.map_err(|e| self.error(e.kind))?)
+            },
+            Some(JsonToken::BeginArray) => {
+                self.enter()?;
+                let value = visitor.visit_seq(SeqAccess { de: &mut *self, first: true })?;
+                self.expect(JsonToken::EndArray, ParseErrorKind::NonEndArray)?;
+                self.depth -= 1;
+                Ok(value)
+            },
+            Some(JsonToken::BeginObject) => {
+                self.enter()?;
+                let value = visitor.visit_map(MapAccess { de: &mut *self, first: true })?;
+                self.expect(JsonToken::EndObject, ParseErrorKind::NonEndObject)?;
+                self.depth -= 1;
+                Ok(value)
+            },
+            Some(_) => Err(self.error(ParseErrorKind::InvalidToken)),
+            None => Err(self.error(ParseErrorKind::NonValue)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if let Some(JsonToken::Null) = self.peek() {
+            self.next();
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.peek() {
+            Some(JsonToken::String(_)) => visitor.visit_enum(self.next_key()?.into_deserializer()),
+            Some(JsonToken::BeginObject) => {
+                self.next();
+                self.enter()?;
+                let value = visitor.visit_enum(EnumAccess { de: &mut *self })?;
+                self.expect(JsonToken::EndObject, ParseErrorKind::NonEndObject)?;
+                self.depth -= 1;
+                Ok(value)
+            },
+            Some(_) => {
+                self.next();
+                Err(self.error(ParseErrorKind::InvalidToken))
+            },
+            None => Err(self.error(ParseErrorKind::NonValue)),
+        }
+    }
+
+    #[cfg(not(feature = "arbitrary_precision"))]
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    deserialize_primitive! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    forward_to_deserialize_any! {
+        bool char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
+
+/// The text of a number, read as serde_json's single-member number map.
+#[cfg(feature = "arbitrary_precision")]
+struct NumberAccess {
+    text: Option<String>,
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl<'de> de::MapAccess<'de> for NumberAccess {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.text {
+            Some(_) => seed.deserialize(NUMBER_TOKEN.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let text = self.text.take().expect("next_value_seed called before next_key_seed");
+        seed.deserialize(text.into_deserializer())
+    }
+}
+
+struct SeqAccess<'a> {
+    de: &'a mut Deserializer,
+    first: bool,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'_> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if let Some(JsonToken::EndArray) = self.de.peek() {
+            return Ok(None);
+        }
+        if !self.first {
+            self.de.expect(JsonToken::ValueSeparator, ParseErrorKind::NonEndArray)?;
+        }
+        self.first = false;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+struct MapAccess<'a> {
+    de: &'a mut Deserializer,
+    first: bool,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'_> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if let Some(JsonToken::EndObject) = self.de.peek() {
+            return Ok(None);
+        }
+        if !self.first {
+            self.de.expect(JsonToken::ValueSeparator, ParseErrorKind::NonEndObject)?;
+        }
+        self.first = false;
+        let key = self.de.next_key()?;
+        seed.deserialize(MapKey { key }).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        self.de.expect(JsonToken::NameSeparator, ParseErrorKind::NonValue)?;
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+/// Reads an externally tagged enum written as `{"variant": content}`.
+struct EnumAccess<'a> {
+    de: &'a mut Deserializer,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'_> {
+    type Error = SerdeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+        let key = self.de.next_key()?;
+        let variant = seed.deserialize(MapKey { key })?;
+        self.de.expect(JsonToken::NameSeparator, ParseErrorKind::NonValue)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for EnumAccess<'_> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<()> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+/// Deserializes an object member name, which may stand for a number or a boolean map key.
+struct MapKey {
+    key: String,
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                match self.key.parse() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => visitor.visit_string(self.key),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for MapKey {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.key)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_enum(self.key.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// A serde `Serializer` producing compact JSON text through the crate's writer.
+pub struct Serializer {
+    output: String,
+    options: WriteOptions,
+}
+
+impl Serializer {
+    pub fn new() -> Serializer {
+        Serializer::with_options(WriteOptions::default())
+    }
+
+    /// Creates a serializer that formats numbers and strings according to `options`.
+    pub fn with_options(options: WriteOptions) -> Serializer {
+        Serializer { output: String::new(), options }
+    }
+
+    /// Returns the JSON text written so far.
+    pub fn into_inner(self) -> String {
+        self.output
+    }
+}
+
+impl Default for Serializer {
+    fn default() -> Self {
+        Serializer::new()
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Ok = ();
+    type Error = SerdeError;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.output.push_str(if v { "true" } else { "false" });
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        Ok(write!(self.output, "{}", v)?)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        Ok(write!(self.output, "{}", v)?)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        Ok(write!(self.output, "{}", v)?)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        Ok(write!(self.output, "{}", v)?)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        Ok(writer::write_number(&mut self.output, v, &self.options)?)
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        Ok(writer::write_string_with(&mut self.output, v, &self.options)?)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        ser::Serializer::collect_seq(self, v)
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        self.output.push_str("null");
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<()> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.output.push('{');
+        writer::write_string_with(&mut self.output, variant, &self.options)?;
+        self.output.push(':');
+        value.serialize(&mut *self)?;
+        self.output.push('}');
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>> {
+        self.output.push('[');
+        Ok(Compound::new(self, false))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>> {
+        self.output.push('{');
+        writer::write_string_with(&mut self.output, variant, &self.options)?;
+        self.output.push_str(":[");
+        Ok(Compound::new(self, true))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>> {
+        self.output.push('{');
+        Ok(Compound::new(self, false))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a>> {
+        #[cfg(feature = "arbitrary_precision")]
+        if _name == NUMBER_TOKEN {
+            return Ok(Compound { ser: self, first: true, variant: false, number: true });
+        }
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>> {
+        self.output.push('{');
+        writer::write_string_with(&mut self.output, variant, &self.options)?;
+        self.output.push_str(":{");
+        Ok(Compound::new(self, true))
+    }
+}
+
+/// Writes the elements of an array or the members of an object.
+pub struct Compound<'a> {
+    ser: &'a mut Serializer,
+    first: bool,
+    /// Whether the compound is wrapped in `{"variant": ...}`.
+    variant: bool,
+    /// Whether the compound is a number carried as its exact text, written without braces.
+    #[cfg(feature = "arbitrary_precision")]
+    number: bool,
+}
+
+impl<'a> Compound<'a> {
+    fn new(ser: &'a mut Serializer, variant: bool) -> Compound<'a> {
+        #[cfg(feature = "arbitrary_precision")]
+        return Compound { ser, first: true, variant, number: false };
+        #[cfg(not(feature = "arbitrary_precision"))]
+        Compound { ser, first: true, variant }
+    }
+
+    fn separator(&mut self) {
+        if !self.first {
+            self.ser.output.push(',');
+        }
+        self.first = false;
+    }
+
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.separator();
+        value.serialize(&mut *self.ser)
+    }
+
+    fn field<T: ?Sized + Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
+        self.separator();
+        writer::write_string_with(&mut self.ser.output, key, &self.ser.options)?;
+        self.ser.output.push(':');
+        value.serialize(&mut *self.ser)
+    }
+
+    /// Writes the text of a number struct, which must be one JSON number, without quotes.
+    #[cfg(feature = "arbitrary_precision")]
+    fn number_text<T: ?Sized + Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
+        let start = self.ser.output.len();
+        value.serialize(&mut *self.ser)?;
+        let text = self.ser.output[start..].strip_prefix('"').and_then(|s| s.strip_suffix('"'));
+        if !self.first || key != NUMBER_TOKEN || text.is_none_or(|s| s.parse::<crate::decimal::Decimal>().is_err()) {
+            return Err(ser::Error::custom("invalid number"));
+        }
+        self.first = false;
+        self.ser.output.remove(start);
+        self.ser.output.pop();
+        Ok(())
+    }
+
+    fn close(self, end: char) -> Result<()> {
+        self.ser.output.push(end);
+        if self.variant {
+            self.ser.output.push('}');
+        }
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.close(']')
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.close(']')
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.close(']')
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.close(']')
+    }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        self.separator();
+        key.serialize(MapKeySerializer { output: &mut self.ser.output, options: &self.ser.options })?;
+        self.ser.output.push(':');
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<()> {
+        self.close('}')
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        #[cfg(feature = "arbitrary_precision")]
+        if self.number {
+            return self.number_text(key, value);
+        }
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        #[cfg(feature = "arbitrary_precision")]
+        if self.number {
+            return Ok(());
+        }
+        self.close('}')
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.close('}')
+    }
+}
+
+/// Writes map keys as JSON strings; numbers and booleans are quoted.
+struct MapKeySerializer<'a> {
+    output: &'a mut String,
+    options: &'a WriteOptions,
+}
+
+fn key_must_be_a_string() -> SerdeError {
+    SerdeError { kind: SerdeErrorKind::KeyMustBeAString }
+}
+
+macro_rules! serialize_quoted_key {
+    ($($method:ident: $ty:ty,)*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<()> {
+                Ok(write!(self.output, "\"{}\"", v)?)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for MapKeySerializer<'_> {
+    type Ok = ();
+    type Error = SerdeError;
+    type SerializeSeq = Impossible<(), SerdeError>;
+    type SerializeTuple = Impossible<(), SerdeError>;
+    type SerializeTupleStruct = Impossible<(), SerdeError>;
+    type SerializeTupleVariant = Impossible<(), SerdeError>;
+    type SerializeMap = Impossible<(), SerdeError>;
+    type SerializeStruct = Impossible<(), SerdeError>;
+    type SerializeStructVariant = Impossible<(), SerdeError>;
+
+    serialize_quoted_key! {
+        serialize_bool: bool,
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_i128: i128,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64,
+        serialize_u128: u128,
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        Ok(writer::write_string_with(self.output, v, self.options)?)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<()> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(key_must_be_a_string())
+    }
+}
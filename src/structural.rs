@@ -0,0 +1,344 @@
+//! Structural index: the positions of every token of a JSON text, found 64 bytes at a time.
+//!
+//! Each block is classified into bitmasks of quotes, backslashes, structural characters and
+//! whitespace, with SSE2 or AVX2 on x86_64, NEON on aarch64 and plain loops elsewhere.
+//! Escaped quotes and string contents are then masked out with bit operations, leaving one
+//! bit per token start: every structural character outside strings, every opening quote and
+//! the first byte of every number or literal name. The closing quote of every string is
+//! recorded too, so that string bodies without escapes can be copied in one piece.
+
+#[cfg(test)]
+use alloc::vec;
+use alloc::vec::Vec;
+
+
+/// Bitmasks of a 64-byte block, with bit `i` standing for byte `i`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Masks {
+    quote: u64,
+    backslash: u64,
+    /// `{`, `}`, `[`, `]`, `:` and `,`.
+    op: u64,
+    whitespace: u64,
+}
+
+const OPS: &[u8] = b"{}[]:,";
+const WHITESPACE: &[u8] = b" \t\n\r";
+
+/// A classifier, unsafe because the SIMD ones need CPU features that must be detected first.
+type Classify = unsafe fn(&[u8; 64]) -> Masks;
+
+// Also the reference the SIMD classifiers are tested against.
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64"), allow(dead_code))]
+fn classify_scalar(block: &[u8; 64]) -> Masks {
+    let mut masks = Masks::default();
+    for (i, b) in block.iter().enumerate() {
+        let bit = 1 << i;
+        match b {
+            b'"' => masks.quote |= bit,
+            b'\\' => masks.backslash |= bit,
+            b'{' | b'}' | b'[' | b']' | b':' | b',' => masks.op |= bit,
+            b' ' | b'\t' | b'\n' | b'\r' => masks.whitespace |= bit,
+            _ => {},
+        }
+    }
+    masks
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use core::arch::x86_64::*;
+    use super::{Masks, OPS, WHITESPACE};
+
+    /// Mask of the bytes of `block` equal to any of `bytes`.
+    #[target_feature(enable = "sse2")]
+    unsafe fn eq_any_sse2(block: &[u8; 64], bytes: &[u8]) -> u64 {
+        let mut mask = 0;
+        for i in 0..4 {
+            let v = _mm_loadu_si128(block.as_ptr().add(i * 16) as *const __m128i);
+            let mut eq = _mm_setzero_si128();
+            for &b in bytes {
+                eq = _mm_or_si128(eq, _mm_cmpeq_epi8(v, _mm_set1_epi8(b as i8)));
+            }
+            mask |= (_mm_movemask_epi8(eq) as u16 as u64) << (i * 16);
+        }
+        mask
+    }
+
+    #[cfg(feature = "std")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn eq_any_avx2(block: &[u8; 64], bytes: &[u8]) -> u64 {
+        let mut mask = 0;
+        for i in 0..2 {
+            let v = _mm256_loadu_si256(block.as_ptr().add(i * 32) as *const __m256i);
+            let mut eq = _mm256_setzero_si256();
+            for &b in bytes {
+                eq = _mm256_or_si256(eq, _mm256_cmpeq_epi8(v, _mm256_set1_epi8(b as i8)));
+            }
+            mask |= (_mm256_movemask_epi8(eq) as u32 as u64) << (i * 32);
+        }
+        mask
+    }
+
+    pub(super) fn classify_sse2(block: &[u8; 64]) -> Masks {
+        // SSE2 is part of the x86_64 baseline.
+        unsafe {
+            Masks {
+                quote: eq_any_sse2(block, b"\""),
+                backslash: eq_any_sse2(block, b"\\"),
+                op: eq_any_sse2(block, OPS),
+                whitespace: eq_any_sse2(block, WHITESPACE),
+            }
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[cfg(feature = "std")]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn classify_avx2(block: &[u8; 64]) -> Masks {
+        Masks {
+            quote: eq_any_avx2(block, b"\""),
+            backslash: eq_any_avx2(block, b"\\"),
+            op: eq_any_avx2(block, OPS),
+            whitespace: eq_any_avx2(block, WHITESPACE),
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod arm {
+    use core::arch::aarch64::*;
+    use super::{Masks, OPS, WHITESPACE};
+
+    #[target_feature(enable = "neon")]
+    unsafe fn eq_any_neon(block: &[u8; 64], bytes: &[u8]) -> u64 {
+        // NEON has no movemask: weight each matching lane by its bit and add the lanes up.
+        const WEIGHTS: [u8; 16] = [1, 2, 4, 8, 16, 32, 64, 128, 1, 2, 4, 8, 16, 32, 64, 128];
+        let weights = vld1q_u8(WEIGHTS.as_ptr());
+        let mut mask = 0;
+        for i in 0..4 {
+            let v = vld1q_u8(block.as_ptr().add(i * 16));
+            let mut eq = vdupq_n_u8(0);
+            for &b in bytes {
+                eq = vorrq_u8(eq, vceqq_u8(v, vdupq_n_u8(b)));
+            }
+            let weighted = vandq_u8(eq, weights);
+            let low = vaddv_u8(vget_low_u8(weighted)) as u64;
+            let high = vaddv_u8(vget_high_u8(weighted)) as u64;
+            mask |= (low | high << 8) << (i * 16);
+        }
+        mask
+    }
+
+    pub(super) fn classify_neon(block: &[u8; 64]) -> Masks {
+        // NEON is part of the aarch64 baseline.
+        unsafe {
+            Masks {
+                quote: eq_any_neon(block, b"\""),
+                backslash: eq_any_neon(block, b"\\"),
+                op: eq_any_neon(block, OPS),
+                whitespace: eq_any_neon(block, WHITESPACE),
+            }
+        }
+    }
+}
+
+/// The fastest classifier the CPU supports, so it is always safe to call. Without `std` AVX2
+/// cannot be detected.
+fn classifier() -> Classify {
+    #[cfg(target_arch = "x86_64")]
+    {
+        #[cfg(feature = "std")]
+        if std::is_x86_feature_detected!("avx2") {
+            return x86::classify_avx2;
+        }
+        x86::classify_sse2
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        arm::classify_neon
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        classify_scalar
+    }
+}
+
+const EVEN_BITS: u64 = 0x5555_5555_5555_5555;
+
+/// Mask of the bytes escaped by a backslash, carrying a backslash at the end of the block.
+fn escaped(backslash: u64, prev_escaped: &mut u64) -> u64 {
+    let backslash = backslash & !*prev_escaped;
+    let follows_escape = backslash << 1 | *prev_escaped;
+    // Runs of backslashes starting on an odd bit; adding the run carries past its end.
+    let odd_starts = backslash & !EVEN_BITS & !follows_escape;
+    let (sequences_on_even_bits, overflow) = odd_starts.overflowing_add(backslash);
+    *prev_escaped = overflow as u64;
+    let invert = sequences_on_even_bits << 1;
+    (EVEN_BITS ^ invert) & follows_escape
+}
+
+/// Bit `i` is the XOR of bits `0..=i`.
+fn prefix_xor(mut bits: u64) -> u64 {
+    bits ^= bits << 1;
+    bits ^= bits << 2;
+    bits ^= bits << 4;
+    bits ^= bits << 8;
+    bits ^= bits << 16;
+    bits ^= bits << 32;
+    bits
+}
+
+/// Carried from one block to the next.
+#[derive(Default)]
+struct State {
+    prev_escaped: u64,
+    /// All ones if the previous block ended inside a string.
+    prev_in_string: u64,
+    /// 1 if the previous block ended with a number or literal name byte.
+    prev_scalar: u64,
+}
+
+/// Bitmasks of a block found by [`State::block`].
+struct Block {
+    /// Token starts.
+    starts: u64,
+    /// Closing quotes.
+    string_ends: u64,
+    /// Backslashes inside strings.
+    string_escapes: u64,
+}
+
+impl State {
+    fn block(&mut self, masks: Masks) -> Block {
+        let escaped = escaped(masks.backslash, &mut self.prev_escaped);
+        let quote = masks.quote & !escaped;
+        let in_string = prefix_xor(quote) ^ self.prev_in_string;
+        self.prev_in_string = ((in_string as i64) >> 63) as u64;
+
+        let scalar = !(masks.op | masks.whitespace);
+        let nonquote_scalar = scalar & !quote;
+        let follows_scalar = nonquote_scalar << 1 | self.prev_scalar;
+        self.prev_scalar = nonquote_scalar >> 63;
+
+        // String contents and closing quotes; the opening quote stays a token start.
+        let string_tail = in_string ^ quote;
+        Block {
+            starts: (masks.op | (scalar & !follows_scalar)) & !string_tail,
+            string_ends: quote & !in_string,
+            string_escapes: masks.backslash & in_string,
+        }
+    }
+}
+
+/// Token starts and string ends of a JSON text.
+pub(crate) struct Index {
+    /// Byte offsets of the tokens.
+    pub(crate) starts: Vec<usize>,
+    /// Byte offset of the closing quote of every string in order, and whether the string
+    /// contains a backslash.
+    pub(crate) strings: Vec<(usize, bool)>,
+}
+
+/// # Safety
+///
+/// `classify` must be safe to call on this CPU.
+unsafe fn index_with(source: &[u8], classify: Classify) -> Option<Index> {
+    let mut index = Index { starts: Vec::with_capacity(source.len() / 8), strings: Vec::new() };
+    let mut state = State::default();
+    let mut escapes = false;
+    for (n, chunk) in source.chunks(64).enumerate() {
+        let mut block = [b' '; 64];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let Block { mut starts, string_ends, string_escapes } = state.block(classify(&block));
+        while starts != 0 {
+            index.starts.push(n * 64 + starts.trailing_zeros() as usize);
+            starts &= starts - 1;
+        }
+        let mut marks = string_ends | string_escapes;
+        while marks != 0 {
+            let bit = marks & marks.wrapping_neg();
+            if string_ends & bit != 0 {
+                index.strings.push((n * 64 + bit.trailing_zeros() as usize, escapes));
+                escapes = false;
+            } else {
+                escapes = true;
+            }
+            marks ^= bit;
+        }
+    }
+    if state.prev_in_string != 0 {
+        return None;
+    }
+    Some(index)
+}
+
+/// Token starts and string ends of `source`, or `None` if it ends inside a string.
+///
+/// The index is only a guide for well-formed input: text such as `truex` yields fewer starts
+/// than tokens, so users must check that the tokens they read line up with it.
+pub(crate) fn index(source: &[u8]) -> Option<Index> {
+    // SAFETY: `classifier` only returns classifiers the CPU supports.
+    unsafe { index_with(source, classifier()) }
+}
+
+#[test]
+fn classifiers_agree() {
+    let source: Vec<u8> = (0..=255).chain(b"{\"a\\\\\":[1, \"\\\"\"]}\t\r\n".iter().copied()).cycle().take(64 * 7).collect();
+    for block in source.chunks(64) {
+        let block: &[u8; 64] = block.try_into().unwrap();
+        let expected = classify_scalar(block);
+        assert_eq!(unsafe { classifier()(block) }, expected);
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(x86::classify_sse2(block), expected);
+    }
+}
+
+#[test]
+fn escapes_across_blocks() {
+    // A run of backslashes that crosses the block boundary.
+    for run in 0..6 {
+        let mut source = vec![b' '; 60];
+        source.push(b'"');
+        source.resize(source.len() + run * 2, b'\\');
+        source.extend_from_slice(b"\\\"\" ");
+        source.extend_from_slice(b", 1");
+        let index = unsafe { index_with(&source, classify_scalar) }.unwrap();
+        assert_eq!(index.starts, vec![60, source.len() - 3, source.len() - 1], "run {}", run);
+        assert_eq!(index.strings, vec![(source.len() - 5, true)], "run {}", run);
+    }
+}
+
+#[test]
+fn string_ends() {
+    // The last string runs into the next block.
+    let mut source = br#"["ab", "c\"d", "", "e\\", ""#.to_vec();
+    source.extend_from_slice(&[b'x'; 70]);
+    source.extend_from_slice(br#""]"#);
+    let index = unsafe { index_with(&source, classify_scalar) }.unwrap();
+    assert_eq!(index.strings, vec![(4, false), (12, true), (16, false), (23, true), (97, false)]);
+}
+
+#[test]
+fn indexed_tokens_parse_the_same() {
+    use alloc::format;
+
+    use crate::lexer::JsonLexer;
+    use crate::parser::JsonParser;
+
+    let long = format!(r#"["{}", "é😀{}"]"#, "x".repeat(100), "\\\"".repeat(40));
+    for source in [r#"{"a": "plain", "b": "esc\"apedé\\", "c": ["", "x", true], "d": -1.5e3}"#, long.as_str()] {
+        let indexed = JsonLexer::new(source).tokenize_indexed().unwrap();
+        let plain = JsonLexer::new(source).tokenize().unwrap();
+        assert_eq!(
+            JsonParser::new(indexed, source.len()).get_value().unwrap(),
+            JsonParser::new(plain, source.len()).get_value().unwrap(),
+        );
+    }
+    // Malformed text is left to the lexer to report.
+    for source in ["[\"a\u{1}\"]", "[1\"a\"]", "[\"a\\x\"]", "\"a"] {
+        assert!(JsonLexer::new(source).tokenize_indexed().is_none(), "{:?}", source);
+    }
+}
@@ -0,0 +1,307 @@
+//! A compact read-only document stored in one flat tape.
+//!
+//! A [`Document`] keeps every value in a single `Vec` of fixed-size slots, in document order,
+//! and all string contents in one buffer. Arrays and objects record where they end on the
+//! tape so that navigation can skip over them. Object members are a name slot followed by
+//! the value's slots. Parsing a document makes a handful of allocations instead of one per
+//! string and container, and [`Node`]s are cheap `Copy` handles into it.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::error::JsonParseError;
+use crate::json::{JsonObject, JsonValue};
+use crate::push::{Event, PushParser};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Slot {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    /// Byte range of the number's text in the string buffer.
+    #[cfg(feature = "arbitrary_precision")]
+    Decimal { start: usize, end: usize },
+    /// Byte range in the string buffer.
+    String { start: usize, end: usize },
+    /// `end` is the slot after the last value of the container.
+    Array { len: usize, end: usize },
+    Object { len: usize, end: usize },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    tape: Vec<Slot>,
+    strings: String,
+}
+
+impl Document {
+    /// Parses a single JSON text.
+    pub fn parse(source: &str) -> Result<Document, JsonParseError> {
+        Document::from_slice(source.as_bytes())
+    }
+
+    /// Parses a single JSON text from UTF-8 bytes.
+    pub fn from_slice(source: &[u8]) -> Result<Document, JsonParseError> {
+        let mut builder = Builder::default();
+        let mut parser = PushParser::single();
+        // Feed in chunks so that the queue of events stays small.
+        for chunk in source.chunks(64 * 1024) {
+            parser.feed(chunk)?;
+            builder.drain(&mut parser);
+        }
+        parser.finish()?;
+        builder.drain(&mut parser);
+        Ok(builder.document)
+    }
+
+    /// The top-level value.
+    pub fn root(&self) -> Node<'_> {
+        Node { document: self, index: 0 }
+    }
+
+    /// Converts the whole document to a [`JsonValue`] tree.
+    pub fn to_value(&self) -> JsonValue {
+        self.root().to_value()
+    }
+
+    fn str(&self, start: usize, end: usize) -> &str {
+        &self.strings[start..end]
+    }
+
+    /// Index of the slot after the value at `index`.
+    fn skip(&self, index: usize) -> usize {
+        match self.tape[index] {
+            Slot::Array { end, .. } | Slot::Object { end, .. } => end,
+            _ => index + 1,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    document: Document,
+    /// Open containers: their slot and number of values so far.
+    open: Vec<(usize, usize)>,
+}
+
+impl Builder {
+    fn drain(&mut self, parser: &mut PushParser) {
+        while let Some(event) = parser.next_event() {
+            self.push(event);
+        }
+    }
+
+    fn push(&mut self, event: Event) {
+        let tape = &mut self.document.tape;
+        let slot = match event {
+            Event::BeginArray | Event::BeginObject => {
+                self.open.push((tape.len(), 0));
+                tape.push(if event == Event::BeginArray { Slot::Array { len: 0, end: 0 } } else { Slot::Object { len: 0, end: 0 } });
+                return;
+            },
+            Event::EndArray | Event::EndObject => {
+                let (index, count) = self.open.pop().unwrap();
+                let end = tape.len();
+                tape[index] = match tape[index] {
+                    Slot::Array { .. } => Slot::Array { len: count, end },
+                    _ => Slot::Object { len: count, end },
+                };
+                self.close_value();
+                return;
+            },
+            Event::Name(name) => {
+                let slot = self.string(&name);
+                self.document.tape.push(slot);
+                return;
+            },
+            Event::String(s) => self.string(&s),
+            Event::Number(n) => Slot::Number(n),
+            #[cfg(feature = "arbitrary_precision")]
+            Event::Decimal(d) => {
+                let start = self.document.strings.len();
+                self.document.strings.push_str(&d.to_string());
+                Slot::Decimal { start, end: self.document.strings.len() }
+            },
+            Event::Boolean(b) => Slot::Boolean(b),
+            Event::Null => Slot::Null,
+        };
+        self.document.tape.push(slot);
+        self.close_value();
+    }
+
+    fn string(&mut self, s: &str) -> Slot {
+        let start = self.document.strings.len();
+        self.document.strings.push_str(s);
+        Slot::String { start, end: self.document.strings.len() }
+    }
+
+    /// Counts a completed value in its container.
+    fn close_value(&mut self) {
+        if let Some((_, count)) = self.open.last_mut() {
+            *count += 1;
+        }
+    }
+}
+
+/// A value in a [`Document`].
+#[derive(Debug, Clone, Copy)]
+pub struct Node<'a> {
+    document: &'a Document,
+    index: usize,
+}
+
+impl<'a> Node<'a> {
+    fn slot(&self) -> Slot {
+        self.document.tape[self.index]
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.slot() == Slot::Null
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self.slot(), Slot::Array { .. })
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self.slot(), Slot::Object { .. })
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.slot() {
+            Slot::Boolean(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.slot() {
+            Slot::Number(n) => Some(n),
+            #[cfg(feature = "arbitrary_precision")]
+            Slot::Decimal { start, end } => self.document.str(start, end).parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self.slot() {
+            Slot::String { start, end } => Some(self.document.str(start, end)),
+            _ => None,
+        }
+    }
+
+    /// The elements, if this is an array.
+    pub fn as_array(&self) -> Option<Elements<'a>> {
+        match self.slot() {
+            Slot::Array { len, .. } => Some(Elements { document: self.document, index: self.index + 1, remaining: len }),
+            _ => None,
+        }
+    }
+
+    /// The members, if this is an object.
+    pub fn as_object(&self) -> Option<Members<'a>> {
+        match self.slot() {
+            Slot::Object { len, .. } => Some(Members { document: self.document, index: self.index + 1, remaining: len }),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the first object member named `name`.
+    pub fn get(&self, name: &str) -> Option<Node<'a>> {
+        self.as_object()?.find(|(n, _)| *n == name).map(|(_, value)| value)
+    }
+
+    /// Returns the `index`th element of an array.
+    pub fn at(&self, index: usize) -> Option<Node<'a>> {
+        self.as_array()?.nth(index)
+    }
+
+    /// Looks up a value by a JSON Pointer such as `/users/0/name`.
+    pub fn pointer(&self, pointer: &str) -> Option<Node<'a>> {
+        let mut node = *self;
+        for token in crate::pointer::parse(pointer).ok()? {
+            node = match node.slot() {
+                Slot::Object { .. } => node.get(&token)?,
+                Slot::Array { .. } => node.at(crate::pointer::parse_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(node)
+    }
+
+    /// Copies the value into a [`JsonValue`] tree.
+    pub fn to_value(&self) -> JsonValue {
+        match self.slot() {
+            Slot::Null => JsonValue::Null,
+            Slot::Boolean(b) => JsonValue::Boolean(b),
+            Slot::Number(n) => JsonValue::Number(n),
+            #[cfg(feature = "arbitrary_precision")]
+            Slot::Decimal { start, end } => JsonValue::Decimal(self.document.str(start, end).parse().expect("written from a Decimal")),
+            Slot::String { start, end } => JsonValue::String(self.document.str(start, end).to_string()),
+            Slot::Array { .. } => JsonValue::Array(self.as_array().unwrap().map(|n| n.to_value()).collect()),
+            Slot::Object { .. } => JsonValue::Objects(self.as_object().unwrap()
+                .map(|(name, value)| JsonObject { name: name.to_string(), value: value.to_value() })
+                .collect()),
+        }
+    }
+}
+
+/// Iterator over the elements of an array [`Node`].
+#[derive(Debug, Clone)]
+pub struct Elements<'a> {
+    document: &'a Document,
+    index: usize,
+    remaining: usize,
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Node<'a>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = Node { document: self.document, index: self.index };
+        self.index = self.document.skip(self.index);
+        Some(node)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Elements<'_> {}
+
+/// Iterator over the names and values of the members of an object [`Node`].
+#[derive(Debug, Clone)]
+pub struct Members<'a> {
+    document: &'a Document,
+    index: usize,
+    remaining: usize,
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = (&'a str, Node<'a>);
+
+    fn next(&mut self) -> Option<(&'a str, Node<'a>)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let name = match self.document.tape[self.index] {
+            Slot::String { start, end } => self.document.str(start, end),
+            _ => unreachable!("member names are strings"),
+        };
+        let value = Node { document: self.document, index: self.index + 1 };
+        self.index = self.document.skip(self.index + 1);
+        Some((name, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Members<'_> {}
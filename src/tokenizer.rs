@@ -0,0 +1,133 @@
+//! A tokenizer that keeps every byte of the source.
+//!
+//! Tokens carry their span and raw text, and whitespace is kept as tokens, so the source
+//! can be reproduced exactly by concatenating token texts. This is meant for syntax
+//! highlighting, minifying and similar tools that work on the text rather than on values.
+
+use alloc::vec::Vec;
+
+use crate::error::JsonLexerError;
+use crate::lexer::{JsonLexer, JsonToken};
+use crate::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// `{`
+    BeginObject,
+    /// `}`
+    EndObject,
+    /// `[`
+    BeginArray,
+    /// `]`
+    EndArray,
+    /// `:`
+    NameSeparator,
+    /// `,`
+    ValueSeparator,
+    String,
+    Number,
+    True,
+    False,
+    Null,
+    /// A run of spaces, tabs and line breaks.
+    Whitespace,
+    /// A `//` comment up to the end of the line, only in JSONC mode.
+    LineComment,
+    /// A `/* */` comment, only in JSONC mode.
+    BlockComment,
+    /// Text that is not a valid token. Its error is available from [`Tokenizer::errors`].
+    Invalid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub span: Span,
+    /// The token exactly as written in the source, including the quotes of strings.
+    pub text: &'a str,
+}
+
+impl Token<'_> {
+    /// Whether the token has no meaning to a parser, i.e. it is whitespace or a comment.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Whitespace | TokenKind::LineComment | TokenKind::BlockComment)
+    }
+}
+
+/// An iterator over the [`Token`]s of a source text.
+///
+/// Malformed text does not stop tokenizing: it is yielded as a [`TokenKind::Invalid`] token.
+pub struct Tokenizer<'a> {
+    source: &'a str,
+    lexer: JsonLexer<'a>,
+    peeked: Option<(Result<JsonToken, JsonLexerError>, Span)>,
+    errors: Vec<JsonLexerError>,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(source: &'a str) -> Tokenizer<'a> {
+        Tokenizer { source, lexer: JsonLexer::new(source), peeked: None, errors: Vec::new() }
+    }
+
+    /// Creates a tokenizer that also accepts JSONC `//` and `/* */` comments.
+    pub fn jsonc(source: &'a str) -> Tokenizer<'a> {
+        Tokenizer { lexer: JsonLexer::with_comments(source), ..Tokenizer::new(source) }
+    }
+
+    /// Errors of the `Invalid` tokens yielded so far.
+    pub fn errors(&self) -> &[JsonLexerError] {
+        &self.errors
+    }
+}
+
+/// Tokenizes the whole source, failing on the first malformed token.
+pub fn tokenize(source: &str) -> Result<Vec<Token<'_>>, JsonLexerError> {
+    let mut tokenizer = Tokenizer::new(source);
+    let tokens = tokenizer.by_ref().collect();
+    match tokenizer.errors.into_iter().next() {
+        Some(e) => Err(e),
+        None => Ok(tokens),
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let (token, mut span) = self.peeked.take().or_else(|| self.lexer.next_spanned())?;
+        let kind = match token {
+            Ok(JsonToken::BeginObject) => TokenKind::BeginObject,
+            Ok(JsonToken::EndObject) => TokenKind::EndObject,
+            Ok(JsonToken::BeginArray) => TokenKind::BeginArray,
+            Ok(JsonToken::EndArray) => TokenKind::EndArray,
+            Ok(JsonToken::NameSeparator) => TokenKind::NameSeparator,
+            Ok(JsonToken::ValueSeparator) => TokenKind::ValueSeparator,
+            Ok(JsonToken::String(_)) => TokenKind::String,
+            Ok(JsonToken::Number(_)) => TokenKind::Number,
+            Ok(JsonToken::True) => TokenKind::True,
+            Ok(JsonToken::False) => TokenKind::False,
+            Ok(JsonToken::Null) => TokenKind::Null,
+            Ok(JsonToken::WhiteSpace(_)) => {
+                // The lexer reads whitespace a char at a time; merge the run into one token.
+                loop {
+                    match self.lexer.next_spanned() {
+                        Some((Ok(JsonToken::WhiteSpace(_)), next)) => span.end = next.end,
+                        next => {
+                            self.peeked = next;
+                            break;
+                        },
+                    }
+                }
+                TokenKind::Whitespace
+            },
+            Ok(JsonToken::Comment) if self.source[span.start..].starts_with("//") => TokenKind::LineComment,
+            Ok(JsonToken::Comment) => TokenKind::BlockComment,
+            Ok(JsonToken::Invalid) => TokenKind::Invalid,
+            Err(e) => {
+                self.errors.push(e);
+                TokenKind::Invalid
+            },
+        };
+        Some(Token { kind, span, text: &self.source[span.start..span.end] })
+    }
+}
@@ -0,0 +1,588 @@
+//! Writing JSON text, from a `JsonValue` or piece by piece with a `JsonWriter`.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+#[cfg(feature = "std")]
+use crate::error::{WriteError, WriteErrorKind};
+use crate::json::JsonValue;
+
+/// Writes `s` as a JSON string, escaping quotation marks, reverse solidi and control characters.
+pub(crate) fn write_string<W: Write>(out: &mut W, s: &str) -> core::fmt::Result {
+    write_string_with(out, s, &WriteOptions::default())
+}
+
+/// Writes `s` as a JSON string, also escaping the characters `options` asks for.
+pub(crate) fn write_string_with<W: Write>(out: &mut W, s: &str, options: &WriteOptions) -> core::fmt::Result {
+    out.write_char('"')?;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let escaped = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{8}' => "\\b",
+            '\u{c}' => "\\f",
+            '\u{0}'..='\u{1f}' => "",
+            '<' | '>' | '&' | '\u{2028}' | '\u{2029}' if options.html_safe => "",
+            _ if options.ascii && !c.is_ascii() => "",
+            _ => continue,
+        };
+        out.write_str(&s[start..i])?;
+        if escaped.is_empty() {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                write!(out, "\\u{:04x}", unit)?;
+            }
+        } else {
+            out.write_str(escaped)?;
+        }
+        start = i + c.len_utf8();
+    }
+    out.write_str(&s[start..])?;
+    out.write_char('"')
+}
+
+/// Options for [`to_string_with`] and [`to_string_pretty_with`], also taken by `JsonWriter` and
+/// the serde `Serializer`.
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    float_fraction: bool,
+    ascii: bool,
+    html_safe: bool,
+}
+
+impl WriteOptions {
+    pub fn new() -> WriteOptions {
+        WriteOptions::default()
+    }
+
+    /// Writes whole numbers as `1.0` rather than `1`, for readers that tell integers and floats
+    /// apart. Numbers written with an exponent, such as `1e+21`, are unchanged, and so are
+    /// integers passed to `JsonWriter::value`.
+    pub fn float_fraction(mut self, float_fraction: bool) -> WriteOptions {
+        self.float_fraction = float_fraction;
+        self
+    }
+
+    /// Escapes every non-ASCII character as `\uXXXX`, with a surrogate pair outside the Basic
+    /// Multilingual Plane, so the output is 7-bit clean.
+    pub fn ascii(mut self, ascii: bool) -> WriteOptions {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Escapes `<`, `>`, `&`, U+2028 and U+2029, so the output can be embedded in an HTML
+    /// `<script>` element without closing it or opening a comment.
+    pub fn html_safe(mut self, html_safe: bool) -> WriteOptions {
+        self.html_safe = html_safe;
+        self
+    }
+}
+
+/// Writes a number in the shortest form that reads back as the same `f64`, laid out like
+/// ECMAScript's `Number.prototype.toString` except that negative zero keeps its sign.
+/// JSON cannot represent NaN or infinities, so they are written as `null`.
+pub(crate) fn write_number<W: Write>(out: &mut W, n: f64, options: &WriteOptions) -> core::fmt::Result {
+    if !n.is_finite() {
+        return out.write_str("null");
+    }
+    if n == 0.0 && n.is_sign_negative() {
+        out.write_char('-')?;
+    }
+    write_es_number(out, n)?;
+    if options.float_fraction && crate::json::is_integer(n) && n.abs() < 1e21 {
+        out.write_str(".0")?;
+    }
+    Ok(())
+}
+
+/// A fixed buffer for formatting without allocating; long enough for any `{:e}` of an `f64`.
+struct Buffer {
+    bytes: [u8; 32],
+    len: usize,
+}
+
+impl Buffer {
+    fn new() -> Buffer {
+        Buffer { bytes: [0; 32], len: 0 }
+    }
+
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap()
+    }
+}
+
+impl Write for Buffer {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let bytes = self.bytes.get_mut(self.len..self.len + s.len()).ok_or(core::fmt::Error)?;
+        bytes.copy_from_slice(s.as_bytes());
+        self.len += s.len();
+        Ok(())
+    }
+}
+
+/// Finds the shortest digits that read back as a finite, positive `n`, returned as the digits
+/// and the power of ten of the last one.
+///
+/// `{:e}` already yields the shortest round-tripping digits (Grisu, falling back to Dragon4
+/// where Grisu cannot decide), choosing the candidate closest to `n`. When `n` lies exactly
+/// halfway between two candidates ECMAScript picks the even one, which `{:e}` does not promise.
+fn shortest_digits(n: f64) -> (u64, i32) {
+    let mut buf = Buffer::new();
+    write!(buf, "{:e}", n).unwrap();
+    let (mantissa, exponent) = buf.as_str().split_once('e').unwrap();
+    let mut digits = 0u64;
+    let mut len = 0;
+    for b in mantissa.bytes().filter(u8::is_ascii_digit) {
+        digits = digits * 10 + (b - b'0') as u64;
+        len += 1;
+    }
+    let power = exponent.parse::<i32>().unwrap() + 1 - len;
+    if digits % 2 == 1 {
+        for even in [digits - 1, digits + 1] {
+            if (10u64.pow(len as u32 - 1)..10u64.pow(len as u32)).contains(&even)
+                && is_halfway(n, digits + even, power)
+                && reads_back(n, even, power) {
+                return (even, power);
+            }
+        }
+    }
+    (digits, power)
+}
+
+/// Whether `n` equals `odd / 2 * 10^power` exactly, for an odd `odd`.
+fn is_halfway(n: f64, odd: u64, power: i32) -> bool {
+    let bits = n.to_bits();
+    let fraction = bits & ((1 << 52) - 1);
+    let biased = (bits >> 52) as i32 & 0x7ff;
+    let (mantissa, exponent) = if biased == 0 { (fraction, -1074) } else { (fraction | 1 << 52, biased - 1075) };
+    let shift = mantissa.trailing_zeros();
+    // With `n = m * 2^e` for an odd `m`, the powers of two and five must match separately.
+    let (m, e) = ((mantissa >> shift) as u128, exponent + shift as i32);
+    let Some(five) = 5u128.checked_pow(power.unsigned_abs()) else {
+        return false;
+    };
+    e == power - 1
+        && if power >= 0 { (odd as u128).checked_mul(five) == Some(m) } else { m.checked_mul(five) == Some(odd as u128) }
+}
+
+fn reads_back(n: f64, digits: u64, power: i32) -> bool {
+    let mut buf = Buffer::new();
+    write!(buf, "{}e{}", digits, power).unwrap();
+    buf.as_str().parse() == Ok(n)
+}
+
+/// Writes a finite number the way ECMAScript's `Number.prototype.toString` does.
+///
+/// The digits are the shortest ones that round-trip; the exponent form is used
+/// below `1e-6` and from `1e21`, as required by RFC 8785.
+pub(crate) fn write_es_number<W: Write>(out: &mut W, n: f64) -> core::fmt::Result {
+    if n == 0.0 {
+        return out.write_char('0');
+    }
+    if n < 0.0 {
+        out.write_char('-')?;
+    }
+    let (digits, power) = shortest_digits(n.abs());
+    let mut buf = Buffer::new();
+    write!(buf, "{}", digits).unwrap();
+    let digits = buf.as_str();
+    let k = digits.len() as i32;
+    let n = power + k;
+    if k <= n && n <= 21 {
+        out.write_str(digits)?;
+        for _ in k..n {
+            out.write_char('0')?;
+        }
+        Ok(())
+    } else if 0 < n && n <= 21 {
+        let (integer, frac) = digits.split_at(n as usize);
+        write!(out, "{}.{}", integer, frac)
+    } else if -6 < n && n <= 0 {
+        out.write_str("0.")?;
+        for _ in n..0 {
+            out.write_char('0')?;
+        }
+        out.write_str(digits)
+    } else {
+        let (first, rest) = digits.split_at(1);
+        out.write_str(first)?;
+        if !rest.is_empty() {
+            write!(out, ".{}", rest)?;
+        }
+        let e = n - 1;
+        write!(out, "e{}{}", if e < 0 { '-' } else { '+' }, e.abs())
+    }
+}
+
+/// Writes `value` as compact JSON text.
+pub(crate) fn write_value<W: Write>(out: &mut W, value: &JsonValue, options: &WriteOptions) -> core::fmt::Result {
+    match value {
+        JsonValue::Number(n) => write_number(out, *n, options),
+        #[cfg(feature = "arbitrary_precision")]
+        JsonValue::Decimal(d) => write!(out, "{}", d),
+        JsonValue::String(s) => write_string_with(out, s, options),
+        JsonValue::Boolean(b) => write!(out, "{}", b),
+        JsonValue::Null => out.write_str("null"),
+        JsonValue::Array(values) => {
+            out.write_char('[')?;
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.write_char(',')?;
+                }
+                write_value(out, value, options)?;
+            }
+            out.write_char(']')
+        },
+        JsonValue::Objects(objects) => {
+            out.write_char('{')?;
+            for (i, object) in objects.iter().enumerate() {
+                if i > 0 {
+                    out.write_char(',')?;
+                }
+                write_string_with(out, &object.name, options)?;
+                out.write_char(':')?;
+                write_value(out, &object.value, options)?;
+            }
+            out.write_char('}')
+        },
+    }
+}
+
+/// Writes `value` with one member or element per line, nested `level` times `indent` deep.
+pub(crate) fn write_pretty<W: Write>(
+    out: &mut W,
+    value: &JsonValue,
+    indent: &str,
+    level: usize,
+    options: &WriteOptions,
+) -> core::fmt::Result {
+    let (open, close, len) = match value {
+        JsonValue::Array(values) if !values.is_empty() => ('[', ']', values.len()),
+        JsonValue::Objects(objects) if !objects.is_empty() => ('{', '}', objects.len()),
+        _ => return write_value(out, value, options),
+    };
+    out.write_char(open)?;
+    for i in 0..len {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        out.write_char('\n')?;
+        for _ in 0..=level {
+            out.write_str(indent)?;
+        }
+        match value {
+            JsonValue::Array(values) => write_pretty(out, &values[i], indent, level + 1, options)?,
+            JsonValue::Objects(objects) => {
+                write_string_with(out, &objects[i].name, options)?;
+                out.write_str(": ")?;
+                write_pretty(out, &objects[i].value, indent, level + 1, options)?;
+            },
+            _ => unreachable!(),
+        }
+    }
+    out.write_char('\n')?;
+    for _ in 0..level {
+        out.write_str(indent)?;
+    }
+    out.write_char(close)
+}
+
+/// Serializes `value` as compact JSON text.
+pub fn to_string(value: &JsonValue) -> String {
+    to_string_with(value, &WriteOptions::default())
+}
+
+/// Serializes `value` as compact JSON text, formatted according to `options`.
+pub fn to_string_with(value: &JsonValue, options: &WriteOptions) -> String {
+    let mut buf = String::new();
+    write_value(&mut buf, value, options).unwrap();
+    buf
+}
+
+/// Serializes `value` as indented JSON text, indenting each level with `indent`.
+pub fn to_string_pretty(value: &JsonValue, indent: &str) -> String {
+    to_string_pretty_with(value, indent, &WriteOptions::default())
+}
+
+/// Serializes `value` as indented JSON text, formatted according to `options`.
+pub fn to_string_pretty_with(value: &JsonValue, indent: &str, options: &WriteOptions) -> String {
+    let mut buf = String::new();
+    write_pretty(&mut buf, value, indent, 0, options).unwrap();
+    buf
+}
+
+/// A single JSON value accepted by [`JsonWriter::value`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scalar<'a> {
+    Null,
+    Boolean(bool),
+    I64(i64),
+    U64(u64),
+    Number(f64),
+    String(&'a str),
+    #[cfg(feature = "arbitrary_precision")]
+    Decimal(&'a crate::decimal::Decimal),
+}
+
+impl Scalar<'_> {
+    fn write<W: Write>(&self, out: &mut W, options: &WriteOptions) -> core::fmt::Result {
+        match self {
+            Scalar::Null => out.write_str("null"),
+            Scalar::Boolean(b) => write!(out, "{}", b),
+            Scalar::I64(n) => write!(out, "{}", n),
+            Scalar::U64(n) => write!(out, "{}", n),
+            Scalar::Number(n) => write_number(out, *n, options),
+            Scalar::String(s) => write_string_with(out, s, options),
+            #[cfg(feature = "arbitrary_precision")]
+            Scalar::Decimal(d) => write!(out, "{}", d),
+        }
+    }
+}
+
+impl core::fmt::Display for Scalar<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.write(f, &WriteOptions::default())
+    }
+}
+
+impl From<bool> for Scalar<'_> {
+    fn from(b: bool) -> Self {
+        Scalar::Boolean(b)
+    }
+}
+
+impl<'a> From<&'a str> for Scalar<'a> {
+    fn from(s: &'a str) -> Self {
+        Scalar::String(s)
+    }
+}
+
+impl<'a> From<&'a String> for Scalar<'a> {
+    fn from(s: &'a String) -> Self {
+        Scalar::String(s)
+    }
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl<'a> From<&'a crate::decimal::Decimal> for Scalar<'a> {
+    fn from(d: &'a crate::decimal::Decimal) -> Self {
+        Scalar::Decimal(d)
+    }
+}
+
+impl<'a, T: Into<Scalar<'a>>> From<Option<T>> for Scalar<'a> {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Scalar::Null, Into::into)
+    }
+}
+
+macro_rules! scalar_from_number {
+    ($variant:ident as $as:ty: $($t:ty)*) => {
+        $(
+            impl From<$t> for Scalar<'_> {
+                fn from(n: $t) -> Self {
+                    Scalar::$variant(n as $as)
+                }
+            }
+        )*
+    };
+}
+
+scalar_from_number!(I64 as i64: i8 i16 i32 i64 isize);
+scalar_from_number!(U64 as u64: u8 u16 u32 u64 usize);
+scalar_from_number!(Number as f64: f32 f64);
+
+/// Writes JSON text to an [`io::Write`](std::io::Write) one token at a time, without building a
+/// [`JsonValue`].
+///
+/// The calls are checked as they are made: object members need a [`key`](Self::key) before
+/// their value, every `begin_*` needs an [`end`](Self::end), and only one value may be written
+/// at the top level. After an error the output may hold a partial document.
+///
+/// ```
+/// use crystalrake::writer::JsonWriter;
+///
+/// let mut writer = JsonWriter::new(Vec::new());
+/// writer.begin_object()?;
+/// writer.key("ids")?;
+/// writer.begin_array()?;
+/// for id in 1..=3 {
+///     writer.value(id)?;
+/// }
+/// writer.end()?;
+/// writer.end()?;
+/// assert_eq!(writer.finish()?, br#"{"ids":[1,2,3]}"#);
+/// # Ok::<(), crystalrake::error::WriteError>(())
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct JsonWriter<W: std::io::Write> {
+    out: W,
+    /// `None` for compact output.
+    indent: Option<String>,
+    /// Open arrays and objects, innermost last.
+    open: alloc::vec::Vec<Frame>,
+    /// Whether the top-level value is complete.
+    done: bool,
+    buf: String,
+    options: WriteOptions,
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    object: bool,
+    len: usize,
+    /// A key was written and its value has not been.
+    key: bool,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> JsonWriter<W> {
+    /// Creates a writer of compact JSON.
+    pub fn new(out: W) -> JsonWriter<W> {
+        JsonWriter {
+            out,
+            indent: None,
+            open: alloc::vec::Vec::new(),
+            done: false,
+            buf: String::new(),
+            options: WriteOptions::default(),
+        }
+    }
+
+    /// Creates a writer of indented JSON, formatted like [`to_string_pretty`].
+    pub fn pretty(out: W, indent: &str) -> JsonWriter<W> {
+        JsonWriter { indent: Some(String::from(indent)), ..JsonWriter::new(out) }
+    }
+
+    /// Formats numbers and strings according to `options`.
+    pub fn with_options(mut self, options: WriteOptions) -> JsonWriter<W> {
+        self.options = options;
+        self
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), WriteError> {
+        self.begin(true)
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), WriteError> {
+        self.begin(false)
+    }
+
+    /// Writes the name of the next object member.
+    pub fn key(&mut self, name: &str) -> Result<(), WriteError> {
+        let len = match self.open.last_mut() {
+            Some(frame) if frame.object && !frame.key => {
+                frame.key = true;
+                frame.len += 1;
+                frame.len
+            },
+            _ => return Err(WriteErrorKind::UnexpectedKey.into()),
+        };
+        self.separator(len)?;
+        self.buf.clear();
+        write_string_with(&mut self.buf, name, &self.options).unwrap();
+        self.buf.push(':');
+        if self.indent.is_some() {
+            self.buf.push(' ');
+        }
+        self.out.write_all(self.buf.as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes a string, number, boolean or null.
+    ///
+    /// Integers are written exactly. NaN and infinities are rejected, since JSON has no way to
+    /// write them.
+    pub fn value<'a, T: Into<Scalar<'a>>>(&mut self, value: T) -> Result<(), WriteError> {
+        let value = value.into();
+        if matches!(value, Scalar::Number(n) if !n.is_finite()) {
+            return Err(WriteErrorKind::NonFiniteNumber.into());
+        }
+        self.before_value()?;
+        self.buf.clear();
+        value.write(&mut self.buf, &self.options).unwrap();
+        self.out.write_all(self.buf.as_bytes())?;
+        if self.open.is_empty() {
+            self.done = true;
+        }
+        Ok(())
+    }
+
+    /// Closes the innermost open array or object.
+    pub fn end(&mut self) -> Result<(), WriteError> {
+        let frame = match self.open.last() {
+            Some(frame) if frame.key => return Err(WriteErrorKind::MissingValue.into()),
+            Some(frame) => *frame,
+            None => return Err(WriteErrorKind::UnexpectedEnd.into()),
+        };
+        self.open.pop();
+        if frame.len > 0 {
+            self.newline()?;
+        }
+        self.out.write_all(if frame.object { b"}" } else { b"]" })?;
+        if self.open.is_empty() {
+            self.done = true;
+        }
+        Ok(())
+    }
+
+    /// Checks that a complete value was written, flushes the output and returns it.
+    pub fn finish(mut self) -> Result<W, WriteError> {
+        if !self.done || !self.open.is_empty() {
+            return Err(WriteErrorKind::Incomplete.into());
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn begin(&mut self, object: bool) -> Result<(), WriteError> {
+        self.before_value()?;
+        self.out.write_all(if object { b"{" } else { b"[" })?;
+        self.open.push(Frame { object, len: 0, key: false });
+        Ok(())
+    }
+
+    /// Checks that a value may come next and writes what precedes it.
+    fn before_value(&mut self) -> Result<(), WriteError> {
+        let len = match self.open.last_mut() {
+            None if self.done => return Err(WriteErrorKind::TrailingValue.into()),
+            None => return Ok(()),
+            Some(frame) if frame.object => {
+                if !frame.key {
+                    return Err(WriteErrorKind::MissingKey.into());
+                }
+                frame.key = false;
+                return Ok(());
+            },
+            Some(frame) => {
+                frame.len += 1;
+                frame.len
+            },
+        };
+        self.separator(len)
+    }
+
+    /// Writes what comes before the `len`th member or element of the innermost container.
+    fn separator(&mut self, len: usize) -> Result<(), WriteError> {
+        if len > 1 {
+            self.out.write_all(b",")?;
+        }
+        self.newline()
+    }
+
+    /// In pretty output, starts a new line indented to the current depth.
+    fn newline(&mut self) -> Result<(), WriteError> {
+        if let Some(indent) = &self.indent {
+            self.out.write_all(b"\n")?;
+            for _ in 0..self.open.len() {
+                self.out.write_all(indent.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
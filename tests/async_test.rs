@@ -0,0 +1,100 @@
+#![cfg(feature = "async")]
+extern crate crystalrake;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crystalrake::async_io::{from_async_reader, EventStream};
+use crystalrake::error::{ParseErrorKind, ReadErrorKind};
+use crystalrake::json::JsonValue;
+use crystalrake::push::Event;
+use futures::executor::block_on;
+use futures::io::AsyncRead;
+use futures::StreamExt;
+
+/// An in-memory reader that returns a few bytes at a time and is not ready every other poll.
+struct SlowReader {
+    data: Vec<u8>,
+    position: usize,
+    chunk: usize,
+    ready: bool,
+    fail: bool,
+}
+
+impl SlowReader {
+    fn new(data: &str, chunk: usize) -> SlowReader {
+        SlowReader { data: data.as_bytes().to_vec(), position: 0, chunk, ready: false, fail: false }
+    }
+}
+
+impl AsyncRead for SlowReader {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        self.ready = !self.ready;
+        if !self.ready {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        if self.fail && self.position == self.data.len() {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset")));
+        }
+        let n = self.chunk.min(buf.len()).min(self.data.len() - self.position);
+        buf[..n].copy_from_slice(&self.data[self.position..self.position + n]);
+        self.position += n;
+        Poll::Ready(Ok(n))
+    }
+}
+
+const DOCUMENT: &str = "{\"text\": \"\\u00e9t\u{e9} \u{1f600}\", \"list\": [1.25, -3e2, true, null]}";
+
+/// The event for an integer, which is exact with the `arbitrary_precision` feature.
+fn number(n: i64) -> Event {
+    #[cfg(feature = "arbitrary_precision")]
+    return Event::Decimal(n.into());
+    #[cfg(not(feature = "arbitrary_precision"))]
+    return Event::Number(n as f64);
+}
+
+#[test]
+fn read_value() {
+    let expected: JsonValue = DOCUMENT.parse().unwrap();
+    for chunk in 1..8 {
+        assert_eq!(block_on(from_async_reader(SlowReader::new(DOCUMENT, chunk))).unwrap(), expected);
+    }
+
+    let e = block_on(from_async_reader(SlowReader::new("[1, 2", 2))).unwrap_err();
+    assert!(matches!(e.kind(), ReadErrorKind::Syntax(e) if matches!(e.kind(), ParseErrorKind::NonEndArray)));
+    let e = block_on(from_async_reader(SlowReader::new("[1] [2]", 2))).unwrap_err();
+    assert!(matches!(e.kind(), ReadErrorKind::Syntax(e) if matches!(e.kind(), ParseErrorKind::InvalidToken)));
+
+    let mut reader = SlowReader::new("[1", 1);
+    reader.fail = true;
+    let e = block_on(from_async_reader(reader)).unwrap_err();
+    assert!(matches!(e.kind(), ReadErrorKind::Io(e) if e.kind() == io::ErrorKind::ConnectionReset));
+}
+
+#[test]
+fn event_stream() {
+    let events: Vec<Event> = block_on(EventStream::new(SlowReader::new("{\"a\": [1, \"x\"]}\n7", 3)).map(Result::unwrap).collect());
+    assert_eq!(events, vec![
+        Event::BeginObject,
+        Event::Name("a".to_string()),
+        Event::BeginArray,
+        number(1),
+        Event::String("x".to_string()),
+        Event::EndArray,
+        Event::EndObject,
+        number(7),
+    ]);
+
+    let results: Vec<_> = block_on(EventStream::new(SlowReader::new("[true, ]", 4)).collect());
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[1].as_ref().unwrap(), &Event::Boolean(true));
+    assert!(matches!(results[2].as_ref().unwrap_err().kind(), ReadErrorKind::Syntax(e) if matches!(e.kind(), ParseErrorKind::TrailingComma)));
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn exact_numbers() {
+    let value = block_on(from_async_reader(SlowReader::new("[12345678901234567890.123456789, 9007199254740993]", 3))).unwrap();
+    assert_eq!(value.to_string(), "[12345678901234567890.123456789,9007199254740993]");
+}
@@ -0,0 +1,88 @@
+extern crate crystalrake;
+use crystalrake::canonical;
+use crystalrake::json;
+use crystalrake::error::CanonicalErrorKind;
+use crystalrake::json::*;
+
+fn canonicalize(json: &str) -> String {
+    canonical::to_string(&json.parse::<JsonValue>().unwrap()).unwrap()
+}
+
+#[test]
+fn rfc8785_example() {
+    let json = r#"{
+        "numbers": [333333333.33333329, 1E30, 4.50,
+                    2e-3, 0.000000000000000000000000001],
+        "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+        "literals": [null, true, false]
+    }"#;
+    assert_eq!(
+        canonicalize(json),
+        r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+    );
+}
+
+#[test]
+fn rfc8785_sorting() {
+    let json = r#"{
+        "\u20ac": "Euro Sign",
+        "\r": "Carriage Return",
+        "\ufb33": "Hebrew Letter Dalet With Dagesh",
+        "1": "One",
+        "\ud83d\ude00": "Emoji: Grinning Face",
+        "\u0080": "Control",
+        "\u00f6": "Latin Small Letter O With Diaeresis"
+    }"#;
+    assert_eq!(
+        canonicalize(json),
+        "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\"ö\":\"Latin Small Letter O With Diaeresis\",\
+         \"€\":\"Euro Sign\",\"😀\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+    );
+}
+
+#[test]
+fn rfc8785_numbers() {
+    let vectors = [
+        (0x0000000000000000, "0"),
+        (0x8000000000000000, "0"),
+        (0x0000000000000001, "5e-324"),
+        (0x8000000000000001, "-5e-324"),
+        (0x7fefffffffffffff, "1.7976931348623157e+308"),
+        (0xffefffffffffffff, "-1.7976931348623157e+308"),
+        (0x4340000000000000, "9007199254740992"),
+        (0xc340000000000000, "-9007199254740992"),
+        (0x4430000000000000, "295147905179352830000"),
+        (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+        (0x44b52d02c7e14af6, "1e+23"),
+        (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+        (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+        (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+        (0x444b1ae4d6e2ef50, "1e+21"),
+        (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+        (0x3eb0c6f7a0b5ed8d, "0.000001"),
+        (0x41b3de4355555553, "333333333.3333332"),
+        (0x41b3de4355555554, "333333333.33333325"),
+        (0x41b3de4355555555, "333333333.3333333"),
+        (0x41b3de4355555556, "333333333.3333334"),
+        (0x41b3de4355555557, "333333333.33333343"),
+        (0xbecbf647612f3696, "-0.0000033333333333333333"),
+        (0x43143ff3c1cb0959, "1424953923781206.2"),
+    ];
+    for (bits, expected) in vectors {
+        let value = JsonValue::Number(f64::from_bits(bits));
+        assert_eq!(canonical::to_string(&value).unwrap(), expected, "{:016x}", bits);
+    }
+}
+
+#[test]
+fn rejects_invalid_values() {
+    let error = canonical::to_string(&json!({ "a": [1, f64::NAN] })).unwrap_err();
+    assert_eq!(error.kind(), &CanonicalErrorKind::NonFiniteNumber);
+    assert_eq!(error.pointer(), "/a/1");
+    let error = canonical::to_string(&JsonValue::Number(f64::INFINITY)).unwrap_err();
+    assert_eq!(error.kind(), &CanonicalErrorKind::NonFiniteNumber);
+
+    let error = canonical::to_string(&r#"{"x": {"b": 1, "a": 2, "b": 3}}"#.parse().unwrap()).unwrap_err();
+    assert_eq!(error.kind(), &CanonicalErrorKind::DuplicateKey("b".to_string()));
+    assert_eq!(error.pointer(), "/x");
+}
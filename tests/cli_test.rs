@@ -0,0 +1,142 @@
+#![cfg(feature = "std")]
+extern crate crystalrake;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_crystalrake"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Usage errors exit without reading the input.
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn fmt_and_minify_stdin() {
+    let source = r#"{"a": [1, 2.5, {}], "b": {"c": null}}"#;
+    let output = run(&["fmt"], source);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "{\n  \"a\": [\n    1,\n    2.5,\n    {}\n  ],\n  \"b\": {\n    \"c\": null\n  }\n}\n");
+
+    let output = run(&["fmt", "--tab", "-"], "[[]]");
+    assert_eq!(stdout(&output), "[\n\t[]\n]\n");
+    let output = run(&["fmt", "--indent", "4"], "[1]");
+    assert_eq!(stdout(&output), "[\n    1\n]\n");
+
+    let output = run(&["minify"], "[ 1 ,\n { \"k\" : true } ]");
+    assert_eq!(stdout(&output), "[1,{\"k\":true}]\n");
+
+    // Numbers keep the digits they were written with; strings are escaped by the writer.
+    let source = "[12345678901234567890.123456789, 1.10, -0, 1E+2, \"\\u00e9\\/\"]";
+    let output = run(&["fmt"], source);
+    assert_eq!(stdout(&output), "[\n  12345678901234567890.123456789,\n  1.10,\n  -0,\n  1E+2,\n  \"é/\"\n]\n");
+    let output = run(&["minify"], source);
+    assert_eq!(stdout(&output), "[12345678901234567890.123456789,1.10,-0,1E+2,\"é/\"]\n");
+}
+
+#[test]
+fn check_files() {
+    let dir = std::env::temp_dir().join(format!("crystalrake-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let good = dir.join("good.json");
+    let bad = dir.join("bad.json");
+    std::fs::write(&good, "{\"a\": 1}").unwrap();
+    std::fs::write(&bad, "[1, 2,]").unwrap();
+    let (good, bad) = (good.to_str().unwrap(), bad.to_str().unwrap());
+
+    assert!(run(&["check", good], "").status.success());
+    let output = run(&["check", good, bad], "");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("error: trailing comma is not allowed\n"), "{}", stderr);
+    assert!(stderr.contains(&format!(" --> {}:1:6\n", bad)), "{}", stderr);
+
+    let missing = dir.join("missing.json");
+    assert_eq!(run(&["check", missing.to_str().unwrap()], "").status.code(), Some(2));
+
+    // Rewrite in place.
+    assert!(run(&["fmt", "--write", good], "").status.success());
+    assert_eq!(std::fs::read_to_string(good).unwrap(), "{\n  \"a\": 1\n}\n");
+
+    // Numbers are rewritten exactly as written.
+    std::fs::write(good, r#"{"id": 12345678901234567890, "x": 1.10}"#).unwrap();
+    assert!(run(&["minify", "-w", good], "").status.success());
+    assert_eq!(std::fs::read_to_string(good).unwrap(), "{\"id\":12345678901234567890,\"x\":1.10}\n");
+    let names = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name()).collect::<Vec<_>>();
+    assert_eq!(names.len(), 2, "{:?}", names);
+
+    // An unreadable file does not hide a failed query.
+    let output = run(&["query", ".a[]", good, missing.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(5));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn usage_errors() {
+    assert_eq!(run(&[], "").status.code(), Some(2));
+    assert_eq!(run(&["lint"], "").status.code(), Some(2));
+    assert_eq!(run(&["fmt", "--indent"], "").status.code(), Some(2));
+    assert_eq!(run(&["fmt", "--write"], "").status.code(), Some(2));
+    let output = run(&["--help"], "");
+    assert!(output.status.success());
+    assert!(stdout(&output).starts_with("usage: crystalrake"));
+}
+
+const STORE: &str = r#"{
+    "store": "corner",
+    "items": [
+        {"name": "apple", "price": 1.5, "tags": ["fruit"]},
+        {"name": "bread", "price": 3, "tags": []},
+        {"name": "cheese", "price": 12.25, "tags": ["dairy", "aged"]}
+    ]
+}"#;
+
+fn query(args: &[&str]) -> String {
+    let output = run(&[&["query"], args].concat(), STORE);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    stdout(&output).to_string()
+}
+
+#[test]
+fn query_paths_and_pipes() {
+    assert_eq!(query(&["-c", ".items[0]"]), "{\"name\":\"apple\",\"price\":1.5,\"tags\":[\"fruit\"]}\n");
+    assert_eq!(query(&[".items[-1].tags[1]"]), "\"aged\"\n");
+    assert_eq!(query(&["-r", ".items[].name"]), "apple\nbread\ncheese\n");
+    assert_eq!(query(&["-r", ".[\"store\"], .\"store\""]), "corner\ncorner\n");
+    assert_eq!(query(&[".missing.deeper"]), "null\n");
+    assert_eq!(query(&["-c", ".items | map(.price)"]), "[1.5,3,12.25]\n");
+    assert_eq!(query(&["-c", "[.items[] | .tags | length]"]), "[1,0,2]\n");
+    assert_eq!(query(&["-c", "keys, (.items | keys), (.store | length)"]), "[\"items\",\"store\"]\n[0,1,2]\n6\n");
+    assert_eq!(query(&["-c", "[.. | select(. == \"fruit\")]"]), "[\"fruit\"]\n");
+}
+
+#[test]
+fn query_select_and_objects() {
+    assert_eq!(query(&["-r", ".items[] | select(.price >= 3 and (.tags | length) > 0) | .name"]), "cheese\n");
+    assert_eq!(query(&["-r", ".items[] | select(.price < 2 or .name == \"bread\") | .name"]), "apple\nbread\n");
+    assert_eq!(query(&["-c", ".items[] | select(.tags | length == 0 | not) | {name, first: .tags[0]}"]),
+        "{\"name\":\"apple\",\"first\":\"fruit\"}\n{\"name\":\"cheese\",\"first\":\"dairy\"}\n");
+    assert_eq!(query(&["-c", "{(.store): (.items | length), \"ok\": true}"]), "{\"corner\":3,\"ok\":true}\n");
+    assert_eq!(query(&["{store}"]), "{\n  \"store\": \"corner\"\n}\n");
+}
+
+#[test]
+fn query_errors() {
+    let output = run(&["query", ".items[", "-"], STORE);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid query"));
+    let output = run(&["query", "unknown"], STORE);
+    assert_eq!(output.status.code(), Some(2));
+    let output = run(&["query", ".store[]"], STORE);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot iterate over string"), "{}", String::from_utf8_lossy(&output.stderr));
+}
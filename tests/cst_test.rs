@@ -0,0 +1,99 @@
+extern crate crystalrake;
+use crystalrake::cst::SyntaxTree;
+use crystalrake::error::EditErrorKind;
+use crystalrake::json::JsonValue;
+use crystalrake::span::Span;
+
+const CONFIG: &str = r#"{
+    // editor settings
+    "tabSize": 4,
+    "rulers": [80, 120], /* columns */
+    "theme" :  "dark"
+}
+"#;
+
+#[test]
+fn lossless() {
+    let tree = SyntaxTree::parse_jsonc(CONFIG).unwrap();
+    assert_eq!(tree.to_string(), CONFIG);
+    assert_eq!(tree.value().pointer("/rulers/1"), Some(&JsonValue::Number(120.0)));
+    assert_eq!(tree.raw("/rulers"), Some("[80, 120]"));
+    assert_eq!(tree.raw(""), Some(CONFIG.trim_end()));
+    assert_eq!(tree.span("/tabSize"), Some(Span::new(40, 41)));
+    assert_eq!(tree.raw("/missing"), None);
+
+    assert!(SyntaxTree::parse(CONFIG).is_err());
+    assert!(SyntaxTree::parse_jsonc("[1] /* open").is_err());
+}
+
+#[test]
+fn set_value() {
+    let mut tree = SyntaxTree::parse_jsonc(CONFIG).unwrap();
+    tree.set("/tabSize", &JsonValue::Number(2.0)).unwrap();
+    tree.set("/rulers/0", &JsonValue::new("x\"y")).unwrap();
+    assert_eq!(tree.source(), CONFIG.replace("4,", "2,").replace("[80", "[\"x\\\"y\""));
+    assert_eq!(tree.value().pointer("/rulers/0"), Some(&JsonValue::new("x\"y")));
+
+    tree.set("/rulers/-", &JsonValue::Null).unwrap();
+    tree.set("/rulers/4", &JsonValue::Null).unwrap_err();
+    assert_eq!(tree.raw("/rulers"), Some("[\"x\\\"y\", 120, null]"));
+
+    tree.set("", &JsonValue::Boolean(true)).unwrap();
+    assert_eq!(tree.source(), "true\n");
+}
+
+#[test]
+fn insert_member() {
+    let mut tree = SyntaxTree::parse_jsonc(CONFIG).unwrap();
+    tree.insert("", "font", &JsonValue::Array(vec![JsonValue::new("mono")])).unwrap();
+    assert_eq!(tree.source(), CONFIG.replace("\"dark\"", "\"dark\",\n    \"font\" :  [\"mono\"]"));
+
+    // `set` adds missing members the same way.
+    let mut tree = SyntaxTree::parse("{\"a\":{}, \"b\": 1}").unwrap();
+    tree.set("/c", &JsonValue::Null).unwrap();
+    tree.set("/a/x", &JsonValue::Number(1.5)).unwrap();
+    assert_eq!(tree.source(), "{\"a\":{\"x\": 1.5}, \"b\": 1, \"c\": null}");
+
+    let error = tree.insert("", "b", &JsonValue::Null).unwrap_err();
+    assert_eq!(error.kind(), &EditErrorKind::KeyExists("b".to_string()));
+    assert_eq!(tree.insert("/b", "k", &JsonValue::Null).unwrap_err().kind(), &EditErrorKind::NotAContainer);
+    assert_eq!(tree.insert("/z", "k", &JsonValue::Null).unwrap_err().kind(), &EditErrorKind::NotFound);
+    assert_eq!(tree.insert("z", "k", &JsonValue::Null).unwrap_err().kind(), &EditErrorKind::InvalidPointer);
+}
+
+#[test]
+fn remove_entry() {
+    let mut tree = SyntaxTree::parse_jsonc(CONFIG).unwrap();
+    tree.remove("/rulers").unwrap();
+    assert_eq!(tree.source(), "{\n    // editor settings\n    \"tabSize\": 4,\n    \"theme\" :  \"dark\"\n}\n");
+    tree.remove("/theme").unwrap();
+    assert_eq!(tree.source(), "{\n    // editor settings\n    \"tabSize\": 4\n}\n");
+    tree.remove("/tabSize").unwrap();
+    assert_eq!(tree.source(), "{\n    // editor settings\n    \n}\n");
+    assert_eq!(tree.value(), &JsonValue::Objects(Vec::new()));
+
+    let mut tree = SyntaxTree::parse("[1, [2, 3], 4]").unwrap();
+    tree.remove("/1/0").unwrap();
+    tree.remove("/2").unwrap();
+    assert_eq!(tree.source(), "[1, [3]]");
+    assert_eq!(tree.remove("/5").unwrap_err().kind(), &EditErrorKind::NotFound);
+    assert_eq!(tree.remove("").unwrap_err().kind(), &EditErrorKind::InvalidPointer);
+}
+
+#[test]
+fn remove_last_entry_keeps_comments() {
+    let mut tree = SyntaxTree::parse_jsonc("[1, // one\n 2 // two\n]").unwrap();
+    tree.remove("/1").unwrap();
+    assert_eq!(tree.source(), "[1 // one\n]");
+
+    let source = "{\n  \"a\": 1,\n  \"b\": 2, // tail\n  \"d\": [3] /* d */\n}";
+    let mut tree = SyntaxTree::parse_jsonc(source).unwrap();
+    tree.remove("/d").unwrap();
+    assert_eq!(tree.source(), "{\n  \"a\": 1,\n  \"b\": 2 // tail\n}");
+    tree.remove("/b").unwrap();
+    assert_eq!(tree.source(), "{\n  \"a\": 1\n}");
+
+    let mut tree = SyntaxTree::parse_jsonc("[1, /* c */ 2 // two\n]").unwrap();
+    tree.remove("/1").unwrap();
+    assert_eq!(tree.source(), "[1\n]");
+}
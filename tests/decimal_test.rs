@@ -0,0 +1,127 @@
+#![cfg(feature = "arbitrary_precision")]
+extern crate crystalrake;
+use crystalrake::decimal::Decimal;
+use crystalrake::error::ParseErrorKind;
+use crystalrake::json::JsonValue;
+
+fn decimal(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+#[test]
+fn parse_keeps_exact_digits() {
+    let source = r#"{"amount":12345678901234567890.123456789,"rate":-0.000001,"big":12e3,"scaled":1.50}"#;
+    let value: JsonValue = source.parse().unwrap();
+    assert_eq!(value.get("amount").unwrap().as_decimal().unwrap(), decimal("12345678901234567890.123456789"));
+    assert!(matches!(value.get("rate"), Some(JsonValue::Decimal(_))));
+    assert_eq!(value.to_string(), source);
+    assert_eq!(value.get("big").unwrap().as_f64(), Some(12000.0));
+
+    let e = "[1e99999999999]".parse::<JsonValue>().unwrap_err();
+    assert!(matches!(e.kind(), ParseErrorKind::NumberOutOfRange));
+    assert!("1.".parse::<Decimal>().is_err());
+    assert!("1 ".parse::<Decimal>().is_err());
+}
+
+#[test]
+fn arithmetic_is_exact() {
+    assert_eq!((decimal("0.1") + decimal("0.2")).to_string(), "0.3");
+    assert_eq!((decimal("1.5") - decimal("2.25")).to_string(), "-0.75");
+    assert_eq!((decimal("-1.5") - decimal("-1.5")).to_string(), "0.0");
+    assert_eq!((&decimal("123456789.123456789") * &decimal("987654321.987654321")).to_string(),
+        "121932631356500531.347203169112635269");
+    assert_eq!((decimal("99999999999999999999") + decimal("1")).to_string(), "100000000000000000000");
+    assert_eq!((decimal("1e3") + decimal("0.5")).to_string(), "1000.5");
+    assert_eq!((decimal("2e3") * decimal("3e2")).to_string(), "6e5");
+    assert_eq!((-decimal("4.2")).to_string(), "-4.2");
+}
+
+#[test]
+fn comparison() {
+    assert_eq!(decimal("1.50"), decimal("1.5"));
+    assert_eq!(decimal("1e2"), decimal("100"));
+    assert_eq!(decimal("-0"), decimal("0.000"));
+    assert!(decimal("-2") < decimal("-1.99"));
+    assert!(decimal("0.30000000000000000001") > decimal("0.3"));
+    assert!(decimal("-0.1") < Decimal::zero(0));
+
+    // Numbers compare by value whether they were parsed exactly or not.
+    assert_eq!(JsonValue::Number(1.5), "1.50".parse::<JsonValue>().unwrap());
+    assert_ne!(JsonValue::Number(0.1), "0.10000000000000000001".parse::<JsonValue>().unwrap());
+}
+
+#[test]
+fn scale_conversion() {
+    assert_eq!(decimal("1.50").scale(), 2);
+    assert_eq!(decimal("12e3").scale(), -3);
+    assert_eq!(decimal("1.5").with_scale(3).to_string(), "1.500");
+    assert_eq!(decimal("12e3").with_scale(1).to_string(), "12000.0");
+    // Half to even.
+    assert_eq!(decimal("2.5").with_scale(0).to_string(), "2");
+    assert_eq!(decimal("3.5").with_scale(0).to_string(), "4");
+    assert_eq!(decimal("-2.51").with_scale(0).to_string(), "-3");
+    assert_eq!(decimal("1.005").with_scale(2).to_string(), "1.00");
+    assert_eq!(decimal("0.05").with_scale(0).to_string(), "0");
+    assert_eq!(decimal("0.0049").with_scale(2).to_string(), "0.00");
+    assert_eq!(decimal("999.5").with_scale(-1).to_string(), "100e1");
+    assert_eq!(decimal("1.2300").normalized().to_string(), "1.23");
+    assert_eq!(decimal("100").normalized().to_string(), "100");
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(Decimal::new(1999, 2).to_string(), "19.99");
+    assert_eq!(Decimal::new(-5, 3).to_string(), "-0.005");
+    assert_eq!(Decimal::from(u64::MAX).to_string(), "18446744073709551615");
+    assert_eq!(Decimal::from_f64(0.1).unwrap(), decimal("0.1"));
+    assert_eq!(Decimal::from_f64(1e21).unwrap().to_string(), "1e21");
+    assert!(Decimal::from_f64(f64::NAN).is_none());
+    assert_eq!(decimal("12e3").to_i128(), Some(12000));
+    assert_eq!(decimal("-7.000").to_i128(), Some(-7));
+    assert_eq!(decimal("7.5").to_i128(), None);
+    assert_eq!(decimal("1e40").to_i128(), None);
+    assert_eq!(decimal("0.1").to_f64(), 0.1);
+
+    let value: JsonValue = "[18446744073709551615, 1.0, 1.5]".parse().unwrap();
+    assert_eq!(value.deserialize::<Vec<Decimal>>().unwrap()[2], decimal("1.5"));
+    assert_eq!(value.as_array().unwrap()[0].deserialize::<u64>().unwrap(), u64::MAX);
+    assert_eq!(value.as_array().unwrap()[1].deserialize::<u8>().unwrap(), 1);
+    assert!(value.as_array().unwrap()[2].deserialize::<u8>().is_err());
+}
+
+#[test]
+fn extreme_exponents() {
+    let tiny = decimal("1e-100000");
+    assert_eq!(tiny.to_string(), "1e-100000");
+    assert_eq!(tiny.to_f64(), 0.0);
+    assert_ne!(tiny, decimal("1"));
+    assert!(tiny < decimal("0.1") && tiny > Decimal::zero(0));
+    assert!(decimal("-1e-100000") > decimal("-1e-99999"));
+
+    let huge = decimal("-12e100000");
+    assert_eq!(huge.to_string(), "-12e100000");
+    assert_eq!(huge.to_f64(), f64::NEG_INFINITY);
+    assert_eq!(huge.to_i128(), None);
+    assert!(huge < tiny);
+    assert_eq!(decimal("170141183460469231731687303715884105727").to_i128(), Some(i128::MAX));
+    assert_eq!(decimal("1e39").to_i128(), None);
+
+    // Exponents are bounded, so arithmetic writes out at most that many zeros.
+    for text in ["1e-2000000000", "1e2000000000", "1e100001", "1e-100001"] {
+        assert!(matches!(text.parse::<Decimal>().unwrap_err().kind(), ParseErrorKind::NumberOutOfRange), "{}", text);
+    }
+    assert_eq!((&tiny + &Decimal::from(1)).scale(), 100_000);
+    assert_eq!(decimal("1e100000").with_scale(2).to_string().len(), 100_004);
+    let digits = format!("0.{}1e-100000", "0".repeat(99_999));
+    assert_eq!(decimal(&digits).scale(), 200_000);
+
+    // Ordinary fractions keep their text; tiny ones switch to an exponent.
+    assert_eq!(decimal("0.05").to_string(), "0.05");
+    assert_eq!(decimal("0.0000001").to_string(), "1e-7");
+    assert_eq!(decimal("123.4e-10").to_string(), "1234e-11");
+    assert_eq!(decimal("1234e-11").to_f64(), 1.234e-8);
+
+    let value: JsonValue = "[1e-100000, 5e-324]".parse().unwrap();
+    assert_eq!(value.to_string(), "[1e-100000,5e-324]");
+    assert_eq!(value.as_array().unwrap()[0].as_f64(), Some(0.0));
+}
@@ -1,5 +1,9 @@
 extern crate crystalrake;
 
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+use crystalrake::error::{FromJsonError, FromJsonErrorKind};
 use crystalrake::json::*;
 
 #[derive(Debug, Eq, PartialEq)]
@@ -13,18 +17,11 @@ impl A {
     }
 }
 
-#[derive(Debug)]
-struct JsonDeserializeError {
-    message: String,
-}
-
 impl FromJson for A {
-    type Err = JsonDeserializeError;
-    fn from_json(json: &JsonValue) -> Result<Self, Self::Err> {
-        match json {
-            // This code must change. It's panicable.
-            JsonValue::Number(n) =>  Ok(A { number: *n as i32}) ,
-            _ => Err(JsonDeserializeError{ message: "fail".to_owned()})
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        match json.as_f64() {
+            Some(n) =>  Ok(A { number: n as i32}) ,
+            None => Err(FromJsonError::invalid_type("number", json))
         }
     }
 }
@@ -34,4 +31,55 @@ impl FromJson for A {
 fn simple_deserialize() {
     let a : A = "1000000000000000".parse::<JsonValue>().unwrap().deserialize().unwrap();
     assert_eq!(a, A::new(1000000000000000));
-}
\ No newline at end of file
+}
+
+#[derive(Debug, PartialEq)]
+struct User {
+    name: String,
+    age: u8,
+    email: Option<String>,
+}
+
+impl FromJson for User {
+    fn from_json(json: &JsonValue) -> Result<Self, FromJsonError> {
+        Ok(User {
+            name: json.field("name")?,
+            age: json.field("age")?,
+            email: json.optional_field("email")?,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn nested_deserialize() {
+    let json = r#"{"users": [{"name": "a", "age": 20}, {"name": "b", "age": 30, "email": "b@example.com"}]}"#;
+    let users: HashMap<String, Vec<User>> = json.parse::<JsonValue>().unwrap().deserialize().unwrap();
+    assert_eq!(users["users"][1], User { name: "b".to_string(), age: 30, email: Some("b@example.com".to_string()) });
+    assert_eq!(users["users"][0].email, None);
+}
+
+#[test]
+fn error_paths() {
+    let error_of = |json: &str| json.parse::<JsonValue>().unwrap().field::<Vec<User>>("users").unwrap_err();
+
+    let e = error_of(r#"{"users": [{"name": "a", "age": 1}, {}, {}, {"name": "d", "age": "4"}]}"#);
+    assert_eq!(e.to_string(), "at /users/1: missing field 'name'");
+
+    let e = error_of(r#"{"users": [{"name": "a", "age": 1}, {"name": "b", "age": 2}, {"name": "c", "age": 3}, {"name": "d", "age": "4"}]}"#);
+    assert_eq!(e.to_string(), "at /users/3/age: expected integer, found string");
+    assert_eq!(e.pointer(), "/users/3/age");
+
+    let e = error_of(r#"{"users": [{"name": "a/b", "age": 256}]}"#);
+    assert_eq!(e.kind(), &FromJsonErrorKind::OutOfRange("u8"));
+    assert_eq!(e.to_string(), "at /users/0/age: number out of range for u8");
+
+    #[cfg(feature = "std")]
+    {
+        let e = r#"{"m": {"a/b": [true, 1]}}"#.parse::<JsonValue>().unwrap().deserialize::<HashMap<String, HashMap<String, Vec<bool>>>>().unwrap_err();
+        assert_eq!(e.to_string(), "at /m/a~1b/1: expected boolean, found number");
+    }
+
+    let e = JsonValue::Null.deserialize::<i32>().unwrap_err();
+    assert_eq!(e.to_string(), "expected integer, found null");
+}
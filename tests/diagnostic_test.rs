@@ -0,0 +1,73 @@
+extern crate crystalrake;
+use crystalrake::json::*;
+
+fn render(source: &str) -> String {
+    source.parse::<JsonValue>().unwrap_err().render(source)
+}
+
+#[test]
+fn plain_rendering() {
+    assert_eq!(render("[1, 2,]"), "\
+error: trailing comma is not allowed
+ --> 1:6
+  |
+1 | [1, 2,]
+  |      ^
+  = expected: value
+  = hint: trailing comma not allowed in strict mode
+");
+
+    let source = "{\n  \"name\": \"crystalrake\",\n  \"tags\": [\"json\" \"rust\"]\n}";
+    assert_eq!(render(source), "\
+error: invalid token found in JSON
+ --> 3:19
+  |
+3 |   \"tags\": [\"json\" \"rust\"]
+  |                   ^^^^^^
+  = expected: `,` or `]`
+  = hint: a comma may be missing
+");
+
+    assert_eq!(render("{\"a\": 'b'}"), "\
+error: invalid charactor ''' found from JSON
+ --> 1:7
+  |
+1 | {\"a\": 'b'}
+  |       ^
+  = hint: strings must be enclosed in double quotes
+");
+
+    assert_eq!(render("[1"), "\
+error: expect end-of-array ']', but cannot find right square bracket
+ --> 1:3
+  |
+1 | [1
+  |   ^
+  = expected: `,` or `]`
+  = hint: add `]` to close the array
+");
+}
+
+#[test]
+fn colored_rendering() {
+    let source = "\t[007]";
+    let rendered = source.parse::<JsonValue>().unwrap_err().render_colored(source);
+    assert_eq!(rendered, "\
+\x1b[1;31merror\x1b[0m\x1b[1m: number cannot have a leading zero\x1b[0m
+ \x1b[1;34m-->\x1b[0m 1:3
+  \x1b[1;34m|\x1b[0m
+\x1b[1;34m1 |\x1b[0m \t[007]
+  \x1b[1;34m|\x1b[0m \t \x1b[1;31m^^\x1b[0m
+  \x1b[1;34m=\x1b[0m \x1b[1mhint\x1b[0m: numbers cannot have leading zeros
+");
+}
+
+#[test]
+fn tolerant_diagnostics() {
+    let source = "{\"a\" 1, \"b\": [true false]}";
+    let recovery = JsonValue::parse_tolerant(source);
+    let hints: Vec<_> = recovery.errors.iter().map(|e| e.hint().unwrap()).collect();
+    assert_eq!(hints, vec!["a colon may be missing after the member name", "a comma may be missing"]);
+    assert_eq!(recovery.errors[0].expected(), &["`:`"]);
+    assert!(recovery.errors[1].render(source).contains("\n1 | {\"a\" 1, \"b\": [true false]}\n  |                    ^^^^^\n"));
+}
@@ -0,0 +1,76 @@
+extern crate crystalrake;
+use crystalrake::diff::*;
+use crystalrake::json;
+use crystalrake::json::JsonValue;
+
+#[test]
+fn object_changes() {
+    let old = json!({ "name": "a", "age": 3, "tags": ["x"], "a/b": 1 });
+    let new = json!({ "name": "a", "age": 4, "tags": ["x", "y"], "email": null });
+    assert_eq!(diff(&old, &new), vec![
+        Change::Changed { pointer: "/age".to_string(), old: json!(3), new: json!(4) },
+        Change::Added { pointer: "/tags/1".to_string(), value: json!("y") },
+        Change::Removed { pointer: "/a~1b".to_string(), value: json!(1) },
+        Change::Added { pointer: "/email".to_string(), value: json!(null) },
+    ]);
+    assert!(diff(&old, &old).is_empty());
+    assert_eq!(diff(&json!(1), &json!("1")), vec![Change::Changed { pointer: String::new(), old: json!(1), new: json!("1") }]);
+}
+
+#[test]
+fn lcs_alignment() {
+    let old = json!([1, 2, 3, 4]);
+    let new = json!([0, 1, 3, 4]);
+    assert_eq!(diff(&old, &new).len(), 2);
+    assert_eq!(diff_with(&old, &new, &DiffOptions::new().lcs(true)), vec![
+        Change::Added { pointer: "/0".to_string(), value: json!(0) },
+        Change::Removed { pointer: "/1".to_string(), value: json!(2) },
+    ]);
+
+    let old = json!(["a", "b", "c", { "k": 1 }]);
+    let new = json!(["c", "a", "b", { "k": 2 }, "d"]);
+    assert_eq!(diff_with(&old, &new, &DiffOptions::new().lcs(true)), vec![
+        Change::Moved { from: "/2".to_string(), to: "/0".to_string(), value: json!("c") },
+        Change::Changed { pointer: "/3/k".to_string(), old: json!(1), new: json!(2) },
+        Change::Added { pointer: "/4".to_string(), value: json!("d") },
+    ]);
+}
+
+#[test]
+fn lcs_large_arrays() {
+    let old = JsonValue::Array((0..100_000).map(JsonValue::from).collect());
+    let mut values: Vec<JsonValue> = (0..100_000).map(JsonValue::from).collect();
+    values.remove(70_000);
+    values.insert(30_000, json!("x"));
+    let new = JsonValue::Array(values);
+    assert_eq!(diff_with(&old, &new, &DiffOptions::new().lcs(true)), vec![
+        Change::Added { pointer: "/30000".to_string(), value: json!("x") },
+        Change::Removed { pointer: "/70000".to_string(), value: json!(70_000) },
+    ]);
+
+    let old = JsonValue::Array((0..3000).map(JsonValue::from).collect());
+    let new = JsonValue::Array((0..3000).filter(|n| n % 3 != 0).map(JsonValue::from).collect());
+    let changes = diff_with(&old, &new, &DiffOptions::new().lcs(true));
+    assert_eq!(changes.len(), 1000);
+    assert!(changes.iter().all(|change| matches!(change, Change::Removed { value, .. } if value.as_f64().unwrap() % 3.0 == 0.0)));
+}
+
+#[test]
+fn unified_rendering() {
+    let changes = diff_with(
+        &json!({ "list": [1, 2], "s": "old" }),
+        &json!({ "list": [2, 1], "s": "new", "n": [true] }),
+        &DiffOptions::new().lcs(true),
+    );
+    assert_eq!(render(&changes), "\
+@@ /list/0 -> /list/1 @@
+ 1
+@@ /s @@
+-\"old\"
++\"new\"
+@@ /n @@
++[true]
+");
+    assert_eq!(changes[0].pointer(), "/list/1");
+    assert_eq!(render(&diff(&json!(null), &json!(false))), "@@ (root) @@\n-null\n+false\n");
+}
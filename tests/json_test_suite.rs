@@ -0,0 +1,41 @@
+extern crate crystalrake;
+use std::{fs, path::PathBuf};
+
+use crystalrake::json::*;
+
+fn test_parsing_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/JSONTestSuite/test_parsing")
+}
+
+fn parse_file(path: &PathBuf) -> bool {
+    let bytes = fs::read(path).unwrap();
+    match std::str::from_utf8(&bytes) {
+        Ok(s) => s.parse::<JsonValue>().is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn cases(prefix: &str) -> Vec<PathBuf> {
+    let mut paths = fs::read_dir(test_parsing_dir()).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.file_name().unwrap().to_string_lossy().starts_with(prefix))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn accept_y_cases() {
+    let cases = cases("y_");
+    assert_eq!(cases.len(), 95);
+    let failed = cases.iter().filter(|path| !parse_file(path)).collect::<Vec<_>>();
+    assert!(failed.is_empty(), "rejected valid JSON: {:#?}", failed);
+}
+
+#[test]
+fn reject_n_cases() {
+    let cases = cases("n_");
+    assert_eq!(cases.len(), 188);
+    let failed = cases.iter().filter(|path| parse_file(path)).collect::<Vec<_>>();
+    assert!(failed.is_empty(), "accepted invalid JSON: {:#?}", failed);
+}
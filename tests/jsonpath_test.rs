@@ -0,0 +1,126 @@
+extern crate crystalrake;
+use crystalrake::error::JsonPathErrorKind;
+use crystalrake::json::*;
+use crystalrake::jsonpath::*;
+
+const STORE: &str = r#"{ "store": {
+    "book": [
+      { "category": "reference", "author": "Nigel Rees", "title": "Sayings of the Century", "price": 8.95 },
+      { "category": "fiction", "author": "Evelyn Waugh", "title": "Sword of Honour", "price": 12.99 },
+      { "category": "fiction", "author": "Herman Melville", "title": "Moby Dick", "isbn": "0-553-21311-3", "price": 8.99 },
+      { "category": "fiction", "author": "J. R. R. Tolkien", "title": "The Lord of the Rings", "isbn": "0-395-19395-8", "price": 22.99 }
+    ],
+    "bicycle": { "color": "red", "price": 399 }
+  }
+}"#;
+
+fn paths(json: &JsonValue, path: &str) -> Vec<String> {
+    json.query(path).unwrap().paths().iter().map(|p| p.to_string()).collect()
+}
+
+fn values(json: &JsonValue, path: &str) -> Vec<JsonValue> {
+    json.query(path).unwrap().values().into_iter().cloned().collect()
+}
+
+fn error_kind(path: &str) -> JsonPathErrorKind {
+    JsonPath::parse(path).unwrap_err().kind().clone()
+}
+
+#[test]
+fn store_examples() {
+    let json = STORE.parse::<JsonValue>().unwrap();
+    let authors = ["Nigel Rees", "Evelyn Waugh", "Herman Melville", "J. R. R. Tolkien"].map(JsonValue::from).to_vec();
+    assert_eq!(values(&json, "$.store.book[*].author"), authors);
+    assert_eq!(values(&json, "$..author"), authors);
+    assert_eq!(paths(&json, "$.store.*"), vec!["$['store']['book']", "$['store']['bicycle']"]);
+    assert_eq!(values(&json, "$.store..price"), [8.95, 12.99, 8.99, 22.99, 399.0].map(JsonValue::from).to_vec());
+    assert_eq!(paths(&json, "$..book[2]"), vec!["$['store']['book'][2]"]);
+    assert_eq!(paths(&json, "$..book[-1]"), vec!["$['store']['book'][3]"]);
+    assert_eq!(paths(&json, "$..book[0,1]"), vec!["$['store']['book'][0]", "$['store']['book'][1]"]);
+    assert_eq!(paths(&json, "$..book[:2]"), vec!["$['store']['book'][0]", "$['store']['book'][1]"]);
+    assert_eq!(paths(&json, "$..book[?@.isbn]"), vec!["$['store']['book'][2]", "$['store']['book'][3]"]);
+    assert_eq!(values(&json, "$.store.book[?@.price < 10].title"), vec![JsonValue::from("Sayings of the Century"), JsonValue::from("Moby Dick")]);
+    assert_eq!(json.query("$..*").unwrap().len(), 27);
+    assert!(json.query("$.store.missing").unwrap().is_empty());
+}
+
+#[test]
+fn slices() {
+    let json = r#"["a", "b", "c", "d", "e", "f", "g"]"#.parse::<JsonValue>().unwrap();
+    let letters = |s: &str| s.chars().map(|c| JsonValue::from(c.to_string())).collect::<Vec<_>>();
+    assert_eq!(values(&json, "$[1:3]"), letters("bc"));
+    assert_eq!(values(&json, "$[5:]"), letters("fg"));
+    assert_eq!(values(&json, "$[1:5:2]"), letters("bd"));
+    assert_eq!(values(&json, "$[5:1:-2]"), letters("fd"));
+    assert_eq!(values(&json, "$[::-1]"), letters("gfedcba"));
+    assert_eq!(values(&json, "$[-2:]"), letters("fg"));
+    assert_eq!(values(&json, "$[::0]"), letters(""));
+    assert_eq!(values(&json, "$[ 0 , -1 ]"), letters("ag"));
+}
+
+#[test]
+fn filters() {
+    let json = r#"{"a": [3, 5, 1, 2, 4, 6, {"b": "j"}, {"b": "k"}, {"b": {}}, {"b": "kilo"}],
+                   "o": {"p": 1, "q": 2, "r": 3, "s": 5, "t": {"u": 6}}, "e": "f"}"#.parse::<JsonValue>().unwrap();
+    assert_eq!(paths(&json, "$.a[?@.b == 'kilo']"), vec!["$['a'][9]"]);
+    assert_eq!(values(&json, "$.a[?@>3.5]"), [5.0, 4.0, 6.0].map(JsonValue::from).to_vec());
+    assert_eq!(paths(&json, "$.a[?@.b]").len(), 4);
+    assert_eq!(values(&json, "$.a[?@<2 || @.b == \"k\"]"), vec![JsonValue::from(1.0), json.pointer("/a/7").unwrap().clone()]);
+    assert_eq!(paths(&json, "$.a[?@.b == $.x]").len(), 6);
+    assert_eq!(values(&json, "$.o[?@<3, ?@<3]"), [1.0, 2.0, 1.0, 2.0].map(JsonValue::from).to_vec());
+    assert_eq!(values(&json, "$.o[?!(@ > 1 && @ < 5)]").len(), 3);
+    assert_eq!(paths(&json, "$.a[?@.b == $.a[8].b]"), vec!["$['a'][8]"]);
+    assert_eq!(paths(&json, "$[?@ == $.e]"), vec!["$['e']"]);
+}
+
+#[test]
+fn functions() {
+    let json = r#"[{"a": "ab", "b": [1, 2]}, {"a": "abc", "c": {"d": "red"}}, {"a": "bab"}]"#.parse::<JsonValue>().unwrap();
+    assert_eq!(paths(&json, "$[?length(@.a) == 3]"), vec!["$[1]", "$[2]"]);
+    assert_eq!(paths(&json, "$[?length(@) == 2]"), vec!["$[0]", "$[1]"]);
+    assert_eq!(paths(&json, "$[?count(@.*) == 1]"), vec!["$[2]"]);
+    assert_eq!(paths(&json, "$[?match(@.a, 'a.')]"), vec!["$[0]"]);
+    assert_eq!(paths(&json, "$[?search(@.a, '[b]a')]"), vec!["$[2]"]);
+    assert_eq!(paths(&json, "$[?!match(@.a, 'a.*')]"), vec!["$[2]"]);
+    assert_eq!(paths(&json, "$[?value(@..d) == 'red']"), vec!["$[1]"]);
+    assert_eq!(paths(&json, "$[?length(value(@.b)) == 2]"), vec!["$[0]"]);
+}
+
+#[test]
+fn regex_functions_on_long_input() {
+    let long = "a".repeat(100_000);
+    let json = JsonValue::Array(vec![JsonValue::String(long.clone()), JsonValue::String(format!("{}b", long))]);
+    assert_eq!(paths(&json, "$[?match(@, '(a|aa)*')]"), vec!["$[0]"]);
+    assert_eq!(paths(&json, "$[?search(@, '(a*)*b')]"), vec!["$[1]"]);
+    assert_eq!(paths(&json, "$[?search(@, '^(a+)+$')]"), vec!["$[0]"]);
+}
+
+#[test]
+fn normalized_paths() {
+    let json = r#"{"it's": {"a\\b\n": [true]}}"#.parse::<JsonValue>().unwrap();
+    let nodes = json.query("$..[0]").unwrap();
+    let node = nodes.iter().next().unwrap();
+    assert_eq!(node.path().to_string(), r"$['it\'s']['a\\b\n'][0]");
+    assert_eq!(node.path().to_pointer(), "/it's/a\\b\n/0");
+    assert_eq!(node.value(), &JsonValue::Boolean(true));
+    assert_eq!(paths(&json, r#"$["it's"]['a\\b\u000a']"#), vec![r"$['it\'s']['a\\b\n']"]);
+}
+
+#[test]
+fn invalid_queries() {
+    assert_eq!(error_kind("$[?length(@.*) < 3]"), JsonPathErrorKind::NotWellTyped);
+    assert_eq!(error_kind("$[?length(@.a)]"), JsonPathErrorKind::NotWellTyped);
+    assert_eq!(error_kind("$[?count(1) == 1]"), JsonPathErrorKind::NotWellTyped);
+    assert_eq!(error_kind("$[?match(@.a) == 1]"), JsonPathErrorKind::ArgumentCount("match".to_string()));
+    assert_eq!(error_kind("$[?foo(@)]"), JsonPathErrorKind::UnknownFunction("foo".to_string()));
+    assert_eq!(error_kind("$[?@.* == 1]"), JsonPathErrorKind::NonSingularQuery);
+    assert_eq!(error_kind("$[01]"), JsonPathErrorKind::InvalidNumber);
+    assert_eq!(error_kind("$[-0]"), JsonPathErrorKind::InvalidNumber);
+    assert_eq!(error_kind("$['\\a']"), JsonPathErrorKind::InvalidEscape);
+    assert_eq!(error_kind("$.a "), JsonPathErrorKind::UnexpectedChar(' '));
+    assert_eq!(error_kind("$.."), JsonPathErrorKind::UnexpectedEnd);
+    assert_eq!(error_kind("a"), JsonPathErrorKind::UnexpectedChar('a'));
+    assert_eq!(error_kind("@.a"), JsonPathErrorKind::UnexpectedChar('@'));
+    assert_eq!(error_kind("@"), JsonPathErrorKind::UnexpectedChar('@'));
+    assert_eq!(JsonPath::parse("$.a[?@ == 1").unwrap_err().position(), 11);
+}
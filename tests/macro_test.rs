@@ -0,0 +1,72 @@
+extern crate crystalrake;
+use crystalrake::json;
+use crystalrake::json::*;
+
+#[derive(Clone)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl ToJson for Point {
+    fn to_json(&self) -> JsonValue {
+        json!({ "x": self.x, "y": self.y })
+    }
+}
+
+#[test]
+fn literals() {
+    assert_eq!(json!(null), JsonValue::Null);
+    assert_eq!(json!(true), JsonValue::Boolean(true));
+    assert_eq!(json!(1.5), JsonValue::Number(1.5));
+    assert_eq!(json!(-3), JsonValue::Number(-3.0));
+    assert_eq!(json!("a"), JsonValue::String("a".to_string()));
+    assert_eq!(json!([]), JsonValue::Array(Vec::new()));
+    assert_eq!(json!({}), JsonValue::Objects(Vec::new()));
+}
+
+#[test]
+fn same_as_parsed() {
+    let value = json!([ 12345, true, false, null, "Hello, world", { "object" : {} } ]);
+    let expected = r#" [ 12345, true, false, null, "Hello, world", { "object" : {} } ]"#.parse::<JsonValue>().unwrap();
+    assert_eq!(value, expected);
+
+    let value = json!({
+        "name": "crystalrake",
+        "list": [1, [2, [3]], {"a": null,},],
+        "empty": {},
+    });
+    let expected = r#"{"name": "crystalrake", "list": [1, [2, [3]], {"a": null}], "empty": {}}"#.parse::<JsonValue>().unwrap();
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn interpolation() {
+    let name = String::from("rake");
+    let count = 3u8;
+    let key = "dynamic";
+    let nested = json!([1, 2]);
+    let point = Point { x: 1, y: -1 };
+    let value = json!({
+        "name": name,
+        "count": count,
+        "sum": count as i32 + 1,
+        "flag": count > 2,
+        "missing": None::<i32>,
+        "nested": nested,
+        "point": point,
+        (key): "value",
+        ["a", "b"].concat(): [point, Some("x")],
+    });
+    assert_eq!(value.get("name"), Some(&JsonValue::from("rake")));
+    assert_eq!(value.get("count"), Some(&JsonValue::from(3)));
+    assert_eq!(value.get("sum"), Some(&JsonValue::from(4)));
+    assert_eq!(value.get("flag"), Some(&JsonValue::Boolean(true)));
+    assert_eq!(value.get("missing"), Some(&JsonValue::Null));
+    assert_eq!(value.get("nested"), Some(&nested));
+    assert_eq!(value.pointer("/point/y"), Some(&JsonValue::from(-1)));
+    assert_eq!(value.get("dynamic"), Some(&JsonValue::from("value")));
+    assert_eq!(value.pointer("/ab/0/x"), Some(&JsonValue::from(1)));
+    assert_eq!(value.pointer("/ab/1"), Some(&JsonValue::from("x")));
+    assert_eq!(name, "rake");
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "mmap")]
+extern crate crystalrake;
+use std::borrow::Cow;
+use std::fs;
+use std::path::PathBuf;
+
+use crystalrake::error::{LexErrorKind, ParseErrorKind, ReadErrorKind};
+use crystalrake::ondemand::Document;
+
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("crystalrake-{}-{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn reads_mapped_file() {
+    let path = temp_file("mapped.json", br#"{"skip": [1, 2, 3], "user": {"name": "ferris", "id": 7}}"#);
+    // Safety: the test owns the file and does not modify it while it is mapped.
+    let file = unsafe { Document::open_mmap(&path) }.unwrap();
+    let doc = file.document();
+    let name = doc.get("user").unwrap().get("name").unwrap().as_str().unwrap();
+    match name {
+        Cow::Borrowed(s) => assert!(file.source().as_bytes().as_ptr_range().contains(&s.as_ptr())),
+        Cow::Owned(_) => panic!("expected a string borrowed from the map"),
+    }
+    assert_eq!(doc.get("user").unwrap().get("id").unwrap().as_f64().unwrap(), 7.0);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn open_errors() {
+    let e = unsafe { Document::open_mmap(std::env::temp_dir().join("crystalrake-does-not-exist.json")) }.unwrap_err();
+    assert!(matches!(e.kind(), ReadErrorKind::Io(_)));
+
+    let path = temp_file("invalid.json", b"[\"\xff\"]");
+    let e = unsafe { Document::open_mmap(&path) }.unwrap_err();
+    match e.kind() {
+        ReadErrorKind::Syntax(e) => {
+            assert!(matches!(e.kind(), ParseErrorKind::LexError(l) if matches!(l.kind(), LexErrorKind::InvalidUtf8)));
+            assert_eq!(e.span().start, 2);
+        },
+        kind => panic!("unexpected error {:?}", kind),
+    }
+    fs::remove_file(path).unwrap();
+
+    let path = temp_file("empty.json", b"");
+    assert!(unsafe { Document::open_mmap(&path) }.unwrap().document().root().is_err());
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,105 @@
+extern crate crystalrake;
+use std::borrow::Cow;
+
+use crystalrake::error::{OnDemandErrorKind, ParseErrorKind};
+use crystalrake::json::JsonValue;
+use crystalrake::ondemand::Document;
+
+const SOURCE: &str = r#"{
+    "name": "crystalrake",
+    "versions": [{"tag": "0.1", "yanked": false}, {"tag": "0.2", "notes": null}],
+    "downloads": 1.5e3,
+    "escaped": "tab\there é",
+    "empty": {"a": [], "b": {}}
+}"#;
+
+fn syntax_kind(e: &crystalrake::error::OnDemandError) -> &ParseErrorKind {
+    match e.kind() {
+        OnDemandErrorKind::Syntax(e) => e.kind(),
+        kind => panic!("expected a syntax error, got {:?}", kind),
+    }
+}
+
+#[test]
+fn navigation() {
+    let doc = Document::new(SOURCE);
+    assert_eq!(doc.get("name").unwrap().as_str().unwrap(), "crystalrake");
+    assert_eq!(doc.get("downloads").unwrap().as_f64().unwrap(), 1500.0);
+
+    let versions = doc.get("versions").unwrap();
+    assert_eq!(versions.at(0).unwrap().get("tag").unwrap().as_str().unwrap(), "0.1");
+    assert!(!versions.at(0).unwrap().get("yanked").unwrap().as_bool().unwrap());
+    assert!(versions.at(1).unwrap().get("notes").unwrap().is_null().unwrap());
+    assert!(!versions.at(1).unwrap().get("tag").unwrap().is_null().unwrap());
+    assert_eq!(versions.elements().unwrap().count(), 2);
+
+    let names: Vec<String> = doc.root().unwrap().members().unwrap().map(|m| m.unwrap().0.into_owned()).collect();
+    assert_eq!(names, vec!["name", "versions", "downloads", "escaped", "empty"]);
+    let empty = doc.get("empty").unwrap();
+    assert_eq!(empty.get("a").unwrap().elements().unwrap().count(), 0);
+    assert_eq!(empty.get("b").unwrap().members().unwrap().count(), 0);
+}
+
+#[test]
+fn strings_are_borrowed_without_escapes() {
+    let doc = Document::new(SOURCE);
+    assert!(matches!(doc.get("name").unwrap().as_str().unwrap(), Cow::Borrowed("crystalrake")));
+    let escaped = doc.get("escaped").unwrap().as_str().unwrap();
+    assert!(matches!(escaped, Cow::Owned(_)));
+    assert_eq!(escaped, "tab\there \u{e9}");
+}
+
+#[test]
+fn raw_and_to_value() {
+    let doc = Document::new(SOURCE);
+    let versions = doc.get("versions").unwrap();
+    assert_eq!(versions.raw().unwrap(), r#"[{"tag": "0.1", "yanked": false}, {"tag": "0.2", "notes": null}]"#);
+    assert_eq!(versions.to_value().unwrap(), SOURCE.parse::<JsonValue>().unwrap().get("versions").unwrap().clone());
+    assert_eq!(doc.get("downloads").unwrap().raw().unwrap(), "1.5e3");
+    assert_eq!(doc.root().unwrap().to_value().unwrap(), SOURCE.parse::<JsonValue>().unwrap());
+}
+
+#[test]
+fn lookup_errors() {
+    let doc = Document::new(SOURCE);
+    let e = doc.get("missing").unwrap_err();
+    assert!(matches!(e.kind(), OnDemandErrorKind::MissingField(name) if name == "missing"));
+    assert_eq!(e.to_string(), "missing field 'missing'");
+
+    let e = doc.get("versions").unwrap().at(2).unwrap_err();
+    assert!(matches!(e.kind(), OnDemandErrorKind::IndexOutOfRange(2)));
+
+    let e = doc.get("name").unwrap().as_f64().unwrap_err();
+    assert!(matches!(e.kind(), OnDemandErrorKind::InvalidType { expected: "number", found: "string" }));
+    assert!(doc.at(0).is_err());
+    assert!(matches!(syntax_kind(&Document::new("  ").root().unwrap_err()), ParseErrorKind::NonValue));
+}
+
+#[test]
+fn skipped_values_are_not_validated() {
+    // Only brackets and quotation marks are checked in the values passed over.
+    let doc = Document::new(r#"{"a": [tru, 01, {"x" "y"}], "b": nul, "c": 1}"#);
+    assert_eq!(doc.get("c").unwrap().as_f64().unwrap(), 1.0);
+    assert!(doc.get("b").unwrap().is_null().is_err());
+    assert!(doc.get("a").unwrap().to_value().is_err());
+}
+
+#[test]
+fn malformed_structure() {
+    let e = Document::new(r#"{"a": [1, 2}, "b": 1}"#).get("b").unwrap_err();
+    assert!(matches!(syntax_kind(&e), ParseErrorKind::InvalidToken));
+    assert!(matches!(syntax_kind(&Document::new(r#"{"a": [1, {}"#).get("b").unwrap_err()), ParseErrorKind::NonEndArray));
+    assert!(matches!(syntax_kind(&Document::new(r#"{"a": 1,}"#).get("b").unwrap_err()), ParseErrorKind::TrailingComma));
+    assert!(matches!(syntax_kind(&Document::new(r#"{"a" 1}"#).get("a").unwrap_err()), ParseErrorKind::InvalidToken));
+    assert!(matches!(syntax_kind(&Document::new(r#"{1: 2}"#).get("a").unwrap_err()), ParseErrorKind::NoObjectName));
+    assert!(matches!(syntax_kind(&Document::new(r#"[1 2]"#).at(1).unwrap_err()), ParseErrorKind::InvalidToken));
+    assert!(matches!(syntax_kind(&Document::new(r#"[1, 2"#).at(2).unwrap_err()), ParseErrorKind::NonEndArray));
+    assert!(matches!(syntax_kind(&Document::new("[truex]").at(0).unwrap().as_bool().unwrap_err()), ParseErrorKind::InvalidToken));
+    assert!(Document::new(r#""\x""#).root().unwrap().as_str().is_err());
+
+    // The iterator stops after the first error.
+    let doc = Document::new("[1, 2 3, 4]");
+    let elements: Vec<_> = doc.root().unwrap().elements().unwrap().collect();
+    assert_eq!(elements.len(), 3);
+    assert!(elements[2].is_err());
+}
@@ -32,6 +32,8 @@ fn false_string() {
     assert_eq!(t, JsonValue::String("false".to_string()));
 }
 
+// With `arbitrary_precision` numbers parse to `JsonValue::Decimal` instead.
+#[cfg(not(feature = "arbitrary_precision"))]
 #[test]
 fn number_value() {
     let json_value = "1234567890.0987654321".parse::<JsonValue>();
@@ -126,4 +128,59 @@ fn contain_emoji() {
     } else {
         panic!("unexpect value : {:?}", json_value);
     }
-}
\ No newline at end of file
+}
+
+fn lex_error_kind(json: &str) -> crystalrake::error::LexErrorKind {
+    use crystalrake::error::ParseErrorKind;
+    match json.parse::<JsonValue>() {
+        Err(e) => match e.kind() {
+            ParseErrorKind::LexError(e) => e.kind().clone(),
+            kind => panic!("unexpect error : {:?}", kind),
+        },
+        Ok(v) => panic!("unexpect value : {:?}", v),
+    }
+}
+
+#[test]
+fn number_grammar() {
+    use crystalrake::error::LexErrorKind;
+    assert!(matches!(lex_error_kind("01"), LexErrorKind::LeadingZero));
+    assert!(matches!(lex_error_kind("-01"), LexErrorKind::LeadingZero));
+    assert!(matches!(lex_error_kind("1e"), LexErrorKind::EmptyExponent));
+    assert!(matches!(lex_error_kind("1E+"), LexErrorKind::EmptyExponent));
+    assert!(matches!(lex_error_kind("1.e1"), LexErrorKind::NonFracDigit));
+    assert!(matches!(lex_error_kind("[2.]"), LexErrorKind::NonFracDigit));
+    assert!(matches!(lex_error_kind("-"), LexErrorKind::NotDigit));
+    assert!(matches!(lex_error_kind("[-]"), LexErrorKind::NotDigit));
+
+    assert_eq!("0".parse::<JsonValue>().unwrap(), JsonValue::Number(0.0));
+    assert_eq!("-0.5e-2".parse::<JsonValue>().unwrap(), JsonValue::Number(-0.005));
+    assert_eq!("[10E+2,0e1]".parse::<JsonValue>().unwrap(), JsonValue::Array(vec![JsonValue::Number(1000.0), JsonValue::Number(0.0)]));
+}
+
+#[test]
+fn display_round_trip() {
+    let json = r#"{"a\"b":["tab\t","\u0001\\/",-1.5,null],"c":{}}"#;
+    let value = json.parse::<JsonValue>().unwrap();
+    let written = value.to_string();
+    assert_eq!(written, r#"{"a\"b":["tab\t","\u0001\\/",-1.5,null],"c":{}}"#);
+    assert_eq!(written.parse::<JsonValue>().unwrap(), value);
+    assert_eq!(JsonValue::Number(f64::NAN).to_string(), "null");
+}
+
+#[test]
+fn pretty_print() {
+    let value: JsonValue = r#"{"a":[1,[],{}],"b":{"c":"d"}}"#.parse().unwrap();
+    assert_eq!(crystalrake::writer::to_string_pretty(&value, "  "), "\
+{
+  \"a\": [
+    1,
+    [],
+    {}
+  ],
+  \"b\": {
+    \"c\": \"d\"
+  }
+}");
+    assert_eq!(crystalrake::writer::to_string_pretty(&JsonValue::Null, "\t"), "null");
+}
@@ -0,0 +1,21 @@
+extern crate crystalrake;
+use crystalrake::json::*;
+use crystalrake::pointer;
+
+#[test]
+fn lookup_by_pointer() {
+    let json_value = r#"{"a/b": [0, {"m~n": true}], "": null}"#.parse::<JsonValue>().unwrap();
+    assert_eq!(json_value.pointer(""), Some(&json_value));
+    assert_eq!(json_value.pointer("/a~1b/1/m~0n"), Some(&JsonValue::Boolean(true)));
+    assert_eq!(json_value.pointer("/"), Some(&JsonValue::Null));
+    assert_eq!(json_value.pointer("/a~1b/01"), None);
+    assert_eq!(json_value.pointer("/a~1b/2"), None);
+    assert_eq!(json_value.pointer("a"), None);
+}
+
+#[test]
+fn escape_tokens() {
+    assert_eq!(pointer::join(["a/b", "m~n", "0"]), "/a~1b/m~0n/0");
+    assert_eq!(pointer::parse("/a~1b/m~0n/0").unwrap(), vec!["a/b", "m~n", "0"]);
+    assert!(pointer::parse("/a~2").is_err());
+}
@@ -0,0 +1,130 @@
+extern crate crystalrake;
+use crystalrake::error::{LexErrorKind, ParseErrorKind};
+use crystalrake::json::JsonValue;
+use crystalrake::push::{Event, PushParser};
+use crystalrake::span::Span;
+
+const DOCUMENT: &str = "{\"name\": \"caf\u{e9} \\ud83d\\ude00\", \"n\": [-0.5e+2, 10, true, null, false], \"o\": {\"\\u00e9\": []}}";
+
+fn parse_chunks(chunks: &[&[u8]]) -> Vec<JsonValue> {
+    let mut parser = PushParser::new();
+    let mut values = Vec::new();
+    for chunk in chunks {
+        parser.feed(chunk).unwrap();
+        values.extend(std::iter::from_fn(|| parser.next_value()));
+    }
+    parser.finish().unwrap();
+    values.extend(std::iter::from_fn(|| parser.next_value()));
+    values
+}
+
+/// The event for an integer, which is exact with the `arbitrary_precision` feature.
+fn number(n: i64) -> Event {
+    #[cfg(feature = "arbitrary_precision")]
+    return Event::Decimal(n.into());
+    #[cfg(not(feature = "arbitrary_precision"))]
+    return Event::Number(n as f64);
+}
+
+#[test]
+fn any_split() {
+    let expected: JsonValue = DOCUMENT.parse().unwrap();
+    let bytes = DOCUMENT.as_bytes();
+    for i in 0..=bytes.len() {
+        assert_eq!(parse_chunks(&[&bytes[..i], &bytes[i..]]), vec![expected.clone()], "split at {}", i);
+    }
+    let single: Vec<&[u8]> = bytes.chunks(1).collect();
+    assert_eq!(parse_chunks(&single), vec![expected]);
+}
+
+#[test]
+fn events_as_soon_as_available() {
+    let mut parser = PushParser::new();
+    parser.feed(b"[12, \"ab").unwrap();
+    assert_eq!(parser.next_event(), Some(Event::BeginArray));
+    assert_eq!(parser.next_event(), Some(number(12)));
+    assert_eq!(parser.next_event(), None);
+    parser.feed(b"c\", {\"k\"").unwrap();
+    assert_eq!(parser.next_event(), Some(Event::String("abc".to_string())));
+    assert_eq!(parser.next_event(), Some(Event::BeginObject));
+    assert_eq!(parser.next_event(), Some(Event::Name("k".to_string())));
+    parser.feed(b": 3").unwrap();
+    // The number may continue in the next chunk.
+    assert_eq!(parser.next_event(), None);
+    parser.feed(b"4}]").unwrap();
+    assert_eq!(parser.next_event(), Some(number(34)));
+    assert_eq!(parser.next_event(), Some(Event::EndObject));
+    assert_eq!(parser.next_event(), Some(Event::EndArray));
+    parser.finish().unwrap();
+}
+
+#[test]
+fn value_sequence() {
+    let values = parse_chunks(&[b"{\"a\": 1}\n[2", b"]\n3", b"\n\"x\""]);
+    assert_eq!(values, vec![
+        JsonValue::Objects(vec![crystalrake::json::JsonObject::new("a", 1.0)]),
+        JsonValue::Array(vec![JsonValue::Number(2.0)]),
+        JsonValue::Number(3.0),
+        JsonValue::new("x"),
+    ]);
+}
+
+fn error(chunks: &[&[u8]]) -> crystalrake::error::JsonParseError {
+    let mut parser = PushParser::new();
+    for chunk in chunks {
+        if let Err(e) = parser.feed(chunk) {
+            return e;
+        }
+    }
+    parser.finish().unwrap_err()
+}
+
+#[test]
+fn errors() {
+    let e = error(&[b"[1,", b"]"]);
+    assert!(matches!(e.kind(), ParseErrorKind::TrailingComma));
+    assert_eq!(e.span(), Span::new(3, 4));
+
+    let e = error(&[b"[1 2]"]);
+    assert!(matches!(e.kind(), ParseErrorKind::InvalidToken));
+    assert_eq!(e.expected(), &["`,`", "`]`"]);
+
+    let e = error(&[b"{\"a\": [01]}"]);
+    assert!(matches!(e.kind(), ParseErrorKind::LexError(l) if matches!(l.kind(), LexErrorKind::LeadingZero)));
+    assert_eq!(e.span(), Span::new(7, 9));
+
+    let e = error(&[b"[\"a\\", b"x\"]"]);
+    assert!(matches!(e.kind(), ParseErrorKind::LexError(l) if matches!(l.kind(), LexErrorKind::NotEscapeChar)));
+    assert_eq!(e.span(), Span::new(3, 5));
+
+    let e = error(&[b"\"\xc3", b"\x28\""]);
+    assert!(matches!(e.kind(), ParseErrorKind::LexError(l) if matches!(l.kind(), LexErrorKind::InvalidUtf8)));
+    assert_eq!(e.span(), Span::new(1, 2));
+
+    let e = error(&[b"\"\\ud83d\""]);
+    assert!(matches!(e.kind(), ParseErrorKind::FromUtf16Error(_)));
+
+    assert!(matches!(error(&[b"{\"a\": [tr", b"ue"]).kind(), ParseErrorKind::NonEndArray));
+    assert!(matches!(error(&[b"  "]).kind(), ParseErrorKind::NonValue));
+    assert!(matches!(error(&[b"[1.", b"]"]).kind(), ParseErrorKind::LexError(_)));
+    assert!(matches!(error(&[b"\"abc"]).kind(), ParseErrorKind::LexError(l) if matches!(l.kind(), LexErrorKind::NonQuotationMark)));
+
+    // Input after an error is ignored.
+    let mut parser = PushParser::new();
+    assert!(parser.feed(b"]").is_err());
+    assert!(parser.feed(b"[]").is_ok());
+    assert_eq!(parser.next_event(), None);
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn exact_numbers() {
+    let source = b"[12345678901234567890.123456789, 9007199254740993]";
+    let value = parse_chunks(&[&source[..20], &source[20..]]).remove(0);
+    assert_eq!(value.to_string(), "[12345678901234567890.123456789,9007199254740993]");
+
+    let mut parser = PushParser::new();
+    parser.feed(b"1.50 ").unwrap();
+    assert!(matches!(parser.next_event(), Some(Event::Decimal(d)) if d.to_string() == "1.50"));
+    assert!(matches!(parser.feed(b"1e100001 ").unwrap_err().kind(), ParseErrorKind::NumberOutOfRange));
+}
@@ -0,0 +1,88 @@
+extern crate crystalrake;
+use crystalrake::error::{LexErrorKind, ParseErrorKind};
+use crystalrake::json;
+use crystalrake::json::*;
+use crystalrake::span::Span;
+
+fn kinds(recovery: &ParseRecovery) -> Vec<String> {
+    recovery.errors.iter()
+        .map(|e| match e.kind() {
+            ParseErrorKind::LexError(e) => format!("{:?}", e.kind()),
+            kind => format!("{:?}", kind),
+        })
+        .collect()
+}
+
+#[test]
+fn error_spans() {
+    let e = "[1, 2,]".parse::<JsonValue>().unwrap_err();
+    assert!(matches!(e.kind(), ParseErrorKind::TrailingComma));
+    assert_eq!(e.span(), Span::new(5, 6));
+
+    let e = "{\"a\": tru}".parse::<JsonValue>().unwrap_err();
+    assert!(matches!(e.kind(), ParseErrorKind::LexError(_)));
+    assert_eq!(e.span(), Span::new(6, 7));
+
+    let e = "[\"é\\x\"]".parse::<JsonValue>().unwrap_err();
+    match e.kind() {
+        ParseErrorKind::LexError(e) => assert!(matches!(e.kind(), LexErrorKind::NotEscapeChar)),
+        kind => panic!("unexpected error {:?}", kind),
+    }
+    assert_eq!(e.span(), Span::new(4, 6));
+
+    let e = "[1".parse::<JsonValue>().unwrap_err();
+    assert!(matches!(e.kind(), ParseErrorKind::NonEndArray));
+    assert_eq!(e.span(), Span::new(2, 2));
+}
+
+#[test]
+fn recovers_all_errors() {
+    let source = r#"{
+        "a": [1, 2,, 3],
+        "b": tru,
+        "c": {"d": 01 "e": true},
+        "f": "ok",
+    }"#;
+    let recovery = JsonValue::parse_tolerant(source);
+    assert_eq!(kinds(&recovery), vec![
+        "InvalidToken",
+        "InvalidChar('t')",
+        "LeadingZero",
+        "InvalidToken",
+        "TrailingComma",
+    ]);
+    assert_eq!(recovery.value, json!({
+        "a": [1, 2, null, 3],
+        "b": null,
+        "c": { "d": null, "e": true },
+        "f": "ok"
+    }));
+    assert_eq!(recovery.placeholders, vec!["/a/2", "/b", "/c/d"]);
+    let spans: Vec<&str> = recovery.errors.iter().map(|e| &source[e.span().start..e.span().end]).collect();
+    assert_eq!(spans, vec![",", "t", "01", "\"e\"", ","]);
+}
+
+#[test]
+fn recovers_brackets() {
+    let recovery = JsonValue::parse_tolerant(r#"{"a": [1, 2}"#);
+    assert_eq!(kinds(&recovery), vec!["NonEndArray"]);
+    assert_eq!(recovery.value, json!({ "a": [1, 2] }));
+
+    let recovery = JsonValue::parse_tolerant(r#"[{"a": 1]] ["#);
+    assert_eq!(kinds(&recovery), vec!["NonEndObject", "InvalidToken"]);
+    assert_eq!(recovery.value, json!([{ "a": 1 }]));
+
+    let recovery = JsonValue::parse_tolerant(r#"{"a": [1, {"b": "unterminated
+    }"#);
+    assert_eq!(recovery.value, json!({ "a": [1, { "b": null }] }));
+    assert_eq!(recovery.placeholders, vec!["/a/1/b"]);
+    assert_eq!(kinds(&recovery).len(), 3);
+
+    let recovery = JsonValue::parse_tolerant("[1, 2]");
+    assert!(recovery.errors.is_empty() && recovery.placeholders.is_empty());
+    assert_eq!(recovery.value, json!([1, 2]));
+
+    let recovery = JsonValue::parse_tolerant("");
+    assert_eq!(kinds(&recovery), vec!["NonValue"]);
+    assert_eq!(recovery.placeholders, vec![""]);
+}
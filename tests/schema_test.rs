@@ -0,0 +1,169 @@
+extern crate crystalrake;
+use crystalrake::json::*;
+use crystalrake::schema::*;
+
+fn compile(schema: &str) -> Schema {
+    Schema::compile(&schema.parse::<JsonValue>().unwrap()).unwrap()
+}
+
+fn errors(schema: &Schema, instance: &str) -> Vec<(String, String)> {
+    match schema.validate(&instance.parse::<JsonValue>().unwrap()) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.iter().map(|e| (e.instance_path().to_string(), e.schema_path().to_string())).collect(),
+    }
+}
+
+fn pair(instance: &str, schema: &str) -> (String, String) {
+    (instance.to_string(), schema.to_string())
+}
+
+#[test]
+fn type_keyword() {
+    let schema = compile(r#"{"type": ["integer", "null"]}"#);
+    assert!(schema.is_valid(&"3".parse().unwrap()));
+    assert!(schema.is_valid(&"3.0".parse().unwrap()));
+    assert!(schema.is_valid(&"null".parse().unwrap()));
+    assert!(!schema.is_valid(&"3.5".parse().unwrap()));
+    let result = schema.validate(&"\"x\"".parse().unwrap()).unwrap_err();
+    assert_eq!(result[0].kind(), &ValidationErrorKind::Type { expected: vec!["integer", "null"], found: "string" });
+}
+
+#[test]
+fn boolean_schema() {
+    assert!(compile("true").is_valid(&"[1]".parse().unwrap()));
+    assert!(!compile("false").is_valid(&"null".parse().unwrap()));
+}
+
+#[test]
+fn report_every_failure() {
+    let schema = compile(r#"{
+        "type": "object",
+        "required": ["id", "name"],
+        "properties": {
+            "id": {"type": "integer", "minimum": 1},
+            "tags": {"type": "array", "items": {"type": "string", "maxLength": 3}}
+        },
+        "additionalProperties": false
+    }"#);
+    let errors = errors(&schema, r#"{"id": 0, "tags": ["abc", "abcd", 5], "extra": true}"#);
+    assert_eq!(errors, vec![
+        pair("", "/required"),
+        pair("/id", "/properties/id/minimum"),
+        pair("/tags/1", "/properties/tags/items/maxLength"),
+        pair("/tags/2", "/properties/tags/items/type"),
+        pair("/extra", "/additionalProperties"),
+    ]);
+}
+
+#[test]
+fn prefix_items() {
+    let schema = compile(r#"{"prefixItems": [{"type": "number"}, {"type": "string"}], "items": false, "minItems": 1}"#);
+    assert!(schema.is_valid(&r#"[1, "a"]"#.parse().unwrap()));
+    assert!(!schema.is_valid(&"[]".parse().unwrap()));
+    assert_eq!(errors(&schema, r#"["a", "b", 3]"#), vec![pair("/0", "/prefixItems/0/type"), pair("/2", "/items")]);
+}
+
+#[test]
+fn enum_and_const() {
+    let schema = compile(r#"{"enum": [1, "two", {"a": [true]}]}"#);
+    assert!(schema.is_valid(&"1.0".parse().unwrap()));
+    assert!(schema.is_valid(&r#"{"a": [true]}"#.parse().unwrap()));
+    assert!(!schema.is_valid(&"2".parse().unwrap()));
+    let schema = compile(r#"{"const": {"a": 1, "b": 2}}"#);
+    assert!(schema.is_valid(&r#"{"b": 2, "a": 1}"#.parse().unwrap()));
+    assert!(!schema.is_valid(&r#"{"a": 1}"#.parse().unwrap()));
+}
+
+#[test]
+fn numeric_and_string_bounds() {
+    let schema = compile(r#"{"exclusiveMinimum": 0, "maximum": 10, "multipleOf": 0.5}"#);
+    assert!(schema.is_valid(&"10".parse().unwrap()));
+    assert!(schema.is_valid(&"0.5".parse().unwrap()));
+    assert!(!schema.is_valid(&"0".parse().unwrap()));
+    assert!(!schema.is_valid(&"10.5".parse().unwrap()));
+    assert!(!schema.is_valid(&"0.75".parse().unwrap()));
+    assert!(compile(r#"{"multipleOf": 0.0001}"#).is_valid(&"0.0075".parse().unwrap()));
+
+    let schema = compile(r#"{"minLength": 2, "maxLength": 3}"#);
+    assert!(schema.is_valid(&r#""あい""#.parse().unwrap()));
+    assert!(!schema.is_valid(&r#""😄""#.parse().unwrap()));
+    assert!(!schema.is_valid(&r#""abcd""#.parse().unwrap()));
+}
+
+#[test]
+fn pattern() {
+    let schema = compile(r#"{"pattern": "^[a-z][a-z0-9_-]{2,15}$"}"#);
+    assert!(schema.is_valid(&r#""user_01""#.parse().unwrap()));
+    assert!(!schema.is_valid(&r#""1user""#.parse().unwrap()));
+    assert!(!schema.is_valid(&r#""ab""#.parse().unwrap()));
+    let schema = compile(r#"{"pattern": "(?:\\d{3}-)?\\d{4}|x+?y"}"#);
+    assert!(schema.is_valid(&r#""call 555-1234""#.parse().unwrap()));
+    assert!(schema.is_valid(&r#""xxy""#.parse().unwrap()));
+    assert!(!schema.is_valid(&r#""12-34""#.parse().unwrap()));
+    assert!(compile(r#"{"pattern": "a"}"#).is_valid(&"12".parse().unwrap()));
+
+    let error = Schema::compile(&r#"{"properties": {"a": {"pattern": "(a"}}}"#.parse().unwrap()).unwrap_err();
+    assert_eq!(error.pointer(), "/properties/a/pattern");
+}
+
+#[test]
+fn pattern_on_long_input() {
+    let long = "a".repeat(100_000);
+    let schema = compile(r#"{"pattern": "^[a-z]+$"}"#);
+    assert!(schema.is_valid(&JsonValue::String(long.clone())));
+    assert!(!schema.is_valid(&JsonValue::String(format!("{}1", long))));
+
+    // Nested quantifiers must not backtrack exponentially.
+    let schema = compile(r#"{"pattern": "^(a+)+$"}"#);
+    assert!(schema.is_valid(&JsonValue::String(long.clone())));
+    assert!(!schema.is_valid(&JsonValue::String(format!("{}!", long))));
+    assert!(!schema.is_valid(&JsonValue::String(format!("{}!", &long[..30]))));
+
+    let error = Schema::compile(&r#"{"pattern": "(a{1000}){1000}"}"#.parse().unwrap()).unwrap_err();
+    assert_eq!(error.pointer(), "/pattern");
+}
+
+#[test]
+fn combinators() {
+    let schema = compile(r#"{
+        "allOf": [{"type": "number"}, {"minimum": 0}],
+        "anyOf": [{"maximum": 5}, {"multipleOf": 10}],
+        "oneOf": [{"multipleOf": 2}, {"multipleOf": 3}],
+        "not": {"const": 4}
+    }"#);
+    assert!(schema.is_valid(&"2".parse().unwrap()));
+    assert!(schema.is_valid(&"3".parse().unwrap()));
+    assert_eq!(errors(&schema, "-1"), vec![pair("", "/allOf/1/minimum"), pair("", "/oneOf")]);
+    assert_eq!(errors(&schema, "4"), vec![pair("", "/not")]);
+    assert_eq!(errors(&schema, "7"), vec![pair("", "/anyOf"), pair("", "/oneOf")]);
+    let result = schema.validate(&"6".parse().unwrap()).unwrap_err();
+    assert_eq!(result.iter().map(|e| e.kind().clone()).collect::<Vec<_>>(), vec![ValidationErrorKind::AnyOf, ValidationErrorKind::OneOf(2)]);
+}
+
+#[test]
+fn references() {
+    let schema = compile(r##"{
+        "$defs": {
+            "node": {
+                "type": "object",
+                "properties": {
+                    "value": {"type": "integer"},
+                    "children": {"type": "array", "items": {"$ref": "#/$defs/node"}}
+                },
+                "required": ["value"]
+            }
+        },
+        "$ref": "#/$defs/node"
+    }"##);
+    assert!(schema.is_valid(&r##"{"value": 1, "children": [{"value": 2, "children": []}]}"##.parse().unwrap()));
+    assert_eq!(
+        errors(&schema, r##"{"value": 1, "children": [{"value": 2, "children": [{"value": "3"}]}]}"##),
+        vec![pair("/children/0/children/0/value", "/$ref/properties/children/items/$ref/properties/children/items/$ref/properties/value/type")]);
+
+    let schema = compile(r##"{"properties": {"a/b": {"type": "string"}, "c": {"$ref": "#/properties/a~1b"}}}"##);
+    assert_eq!(errors(&schema, r##"{"c": 1}"##), vec![pair("/c", "/properties/c/$ref/type")]);
+    assert!(compile(r##"{"$ref": "#"}"##).is_valid(&"1".parse().unwrap()));
+
+    let error = Schema::compile(&r##"{"$ref": "#/$defs/missing"}"##.parse().unwrap()).unwrap_err();
+    assert!(matches!(error.kind(), crystalrake::error::SchemaErrorKind::UnresolvedRef(_)));
+}
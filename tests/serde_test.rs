@@ -0,0 +1,130 @@
+#![cfg(feature = "serde")]
+extern crate crystalrake;
+use std::collections::BTreeMap;
+
+use crystalrake::error::{ParseErrorKind, SerdeErrorKind};
+use crystalrake::json;
+use crystalrake::json::*;
+use crystalrake::writer::WriteOptions;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct User {
+    name: String,
+    age: u32,
+    email: Option<String>,
+    tags: Vec<String>,
+    role: Role,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Role {
+    Guest,
+    Member(u64),
+    Admin { level: i8 },
+}
+
+#[test]
+fn derive_round_trip() {
+    let text = r#"{ "name": "Taro \"T\"", "age": 42, "email": null, "tags": ["a", "b\n"], "role": {"Admin": {"level": -3}} }"#;
+    let user: User = crystalrake::serde::from_str(text).unwrap();
+    assert_eq!(user, User {
+        name: "Taro \"T\"".to_string(),
+        age: 42,
+        email: None,
+        tags: vec!["a".to_string(), "b\n".to_string()],
+        role: Role::Admin { level: -3 },
+    });
+
+    let written = crystalrake::serde::to_string(&user).unwrap();
+    assert_eq!(written, r#"{"name":"Taro \"T\"","age":42,"email":null,"tags":["a","b\n"],"role":{"Admin":{"level":-3}}}"#);
+    assert_eq!(crystalrake::serde::from_str::<User>(&written).unwrap(), user);
+
+    assert_eq!(crystalrake::serde::to_string(&Role::Guest).unwrap(), r#""Guest""#);
+    assert_eq!(crystalrake::serde::from_str::<Role>(r#"{"Member":7}"#).unwrap(), Role::Member(7));
+}
+
+#[test]
+fn maps_and_numbers() {
+    let map: BTreeMap<u32, f64> = crystalrake::serde::from_str(r#"{"1": 1.5, "2": -2e3}"#).unwrap();
+    assert_eq!(map[&1], 1.5);
+    assert_eq!(map[&2], -2000.0);
+    assert_eq!(crystalrake::serde::to_string(&map).unwrap(), r#"{"1":1.5,"2":-2000}"#);
+
+    let big: u64 = crystalrake::serde::from_str("18446744073709551615").unwrap();
+    assert_eq!(big, u64::MAX);
+    let neg: i64 = crystalrake::serde::from_str("-9223372036854775808").unwrap();
+    assert_eq!(neg, i64::MIN);
+    assert!(crystalrake::serde::from_str::<u8>("256").is_err());
+
+    let mut bad = BTreeMap::new();
+    bad.insert(vec![1], 1);
+    let err = crystalrake::serde::to_string(&bad).unwrap_err();
+    assert!(matches!(err.kind(), SerdeErrorKind::KeyMustBeAString));
+}
+
+#[test]
+fn write_options() {
+    let mut map = BTreeMap::new();
+    map.insert("ü<", vec![1.0, 0.5]);
+    let options = WriteOptions::new().ascii(true).html_safe(true).float_fraction(true);
+    assert_eq!(crystalrake::serde::to_string_with(&map, options).unwrap(), r#"{"\u00fc\u003c":[1.0,0.5]}"#);
+}
+
+#[test]
+fn json_value() {
+    let value = json!({ "a": [1, "x", null, true], "b": { "c": 0.5 } });
+    let text = crystalrake::serde::to_string(&value).unwrap();
+    assert_eq!(text, r#"{"a":[1,"x",null,true],"b":{"c":0.5}}"#);
+    assert_eq!(text, value.to_string());
+    assert_eq!(crystalrake::serde::from_str::<JsonValue>(&text).unwrap(), value);
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn exact_numbers() {
+    let text = r#"{"amount":12345678901234567890.123456789,"big":123456789012345678901234,"n":-7,"scaled":1.50,"e":12e3}"#;
+    let value: JsonValue = text.parse().unwrap();
+    let written = crystalrake::serde::to_string(&value).unwrap();
+    assert_eq!(written, text);
+    assert_eq!(written.parse::<JsonValue>().unwrap(), value);
+
+    assert_eq!(serde_json::to_string(&value).unwrap(), text);
+    let from_serde_json: JsonValue = serde_json::from_str(text).unwrap();
+    assert_eq!(crystalrake::serde::to_string(&from_serde_json).unwrap(), text);
+    let text = "[12345678901234567890.123456789,1.50,9007199254740993]";
+    let serde_json_value: serde_json::Value = serde_json::from_str(text).unwrap();
+    assert_eq!(crystalrake::serde::to_string(&serde_json_value).unwrap(), text);
+
+    let text = "[12345678901234567890.123456789, 9007199254740993]";
+    let exact = "[12345678901234567890.123456789,9007199254740993]";
+    assert_eq!(crystalrake::serde::from_str::<JsonValue>(text).unwrap().to_string(), exact);
+    assert_eq!(crystalrake::serde::from_str::<serde_json::Value>(text).unwrap().to_string(), exact);
+    assert_eq!(crystalrake::serde::from_str::<(f64, u64)>(text).unwrap(), (12345678901234567890.123456789, 9007199254740993));
+
+    #[derive(Serialize)]
+    #[serde(rename = "$serde_json::private::Number")]
+    struct Raw {
+        #[serde(rename = "$serde_json::private::Number")]
+        text: &'static str,
+    }
+    assert_eq!(crystalrake::serde::to_string(&Raw { text: "-1.5e3" }).unwrap(), "-1.5e3");
+    assert!(crystalrake::serde::to_string(&Raw { text: "1." }).is_err());
+}
+
+#[test]
+fn syntax_errors() {
+    let syntax_kind = |text: &str| match crystalrake::serde::from_str::<JsonValue>(text).unwrap_err().kind() {
+        SerdeErrorKind::Syntax(e) => format!("{:?}", e.kind()),
+        kind => panic!("unexpected error {:?}", kind),
+    };
+    assert_eq!(syntax_kind("[1,]"), format!("{:?}", ParseErrorKind::InvalidToken));
+    assert_eq!(syntax_kind("[1 2]"), format!("{:?}", ParseErrorKind::InvalidToken));
+    assert_eq!(syntax_kind(r#"{"a":1"#), format!("{:?}", ParseErrorKind::NonEndObject));
+    assert_eq!(syntax_kind("1 2"), format!("{:?}", ParseErrorKind::InvalidToken));
+    assert_eq!(syntax_kind(""), format!("{:?}", ParseErrorKind::NonValue));
+    assert_eq!(syntax_kind(&"[".repeat(1000)), format!("{:?}", ParseErrorKind::RecursionLimitExceeded));
+
+    let err = crystalrake::serde::from_str::<User>(r#"{"name": "x"}"#).unwrap_err();
+    assert!(matches!(err.kind(), SerdeErrorKind::Message(_)));
+}
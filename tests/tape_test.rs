@@ -0,0 +1,61 @@
+extern crate crystalrake;
+use crystalrake::error::ParseErrorKind;
+use crystalrake::json::JsonValue;
+use crystalrake::tape::Document;
+
+const SOURCE: &str = r#"{
+    "name": "crystalrake",
+    "versions": [{"tag": "0.1", "yanked": false}, {"tag": "0.2", "notes": null}],
+    "downloads": 1.5e3,
+    "empty": {"a": [], "b": {}}
+}"#;
+
+#[test]
+fn navigation() {
+    let document = Document::parse(SOURCE).unwrap();
+    let root = document.root();
+    assert!(root.is_object());
+    assert_eq!(root.get("name").and_then(|n| n.as_str()), Some("crystalrake"));
+    assert_eq!(root.get("downloads").and_then(|n| n.as_f64()), Some(1500.0));
+    assert!(root.get("missing").is_none());
+
+    let versions = root.get("versions").unwrap();
+    assert_eq!(versions.as_array().unwrap().len(), 2);
+    let tags: Vec<&str> = versions.as_array().unwrap().map(|v| v.get("tag").unwrap().as_str().unwrap()).collect();
+    assert_eq!(tags, vec!["0.1", "0.2"]);
+    assert_eq!(versions.at(0).unwrap().get("yanked").unwrap().as_bool(), Some(false));
+    assert!(versions.at(1).unwrap().get("notes").unwrap().is_null());
+    assert!(versions.at(2).is_none());
+
+    let names: Vec<&str> = root.as_object().unwrap().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["name", "versions", "downloads", "empty"]);
+    assert_eq!(root.pointer("/versions/1/tag").unwrap().as_str(), Some("0.2"));
+    assert!(root.pointer("/empty/a").unwrap().is_array());
+    assert_eq!(root.pointer("/empty/b").unwrap().as_object().unwrap().len(), 0);
+    assert!(root.pointer("/versions/x").is_none());
+    assert!(root.get("name").unwrap().as_array().is_none());
+}
+
+#[test]
+fn to_value() {
+    let document = Document::parse(SOURCE).unwrap();
+    assert_eq!(document.to_value(), SOURCE.parse::<JsonValue>().unwrap());
+    assert_eq!(document.root().pointer("/versions/0").unwrap().to_value(), SOURCE.parse::<JsonValue>().unwrap().pointer("/versions/0").unwrap().clone());
+    assert_eq!(Document::parse(" \"\\u00e9\" ").unwrap().root().as_str(), Some("\u{e9}"));
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(Document::parse("[1, 2").unwrap_err().kind(), ParseErrorKind::NonEndArray));
+    assert!(matches!(Document::parse("{} []").unwrap_err().kind(), ParseErrorKind::InvalidToken));
+    assert!(matches!(Document::parse("").unwrap_err().kind(), ParseErrorKind::NonValue));
+    assert!(Document::from_slice(b"\"\xff\"").is_err());
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn exact_numbers() {
+    let document = Document::parse("[12345678901234567890.123456789, 9007199254740993]").unwrap();
+    assert_eq!(document.to_value().to_string(), "[12345678901234567890.123456789,9007199254740993]");
+    assert_eq!(document.root().at(1).and_then(|n| n.as_f64()), Some(9007199254740992.0));
+}
@@ -0,0 +1,54 @@
+extern crate crystalrake;
+use crystalrake::span::Span;
+use crystalrake::tokenizer::*;
+
+#[test]
+fn tokens_with_spans() {
+    let source = "{ \"a\\n\": [1.50e1, true],\n\t\"é\": null }";
+    let tokens = tokenize(source).unwrap();
+    let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind).collect();
+    assert_eq!(kinds, vec![
+        TokenKind::BeginObject, TokenKind::Whitespace, TokenKind::String, TokenKind::NameSeparator,
+        TokenKind::Whitespace, TokenKind::BeginArray, TokenKind::Number, TokenKind::ValueSeparator,
+        TokenKind::Whitespace, TokenKind::True, TokenKind::EndArray, TokenKind::ValueSeparator,
+        TokenKind::Whitespace, TokenKind::String, TokenKind::NameSeparator, TokenKind::Whitespace,
+        TokenKind::Null, TokenKind::Whitespace, TokenKind::EndObject,
+    ]);
+    assert_eq!(tokens[2].text, "\"a\\n\"");
+    assert_eq!(tokens[6].text, "1.50e1");
+    assert_eq!(tokens[6].span, Span::new(10, 16));
+    assert_eq!(tokens[12].text, "\n\t");
+    assert_eq!(tokens[13].span, Span::new(26, 30));
+
+    let round_trip: String = tokens.iter().map(|t| t.text).collect();
+    assert_eq!(round_trip, source);
+}
+
+#[test]
+fn minify() {
+    let source = "[ 1 ,\n  { \"k\" : \"v w\" } ]\n";
+    let minified: String = Tokenizer::new(source).filter(|t| !t.is_trivia()).map(|t| t.text).collect();
+    assert_eq!(minified, r#"[1,{"k":"v w"}]"#);
+}
+
+#[test]
+fn invalid_tokens() {
+    let source = "[01, 'x', +2]";
+    let mut tokenizer = Tokenizer::new(source);
+    let invalid: Vec<&str> = tokenizer.by_ref().filter(|t| t.kind == TokenKind::Invalid).map(|t| t.text).collect();
+    assert_eq!(invalid, vec!["01", "'", "x", "'", "+"]);
+    assert_eq!(tokenizer.errors().len(), 5);
+    assert_eq!(tokenizer.errors()[0].span(), Span::new(1, 3));
+
+    assert!(tokenize(source).is_err());
+    assert_eq!(Tokenizer::new(source).map(|t| t.text).collect::<String>(), source);
+}
+
+#[test]
+fn jsonc_comments() {
+    let source = "[1, // one\n/* two */ 2]";
+    let kinds: Vec<TokenKind> = Tokenizer::jsonc(source).filter(|t| t.is_trivia() && t.kind != TokenKind::Whitespace).map(|t| t.kind).collect();
+    assert_eq!(kinds, vec![TokenKind::LineComment, TokenKind::BlockComment]);
+    assert_eq!(Tokenizer::jsonc(source).map(|t| t.text).collect::<String>(), source);
+    assert!(tokenize(source).is_err());
+}
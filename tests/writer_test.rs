@@ -0,0 +1,194 @@
+#![cfg(feature = "std")]
+extern crate crystalrake;
+use std::io;
+
+use crystalrake::error::WriteErrorKind;
+use crystalrake::json;
+use crystalrake::json::JsonValue;
+use crystalrake::writer::{self, JsonWriter, WriteOptions};
+
+fn write_document(writer: &mut JsonWriter<Vec<u8>>) -> Result<(), crystalrake::error::WriteError> {
+    writer.begin_object()?;
+    writer.key("name")?;
+    writer.value("crystal\"rake")?;
+    writer.key("tags")?;
+    writer.begin_array()?;
+    writer.value(1)?;
+    writer.value(2.5)?;
+    writer.value(true)?;
+    writer.value(None::<&str>)?;
+    writer.begin_object()?;
+    writer.end()?;
+    writer.end()?;
+    writer.key("empty")?;
+    writer.begin_array()?;
+    writer.end()?;
+    writer.key("nested")?;
+    writer.begin_object()?;
+    writer.key("a")?;
+    writer.begin_array()?;
+    writer.begin_array()?;
+    writer.value(Some("x"))?;
+    writer.end()?;
+    writer.end()?;
+    writer.end()?;
+    writer.end()
+}
+
+#[test]
+fn compact_and_pretty() {
+    let mut compact = JsonWriter::new(Vec::new());
+    write_document(&mut compact).unwrap();
+    let compact = String::from_utf8(compact.finish().unwrap()).unwrap();
+    assert_eq!(compact, r#"{"name":"crystal\"rake","tags":[1,2.5,true,null,{}],"empty":[],"nested":{"a":[["x"]]}}"#);
+
+    let mut pretty = JsonWriter::pretty(Vec::new(), "  ");
+    write_document(&mut pretty).unwrap();
+    let pretty = String::from_utf8(pretty.finish().unwrap()).unwrap();
+    let value: JsonValue = compact.parse().unwrap();
+    assert_eq!(pretty, writer::to_string_pretty(&value, "  "));
+    assert_eq!(writer::to_string(&value), compact);
+}
+
+#[test]
+fn top_level_scalars() {
+    let mut writer = JsonWriter::new(Vec::new());
+    writer.value("only").unwrap();
+    assert!(matches!(writer.value(1).unwrap_err().kind(), WriteErrorKind::TrailingValue));
+    assert!(matches!(writer.begin_array().unwrap_err().kind(), WriteErrorKind::TrailingValue));
+    assert_eq!(writer.finish().unwrap(), br#""only""#);
+}
+
+#[test]
+fn invalid_calls() {
+    let kind = |f: fn(&mut JsonWriter<Vec<u8>>) -> Result<(), crystalrake::error::WriteError>| {
+        let mut writer = JsonWriter::new(Vec::new());
+        format!("{:?}", f(&mut writer).unwrap_err().kind())
+    };
+    assert_eq!(kind(|w| { w.begin_object()?; w.value(1) }), "MissingKey");
+    assert_eq!(kind(|w| { w.begin_object()?; w.key("a")?; w.key("b") }), "UnexpectedKey");
+    assert_eq!(kind(|w| { w.begin_array()?; w.key("a") }), "UnexpectedKey");
+    assert_eq!(kind(|w| w.key("a")), "UnexpectedKey");
+    assert_eq!(kind(|w| w.end()), "UnexpectedEnd");
+    assert_eq!(kind(|w| { w.begin_array()?; w.end()?; w.end() }), "UnexpectedEnd");
+    assert_eq!(kind(|w| { w.begin_object()?; w.key("a")?; w.end() }), "MissingValue");
+
+    let mut writer = JsonWriter::new(Vec::new());
+    writer.begin_array().unwrap();
+    let e = writer.finish().unwrap_err();
+    assert!(matches!(e.kind(), WriteErrorKind::Incomplete));
+    assert_eq!(e.to_string(), "writer finished before the value was complete");
+    assert!(matches!(JsonWriter::new(Vec::new()).finish().unwrap_err().kind(), WriteErrorKind::Incomplete));
+}
+
+struct FailingWriter;
+
+impl io::Write for FailingWriter {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn io_errors() {
+    let mut writer = JsonWriter::new(FailingWriter);
+    assert!(matches!(writer.begin_array().unwrap_err().kind(), WriteErrorKind::Io(e) if e.kind() == io::ErrorKind::BrokenPipe));
+}
+
+#[test]
+fn exact_integers() {
+    let write = |value: writer::Scalar| {
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.value(value).unwrap();
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    };
+    assert_eq!(write(9007199254740993u64.into()), "9007199254740993");
+    assert_eq!(write(i64::MAX.into()), "9223372036854775807");
+    assert_eq!(write(i64::MIN.into()), "-9223372036854775808");
+    assert_eq!(write(u64::MAX.into()), "18446744073709551615");
+    assert_eq!(write((-7i8).into()), "-7");
+    assert_eq!(write(1.5f32.into()), "1.5");
+}
+
+#[test]
+fn non_finite_numbers() {
+    for n in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        let mut writer = JsonWriter::new(Vec::new());
+        let e = writer.value(n).unwrap_err();
+        assert!(matches!(e.kind(), WriteErrorKind::NonFiniteNumber));
+        assert_eq!(e.to_string(), "non-finite number");
+        writer.value(1).unwrap();
+        assert_eq!(writer.finish().unwrap(), b"1");
+    }
+    let mut writer = JsonWriter::new(Vec::new());
+    writer.begin_array().unwrap();
+    assert!(matches!(writer.value(f32::NAN).unwrap_err().kind(), WriteErrorKind::NonFiniteNumber));
+}
+
+#[test]
+fn shortest_numbers() {
+    let cases = [
+        (1.0, "1"),
+        (0.1 + 0.2, "0.30000000000000004"),
+        (1e21, "1e+21"),
+        (123456789012345680000.0, "123456789012345680000"),
+        (1e-7, "1e-7"),
+        (0.000001, "0.000001"),
+        (-1.5e-300, "-1.5e-300"),
+        (5e-324, "5e-324"),
+        (f64::MAX, "1.7976931348623157e+308"),
+        (-0.0, "-0"),
+    ];
+    for (n, expected) in cases {
+        assert_eq!(JsonValue::Number(n).to_string(), expected);
+    }
+
+    let mut bits = 0x9e3779b97f4a7c15u64;
+    for _ in 0..10000 {
+        bits ^= bits << 13;
+        bits ^= bits >> 7;
+        bits ^= bits << 17;
+        let n = f64::from_bits(bits);
+        if n.is_finite() {
+            let text = JsonValue::Number(n).to_string();
+            assert_eq!(text.parse::<f64>().unwrap().to_bits(), bits, "{}", text);
+        }
+    }
+}
+
+#[test]
+fn float_fraction() {
+    let value = json!([1.0, 1.5, -0.0, 1e21, f64::NAN]);
+    assert_eq!(writer::to_string(&value), "[1,1.5,-0,1e+21,null]");
+    let options = WriteOptions::new().float_fraction(true);
+    assert_eq!(writer::to_string_with(&value, &options), "[1.0,1.5,-0.0,1e+21,null]");
+    assert_eq!(writer::to_string_pretty_with(&json!([2.0]), " ", &options), "[\n 2.0\n]");
+}
+
+#[test]
+fn escaping() {
+    let value = json!({ "é": "</script><!--a&b-->\u{2028}\u{2029}ü😀\n" });
+    assert_eq!(writer::to_string(&value), "{\"é\":\"</script><!--a&b-->\u{2028}\u{2029}ü😀\\n\"}");
+
+    let ascii = WriteOptions::new().ascii(true);
+    let text = writer::to_string_with(&value, &ascii);
+    assert_eq!(text, r#"{"\u00e9":"</script><!--a&b-->\u2028\u2029\u00fc\ud83d\ude00\n"}"#);
+    assert_eq!(text.parse::<JsonValue>().unwrap(), value);
+
+    let html = WriteOptions::new().html_safe(true);
+    let text = writer::to_string_with(&value, &html);
+    assert_eq!(text, "{\"é\":\"\\u003c/script\\u003e\\u003c!--a\\u0026b--\\u003e\\u2028\\u2029ü😀\\n\"}");
+    assert_eq!(text.parse::<JsonValue>().unwrap(), value);
+
+    let mut writer = JsonWriter::new(Vec::new()).with_options(ascii.html_safe(true).float_fraction(true));
+    writer.begin_array().unwrap();
+    writer.value("<ü>").unwrap();
+    writer.value(2.0).unwrap();
+    writer.value(2).unwrap();
+    writer.end().unwrap();
+    assert_eq!(writer.finish().unwrap(), br#"["\u003c\u00fc\u003e",2.0,2]"#);
+}